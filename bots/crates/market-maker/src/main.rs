@@ -11,6 +11,12 @@ use std::{
 use anyhow::Context;
 use client::{
     print_kv,
+    sender::{
+        ComputeUnitLimit,
+        PriorityFee,
+        SenderConfig,
+        TransactionSender,
+    },
     transactions::{
         CustomRpcClient,
         SendTransactionConfig,
@@ -74,6 +80,7 @@ async fn main() -> anyhow::Result<()> {
             compute_budget: Some(2000000),
             debug_logs: Some(true),
             program_id_filter: HashSet::from([dropset_interface::program::ID]),
+            ..Default::default()
         }),
     );
     let ctx = initialize_context_from_cli(&rpc, &reqwest_client).await?;
@@ -215,6 +222,20 @@ async fn throttled_order_update(
     rpc: &CustomRpcClient,
     throttle_window_ms: u64,
 ) -> anyhow::Result<()> {
+    let mut config = SenderConfig {
+        priority_fee: PriorityFee::Recent {
+            percentile: 75,
+            max: 1_000_000,
+        },
+        compute_unit_limit: ComputeUnitLimit::Simulated { margin_percent: 20 },
+        ..rpc.config.sender_config()
+    };
+    // The updates only contain the market's instructions, so they can load its lookup table.
+    config
+        .lookup_tables
+        .extend(maker_ctx.try_borrow()?.market_ctx.lookup_table.clone());
+    let sender = rpc.sender(config);
+
     loop {
         // Wait until the value has changed. Not equality wise, but a sender posting a new value.
         rx.changed().await?;
@@ -232,8 +253,12 @@ async fn throttled_order_update(
         };
 
         if !instructions.is_empty() {
-            rpc.send_and_confirm_txn(&maker_keypair, &[&maker_keypair], &instructions)
-                .await?;
+            match sender.send(&maker_keypair, &[], &instructions).await {
+                Ok(signature) => print_kv!("Updated orders", signature),
+                // A failed update is retried with fresh instructions on the next task update, so
+                // it shouldn't end the loop.
+                Err(e) => eprintln!("Failed to update orders: {e}"),
+            }
         }
 
         // Sleep for the throttle window in milliseconds before doing work again.
@@ -13,7 +13,7 @@ use dropset_interface::instructions::{
 };
 use price::{
     client_helpers::{
-        decimal_pow10_i16,
+        token_to_atoms_price,
         try_encoded_u32_to_decoded_decimal,
     },
     to_order_info,
@@ -68,10 +68,7 @@ pub fn normalize_non_atoms_price(
     base_decimals: u8,
     quote_decimals: u8,
 ) -> Decimal {
-    decimal_pow10_i16(
-        non_atoms_price,
-        quote_decimals as i16 - base_decimals as i16,
-    )
+    token_to_atoms_price(non_atoms_price, base_decimals, quote_decimals)
 }
 
 /// Returns values from each hashmap whose keys don't exist in the other.
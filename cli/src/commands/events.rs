@@ -0,0 +1,130 @@
+//! `dropset events tail`.
+
+use std::{
+    str::FromStr,
+    time::Duration,
+};
+
+use clap::Subcommand;
+use client::backfill::{
+    market_events,
+    BackfilledEvent,
+    RpcTransactionSource,
+    TransactionSource,
+};
+use serde::Serialize;
+use solana_address::Address;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_sdk::{
+    clock::UnixTimestamp,
+    signature::Signature,
+};
+use transaction_parser::events::dropset_event::SequencedEvent;
+
+use crate::{
+    output::{
+        OutputFormat,
+        Table,
+    },
+    Session,
+};
+
+#[derive(Subcommand)]
+pub enum EventsCommand {
+    /// Prints a market's events as they're emitted, one per line.
+    Tail {
+        market: Address,
+        /// Start from the market's first transaction instead of its latest one.
+        #[arg(long)]
+        from_start: bool,
+        /// How often to poll for new transactions, in seconds.
+        #[arg(long, default_value_t = 2)]
+        interval: u64,
+    },
+}
+
+#[derive(Serialize)]
+struct EventLine<'a> {
+    slot: u64,
+    signature: String,
+    block_time: Option<UnixTimestamp>,
+    #[serde(flatten)]
+    event: &'a SequencedEvent,
+}
+
+impl EventsCommand {
+    pub async fn run(self, session: Session) -> anyhow::Result<()> {
+        let Self::Tail {
+            market,
+            from_start,
+            interval,
+        } = self;
+        let source = RpcTransactionSource::new(&session.rpc.client);
+
+        let mut last = match from_start {
+            true => None,
+            false => latest_signature(&session, &market).await?,
+        };
+
+        if matches!(session.output, OutputFormat::Table) {
+            print!("{}", Table::new(["SLOT", "SIGNATURE", "SEQ", "EVENT"]));
+        }
+
+        loop {
+            for signature in source.signatures(&market, last).await? {
+                let encoded = source.transaction(&signature).await?;
+                for event in market_events(encoded, &market, 0)? {
+                    print_event(session.output, &event)?;
+                }
+                last = Some(signature);
+            }
+
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+        }
+    }
+}
+
+async fn latest_signature(
+    session: &Session,
+    market: &Address,
+) -> anyhow::Result<Option<Signature>> {
+    let latest = session
+        .rpc
+        .client
+        .get_signatures_for_address_with_config(
+            market,
+            GetConfirmedSignaturesForAddress2Config {
+                limit: Some(1),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    latest
+        .first()
+        .map(|status| Signature::from_str(&status.signature))
+        .transpose()
+        .map_err(Into::into)
+}
+
+/// Prints an event as a single line: a JSON object, or a row without the table header since the
+/// column widths aren't known up front.
+fn print_event(output: OutputFormat, event: &BackfilledEvent) -> anyhow::Result<()> {
+    match output {
+        OutputFormat::Table => println!(
+            "{}  {}  {}  {:?}",
+            event.slot, event.signature, event.event.seq, event.event.event
+        ),
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string(&EventLine {
+                slot: event.slot,
+                signature: event.signature.to_string(),
+                block_time: event.block_time,
+                event: &event.event,
+            })?
+        ),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,244 @@
+//! `dropset market create|list|show|expand`.
+
+use clap::Subcommand;
+use client::{
+    context::market::MarketContext,
+    registry::{
+        MarketEntry,
+        MarketRegistry,
+    },
+};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use solana_address::Address;
+use solana_sdk::signer::Signer;
+use transaction_parser::views::OrderView;
+
+use crate::{
+    output::Table,
+    Session,
+};
+
+#[derive(Subcommand)]
+pub enum MarketCommand {
+    /// Registers the market for a base and quote mint.
+    Create {
+        #[arg(long)]
+        base_mint: Address,
+        #[arg(long)]
+        quote_mint: Address,
+        /// The number of sectors to preallocate. More are added as seats and orders need them.
+        #[arg(long, default_value_t = 10)]
+        sectors: u16,
+        /// Also create an address lookup table holding the market's static accounts.
+        #[arg(long)]
+        lookup_table: bool,
+    },
+    /// Lists every market on the program.
+    List {
+        /// Only list markets trading this mint as the base or quote token.
+        #[arg(long)]
+        mint: Option<Address>,
+    },
+    /// Shows a market's header and book.
+    Show { market: Address },
+    /// Expands the market account to the current layout version, paying any additional rent.
+    Expand { market: Address },
+}
+
+#[derive(Serialize)]
+struct CreatedMarket {
+    market: String,
+    base_mint: String,
+    quote_mint: String,
+    lookup_table: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ListedMarket {
+    market: String,
+    base_mint: String,
+    quote_mint: String,
+    base_decimals: u8,
+    quote_decimals: u8,
+    num_bids: u32,
+    num_asks: u32,
+}
+
+impl From<&MarketEntry> for ListedMarket {
+    fn from(entry: &MarketEntry) -> Self {
+        Self {
+            market: entry.market.to_string(),
+            base_mint: entry.base.address.to_string(),
+            quote_mint: entry.quote.address.to_string(),
+            base_decimals: entry.base.decimals,
+            quote_decimals: entry.quote.decimals,
+            num_bids: entry.num_bids,
+            num_asks: entry.num_asks,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct MarketSummary {
+    market: String,
+    base_mint: String,
+    quote_mint: String,
+    base_decimals: u8,
+    quote_decimals: u8,
+    layout_version: u8,
+    num_seats: u32,
+    bids: Vec<Level>,
+    asks: Vec<Level>,
+}
+
+#[derive(Serialize)]
+struct Level {
+    price: Decimal,
+    base_remaining: u64,
+    quote_remaining: u64,
+    seat: u32,
+}
+
+impl MarketCommand {
+    pub async fn run(self, session: Session) -> anyhow::Result<()> {
+        match self {
+            Self::Create {
+                base_mint,
+                quote_mint,
+                sectors,
+                lookup_table,
+            } => {
+                let payer = session.keypair()?;
+                let mut market = MarketContext::new_from_token_pair(
+                    &session.rpc,
+                    base_mint,
+                    quote_mint,
+                    None,
+                    None,
+                )
+                .await?;
+                market
+                    .register_market(payer.pubkey(), sectors)
+                    .send_single_signer(&session.rpc, &payer)
+                    .await?;
+                let lookup_table = match lookup_table {
+                    true => Some(market.create_lookup_table(&session.rpc, &payer).await?),
+                    false => None,
+                };
+
+                let created = CreatedMarket {
+                    market: market.market.to_string(),
+                    base_mint: base_mint.to_string(),
+                    quote_mint: quote_mint.to_string(),
+                    lookup_table: lookup_table.map(|table| table.to_string()),
+                };
+                session.output.print(&created, |created| {
+                    Table::key_values([
+                        ("market", created.market.clone()),
+                        ("base_mint", created.base_mint.clone()),
+                        ("quote_mint", created.quote_mint.clone()),
+                        (
+                            "lookup_table",
+                            created.lookup_table.clone().unwrap_or("-".into()),
+                        ),
+                    ])
+                })
+            }
+            Self::List { mint } => {
+                let registry = MarketRegistry::load(&session.rpc).await?;
+                let markets: Vec<ListedMarket> = match mint {
+                    Some(mint) => registry.with_mint(&mint).map(ListedMarket::from).collect(),
+                    None => registry.markets().iter().map(ListedMarket::from).collect(),
+                };
+
+                session.output.print(&markets, |markets| {
+                    let mut table =
+                        Table::new(["MARKET", "BASE", "QUOTE", "DECIMALS", "BIDS", "ASKS"]);
+                    for market in markets {
+                        table.row([
+                            market.market.clone(),
+                            market.base_mint.clone(),
+                            market.quote_mint.clone(),
+                            format!("{}/{}", market.base_decimals, market.quote_decimals),
+                            market.num_bids.to_string(),
+                            market.num_asks.to_string(),
+                        ]);
+                    }
+                    table
+                })
+            }
+            Self::Show { market } => {
+                let market = session.market(market).await?;
+                let view = market.view_market(&session.rpc).await?;
+                let levels = |orders: &[OrderView]| {
+                    orders
+                        .iter()
+                        .map(|order| {
+                            Ok(Level {
+                                price: market.to_decimal_price(order.encoded_price)?,
+                                base_remaining: order.base_remaining,
+                                quote_remaining: order.quote_remaining,
+                                seat: order.user_seat,
+                            })
+                        })
+                        .collect::<anyhow::Result<Vec<_>>>()
+                };
+
+                let summary = MarketSummary {
+                    market: market.market.to_string(),
+                    base_mint: market.base.mint_address.to_string(),
+                    quote_mint: market.quote.mint_address.to_string(),
+                    base_decimals: market.base.mint_decimals,
+                    quote_decimals: market.quote.mint_decimals,
+                    layout_version: view.header.layout_version,
+                    num_seats: view.header.num_seats,
+                    bids: levels(&view.bids)?,
+                    asks: levels(&view.asks)?,
+                };
+                session.output.print_tables(&summary, |summary| {
+                    let header = Table::key_values([
+                        ("market", summary.market.clone()),
+                        ("base_mint", summary.base_mint.clone()),
+                        ("quote_mint", summary.quote_mint.clone()),
+                        ("base_decimals", summary.base_decimals.to_string()),
+                        ("quote_decimals", summary.quote_decimals.to_string()),
+                        ("layout_version", summary.layout_version.to_string()),
+                        ("seats", summary.num_seats.to_string()),
+                    ]);
+
+                    // Asks are printed highest first so the best prices meet in the middle.
+                    let mut book = Table::new(["SIDE", "PRICE", "BASE", "QUOTE", "SEAT"]);
+                    let asks = summary.asks.iter().rev().map(|level| ("ask", level));
+                    let bids = summary.bids.iter().map(|level| ("bid", level));
+                    for (side, level) in asks.chain(bids) {
+                        book.row([
+                            side.to_string(),
+                            level.price.to_string(),
+                            level.base_remaining.to_string(),
+                            level.quote_remaining.to_string(),
+                            level.seat.to_string(),
+                        ]);
+                    }
+
+                    vec![header, book]
+                })
+            }
+            Self::Expand { market } => {
+                let payer = session.keypair()?;
+                let market = session.market(market).await?;
+                market
+                    .migrate_market(payer.pubkey())
+                    .send_single_signer(&session.rpc, &payer)
+                    .await?;
+                let view = market.view_market(&session.rpc).await?;
+
+                session
+                    .output
+                    .print(&view.header.layout_version, |version| {
+                        Table::key_values([("layout_version", version.to_string())])
+                    })
+            }
+        }
+    }
+}
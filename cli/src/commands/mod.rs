@@ -0,0 +1,52 @@
+//! The `dropset` CLI's subcommands, one module per command group.
+
+pub mod events;
+pub mod market;
+pub mod order;
+pub mod portfolio;
+pub mod seat;
+pub mod trade;
+pub mod tx;
+
+use client::context::market::{
+    BookSide,
+    Denomination,
+};
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum Side {
+    Bid,
+    Ask,
+}
+
+impl From<Side> for BookSide {
+    fn from(side: Side) -> Self {
+        match side {
+            Side::Bid => BookSide::Bid,
+            Side::Ask => BookSide::Ask,
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum Token {
+    Base,
+    Quote,
+}
+
+impl From<Token> for Denomination {
+    fn from(token: Token) -> Self {
+        match token {
+            Token::Base => Denomination::Base,
+            Token::Quote => Denomination::Quote,
+        }
+    }
+}
+
+/// The lowercase name of a book side, as printed in tables and JSON.
+pub fn side_name(side: BookSide) -> &'static str {
+    match side {
+        BookSide::Bid => "bid",
+        BookSide::Ask => "ask",
+    }
+}
@@ -0,0 +1,146 @@
+//! `dropset order post|cancel|cancel-all|list`.
+
+use clap::Subcommand;
+use client::context::market::BookSide;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use solana_address::Address;
+
+use crate::{
+    commands::{
+        side_name,
+        Side,
+    },
+    output::Table,
+    Session,
+};
+
+#[derive(Subcommand)]
+pub enum OrderCommand {
+    /// Posts a limit order.
+    Post {
+        market: Address,
+        #[arg(value_enum)]
+        side: Side,
+        /// The price in quote tokens per base token.
+        price: Decimal,
+        /// The size in base atoms.
+        size: u64,
+    },
+    /// Cancels your order at a price.
+    Cancel {
+        market: Address,
+        #[arg(value_enum)]
+        side: Side,
+        /// The price in quote tokens per base token.
+        price: Decimal,
+    },
+    /// Cancels all of your orders.
+    CancelAll { market: Address },
+    /// Lists your resting orders.
+    List { market: Address },
+}
+
+/// An order and its remaining (or, for a newly posted order, total) amounts.
+#[derive(Serialize)]
+struct OrderRow {
+    side: &'static str,
+    price: Decimal,
+    base_atoms: u64,
+    quote_atoms: u64,
+}
+
+impl OrderRow {
+    fn new(side: BookSide, price: Decimal, base_atoms: u64, quote_atoms: u64) -> Self {
+        Self {
+            side: side_name(side),
+            price,
+            base_atoms,
+            quote_atoms,
+        }
+    }
+}
+
+fn orders_table(orders: &[OrderRow]) -> Table {
+    let mut table = Table::new(["SIDE", "PRICE", "BASE", "QUOTE"]);
+    for order in orders {
+        table.row([
+            order.side.to_string(),
+            order.price.to_string(),
+            order.base_atoms.to_string(),
+            order.quote_atoms.to_string(),
+        ]);
+    }
+    table
+}
+
+impl OrderCommand {
+    pub async fn run(self, session: Session) -> anyhow::Result<()> {
+        let output = session.output;
+        let orders = match self {
+            Self::Post {
+                market,
+                side,
+                price,
+                size,
+            } => {
+                let mut client = session.dropset_client(market).await?;
+                let placed = client.place_limit(side.into(), price, size).await?;
+                vec![OrderRow::new(
+                    placed.side,
+                    placed.price,
+                    placed.base_atoms,
+                    placed.quote_atoms,
+                )]
+            }
+            Self::Cancel {
+                market,
+                side,
+                price,
+            } => {
+                let mut client = session.dropset_client(market).await?;
+                let canceled = client.cancel(side.into(), price).await?;
+                vec![OrderRow::new(
+                    canceled.side,
+                    canceled.price,
+                    canceled.base_remaining,
+                    canceled.quote_remaining,
+                )]
+            }
+            Self::CancelAll { market } => {
+                let mut client = session.dropset_client(market).await?;
+                let mut canceled_orders = vec![];
+                for order in client.open_orders().await? {
+                    let canceled = client
+                        .cancel_encoded(order.side, order.encoded_price)
+                        .await?;
+                    canceled_orders.push(OrderRow::new(
+                        canceled.side,
+                        canceled.price,
+                        canceled.base_remaining,
+                        canceled.quote_remaining,
+                    ));
+                }
+                canceled_orders
+            }
+            Self::List { market } => {
+                let mut client = session.dropset_client(market).await?;
+                client
+                    .open_orders()
+                    .await?
+                    .into_iter()
+                    .map(|order| {
+                        OrderRow::new(
+                            order.side,
+                            order.price,
+                            order.base_remaining,
+                            order.quote_remaining,
+                        )
+                    })
+                    .collect()
+            }
+        };
+
+        output.print(&orders, |orders| orders_table(orders))
+    }
+}
@@ -0,0 +1,185 @@
+//! `dropset portfolio`.
+
+use clap::Args;
+use client::{
+    portfolio::Portfolio,
+    registry::MarketRegistry,
+};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use solana_address::Address;
+use solana_sdk::signer::Signer;
+
+use crate::{
+    commands::side_name,
+    output::Table,
+    Session,
+};
+
+#[derive(Args)]
+pub struct PortfolioCommand {
+    /// The user whose seats are listed. Defaults to the configured keypair.
+    #[arg(long)]
+    user: Option<Address>,
+    /// The mint balances are valued in, using mid prices of markets pairing it with each mint.
+    #[arg(long)]
+    quote_mint: Address,
+}
+
+#[derive(Serialize)]
+struct PortfolioSummary {
+    user: String,
+    quote_mint: String,
+    total_value: Decimal,
+    positions: Vec<PositionRow>,
+    mints: Vec<MintRow>,
+    invalid_orders: Vec<InvalidOrderRow>,
+}
+
+#[derive(Serialize)]
+struct PositionRow {
+    market: String,
+    base_free: u64,
+    base_locked: u64,
+    quote_free: u64,
+    quote_locked: u64,
+    mid_price: Option<Decimal>,
+    orders: Vec<OrderRow>,
+}
+
+#[derive(Serialize)]
+struct OrderRow {
+    side: &'static str,
+    price: Decimal,
+    base_remaining: u64,
+    quote_remaining: u64,
+}
+
+#[derive(Serialize)]
+struct MintRow {
+    mint: String,
+    free: u64,
+    locked: u64,
+    value: Option<Decimal>,
+}
+
+#[derive(Serialize)]
+struct InvalidOrderRow {
+    market: String,
+    order_index: u32,
+    encoded_price: u32,
+}
+
+impl PortfolioCommand {
+    pub async fn run(self, session: Session) -> anyhow::Result<()> {
+        let user = match self.user {
+            Some(user) => user,
+            None => session.keypair()?.pubkey(),
+        };
+        let registry = MarketRegistry::load(&session.rpc).await?;
+        let portfolio = Portfolio::load(&session.rpc, &registry, &user, &self.quote_mint).await?;
+
+        let summary = PortfolioSummary {
+            user: user.to_string(),
+            quote_mint: self.quote_mint.to_string(),
+            total_value: portfolio.total_value(),
+            positions: portfolio
+                .positions
+                .iter()
+                .map(|position| PositionRow {
+                    market: position.market.market.to_string(),
+                    base_free: position.base.free,
+                    base_locked: position.base.locked,
+                    quote_free: position.quote.free,
+                    quote_locked: position.quote.locked,
+                    mid_price: position.mid_price,
+                    orders: position
+                        .orders
+                        .iter()
+                        .map(|order| OrderRow {
+                            side: side_name(order.side),
+                            price: order.price,
+                            base_remaining: order.base_remaining,
+                            quote_remaining: order.quote_remaining,
+                        })
+                        .collect(),
+                })
+                .collect(),
+            mints: portfolio
+                .mints
+                .iter()
+                .map(|mint| MintRow {
+                    mint: mint.mint.address.to_string(),
+                    free: mint.balance.free,
+                    locked: mint.balance.locked,
+                    value: mint.value,
+                })
+                .collect(),
+            invalid_orders: portfolio
+                .invalid_orders
+                .iter()
+                .map(|order| InvalidOrderRow {
+                    market: order.market.to_string(),
+                    order_index: order.order_index,
+                    encoded_price: order.encoded_price,
+                })
+                .collect(),
+        };
+
+        let optional = |value: Option<Decimal>| value.map_or("-".into(), |v| v.to_string());
+        session.output.print_tables(&summary, |summary| {
+            let header = Table::key_values([
+                ("user", summary.user.clone()),
+                ("quote_mint", summary.quote_mint.clone()),
+                ("total_value", summary.total_value.to_string()),
+            ]);
+
+            let mut positions = Table::new([
+                "MARKET",
+                "BASE_FREE",
+                "BASE_LOCKED",
+                "QUOTE_FREE",
+                "QUOTE_LOCKED",
+                "ORDERS",
+                "MID",
+            ]);
+            for position in summary.positions.iter() {
+                positions.row([
+                    position.market.clone(),
+                    position.base_free.to_string(),
+                    position.base_locked.to_string(),
+                    position.quote_free.to_string(),
+                    position.quote_locked.to_string(),
+                    position.orders.len().to_string(),
+                    optional(position.mid_price),
+                ]);
+            }
+
+            let mut mints = Table::new(["MINT", "FREE", "LOCKED", "VALUE"]);
+            for mint in summary.mints.iter() {
+                mints.row([
+                    mint.mint.clone(),
+                    mint.free.to_string(),
+                    mint.locked.to_string(),
+                    optional(mint.value),
+                ]);
+            }
+
+            let mut tables = vec![header, positions, mints];
+            // Markets with orders that can't be priced are left unvalued, so list those orders.
+            if !summary.invalid_orders.is_empty() {
+                let mut invalid = Table::new(["MARKET", "INVALID_ORDER", "ENCODED_PRICE"]);
+                for order in summary.invalid_orders.iter() {
+                    invalid.row([
+                        order.market.clone(),
+                        order.order_index.to_string(),
+                        order.encoded_price.to_string(),
+                    ]);
+                }
+                tables.push(invalid);
+            }
+
+            tables
+        })
+    }
+}
@@ -0,0 +1,82 @@
+//! `dropset seat deposit|withdraw|close`.
+
+use clap::Subcommand;
+use serde::Serialize;
+use solana_address::Address;
+
+use crate::{
+    commands::Token,
+    output::Table,
+    Session,
+};
+
+#[derive(Subcommand)]
+pub enum SeatCommand {
+    /// Deposits tokens into your seat, creating it if needed.
+    Deposit {
+        market: Address,
+        #[arg(value_enum)]
+        token: Token,
+        /// The amount in atoms.
+        amount: u64,
+    },
+    /// Withdraws tokens from your seat.
+    Withdraw {
+        market: Address,
+        #[arg(value_enum)]
+        token: Token,
+        /// The amount in atoms.
+        amount: u64,
+    },
+    /// Closes your seat, withdrawing its remaining balances.
+    Close { market: Address },
+}
+
+#[derive(Serialize)]
+struct SeatBalances {
+    base_available: u64,
+    quote_available: u64,
+}
+
+impl SeatCommand {
+    pub async fn run(self, session: Session) -> anyhow::Result<()> {
+        let output = session.output;
+        let (market, token, amount, is_deposit) = match self {
+            Self::Deposit {
+                market,
+                token,
+                amount,
+            } => (market, token, amount, true),
+            Self::Withdraw {
+                market,
+                token,
+                amount,
+            } => (market, token, amount, false),
+            Self::Close { market } => {
+                let mut client = session.dropset_client(market).await?;
+                client.close_seat().await?;
+                return output.print(&client.trader().to_string(), |trader| {
+                    Table::key_values([("closed_seat", trader.clone())])
+                });
+            }
+        };
+
+        let mut client = session.dropset_client(market).await?;
+        match is_deposit {
+            true => client.deposit(token.into(), amount).await?,
+            false => client.withdraw(token.into(), amount).await?,
+        }
+
+        let balances = client.balances().await?;
+        let balances = SeatBalances {
+            base_available: balances.base_available,
+            quote_available: balances.quote_available,
+        };
+        output.print(&balances, |balances| {
+            Table::key_values([
+                ("base_available", balances.base_available.to_string()),
+                ("quote_available", balances.quote_available.to_string()),
+            ])
+        })
+    }
+}
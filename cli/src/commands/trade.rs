@@ -0,0 +1,74 @@
+//! `dropset trade buy|sell`.
+
+use clap::Subcommand;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use solana_address::Address;
+
+use crate::{
+    output::Table,
+    Session,
+};
+
+#[derive(Subcommand)]
+pub enum TradeCommand {
+    /// Buys base with a market order against the asks.
+    Buy {
+        market: Address,
+        /// The amount of quote atoms to spend.
+        amount: u64,
+        /// The highest ask price, in quote tokens per base token, the order may fill against.
+        #[arg(long)]
+        max_price: Decimal,
+    },
+    /// Sells base with a market order against the bids.
+    Sell {
+        market: Address,
+        /// The amount of base atoms to sell.
+        amount: u64,
+        /// The lowest bid price, in quote tokens per base token, the order may fill against.
+        #[arg(long)]
+        min_price: Decimal,
+    },
+}
+
+#[derive(Serialize)]
+struct Fill {
+    base_filled: u64,
+    quote_filled: u64,
+}
+
+impl TradeCommand {
+    pub async fn run(self, session: Session) -> anyhow::Result<()> {
+        let output = session.output;
+        let fill = match self {
+            Self::Buy {
+                market,
+                amount,
+                max_price,
+            } => {
+                let mut client = session.dropset_client(market).await?;
+                client.market_buy(amount, max_price).await?
+            }
+            Self::Sell {
+                market,
+                amount,
+                min_price,
+            } => {
+                let mut client = session.dropset_client(market).await?;
+                client.market_sell(amount, min_price).await?
+            }
+        };
+
+        let fill = Fill {
+            base_filled: fill.base_filled,
+            quote_filled: fill.quote_filled,
+        };
+        output.print(&fill, |fill| {
+            Table::key_values([
+                ("base_filled", fill.base_filled.to_string()),
+                ("quote_filled", fill.quote_filled.to_string()),
+            ])
+        })
+    }
+}
@@ -0,0 +1,88 @@
+//! `dropset tx decode`.
+
+use std::collections::HashSet;
+
+use clap::Subcommand;
+use client::{
+    backfill::{
+        RpcTransactionSource,
+        TransactionSource,
+    },
+    pretty::transaction::PrettyTransaction,
+};
+use serde::Serialize;
+use solana_sdk::signature::Signature;
+use transaction_parser::{
+    client_rpc::{
+        parse_transaction,
+        ParsedTransaction,
+    },
+    events::dropset_event::SequencedEvent,
+    ParseDropsetEvents,
+};
+
+use crate::{
+    output::OutputFormat,
+    Session,
+};
+
+#[derive(Subcommand)]
+pub enum TxCommand {
+    /// Decodes a transaction's instructions, `dropset` events, and `dropset` error.
+    Decode { signature: Signature },
+}
+
+#[derive(Serialize)]
+struct DecodedTransaction<'a> {
+    transaction: &'a ParsedTransaction,
+    dropset_error: Option<String>,
+    events: Vec<SequencedEvent>,
+}
+
+impl TxCommand {
+    pub async fn run(self, session: Session) -> anyhow::Result<()> {
+        let Self::Decode { signature } = self;
+        let encoded = RpcTransactionSource::new(&session.rpc.client)
+            .transaction(&signature)
+            .await?;
+        let transaction = parse_transaction(encoded)?;
+
+        match session.output {
+            OutputFormat::Table => {
+                print!(
+                    "{}",
+                    PrettyTransaction {
+                        signature,
+                        sender: transaction.fee_payer,
+                        indent_size: 2,
+                        transaction: &transaction,
+                        instruction_filter: &HashSet::new(),
+                    }
+                );
+            }
+            OutputFormat::Json => {
+                let events = transaction
+                    .instructions
+                    .iter()
+                    .flat_map(|outer| outer.inner_instructions.iter())
+                    .map(|inner| {
+                        inner
+                            .parse_sequenced_events()
+                            .map_err(|e| anyhow::anyhow!("Failed to parse events: {e:?}"))
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                let decoded = DecodedTransaction {
+                    transaction: &transaction,
+                    dropset_error: transaction.dropset_error().map(|e| e.to_string()),
+                    events,
+                };
+                println!("{}", serde_json::to_string_pretty(&decoded)?);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+//! The `dropset` CLI's config file and how it combines with command-line overrides.
+
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use anyhow::Context;
+use serde::Deserialize;
+use solana_sdk::signer::keypair::{
+    read_keypair_file,
+    Keypair,
+};
+
+use crate::output::OutputFormat;
+
+const DEFAULT_RPC_URL: &str = "http://localhost:8899";
+
+/// The config file, e.g.:
+///
+/// ```toml
+/// rpc_url = "https://api.devnet.solana.com"
+/// keypair = "~/.config/solana/id.json"
+/// output = "json"
+/// ```
+///
+/// Every field is optional and can be overridden on the command line.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub rpc_url: Option<String>,
+    pub keypair: Option<PathBuf>,
+    pub output: Option<OutputFormat>,
+}
+
+impl Config {
+    /// Loads the config at `path`, or at `~/.config/dropset/config.toml` if no path is passed. A
+    /// missing default config file is treated as an empty config.
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match home_dir() {
+                Some(home) => (home.join(".config/dropset/config.toml"), false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        if !required && !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    pub fn rpc_url(&self) -> &str {
+        self.rpc_url.as_deref().unwrap_or(DEFAULT_RPC_URL)
+    }
+
+    /// Reads the configured keypair, defaulting to the Solana CLI's default keypair.
+    pub fn keypair(&self) -> anyhow::Result<Keypair> {
+        let path = match &self.keypair {
+            Some(path) => expand_home(path),
+            None => home_dir()
+                .context("No keypair configured and no home directory to find the default in")?
+                .join(".config/solana/id.json"),
+        };
+
+        read_keypair_file(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read keypair {}: {e}", path.display()))
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(PathBuf::from)
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
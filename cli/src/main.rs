@@ -0,0 +1,163 @@
+//! See [`main`].
+
+mod commands;
+mod config;
+mod output;
+
+use std::path::PathBuf;
+
+use clap::{
+    Parser,
+    Subcommand,
+};
+use client::{
+    context::market::MarketContext,
+    dropset_client::DropsetClient,
+    transactions::{
+        CustomRpcClient,
+        SendTransactionConfig,
+    },
+};
+use solana_address::Address;
+use solana_sdk::signer::keypair::Keypair;
+
+use crate::{
+    commands::{
+        events::EventsCommand,
+        market::MarketCommand,
+        order::OrderCommand,
+        portfolio::PortfolioCommand,
+        seat::SeatCommand,
+        trade::TradeCommand,
+        tx::TxCommand,
+    },
+    config::Config,
+    output::OutputFormat,
+};
+
+#[derive(Parser)]
+#[command(
+    name = "dropset",
+    about = "Create, inspect, and trade on dropset markets"
+)]
+struct CliArgs {
+    /// The config file to read the RPC URL, keypair, and output format from. Defaults to
+    /// `~/.config/dropset/config.toml`.
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// The RPC URL, overriding the config file.
+    #[arg(long, short = 'u', global = true)]
+    url: Option<String>,
+
+    /// The keypair file that signs and pays for transactions, overriding the config file.
+    #[arg(long, short = 'k', global = true)]
+    keypair: Option<PathBuf>,
+
+    /// The output format, overriding the config file.
+    #[arg(long, short = 'o', global = true, value_enum)]
+    output: Option<OutputFormat>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create, inspect, and migrate markets.
+    #[command(subcommand)]
+    Market(MarketCommand),
+    /// Deposit to, withdraw from, and close a seat.
+    #[command(subcommand)]
+    Seat(SeatCommand),
+    /// Post, cancel, and list resting orders.
+    #[command(subcommand)]
+    Order(OrderCommand),
+    /// Send market orders.
+    #[command(subcommand)]
+    Trade(TradeCommand),
+    /// List a user's seats, balances, and orders across every market.
+    Portfolio(PortfolioCommand),
+    /// Follow a market's events.
+    #[command(subcommand)]
+    Events(EventsCommand),
+    /// Inspect transactions.
+    #[command(subcommand)]
+    Tx(TxCommand),
+}
+
+/// The RPC client, config, and output format every command runs with.
+pub struct Session {
+    pub rpc: CustomRpcClient,
+    pub config: Config,
+    pub output: OutputFormat,
+}
+
+impl Session {
+    pub fn keypair(&self) -> anyhow::Result<Keypair> {
+        self.config.keypair()
+    }
+
+    pub async fn market(&self, market: Address) -> anyhow::Result<MarketContext> {
+        MarketContext::new_from_market(&self.rpc, market).await
+    }
+
+    /// Builds a [`DropsetClient`] trading on `market` with the configured keypair.
+    pub async fn dropset_client(self, market: Address) -> anyhow::Result<DropsetClient> {
+        let trader = self.keypair()?;
+        let market = self.market(market).await?;
+
+        Ok(DropsetClient::new(self.rpc, market, trader))
+    }
+}
+
+/// A command-line tool for operating `dropset` markets.
+///
+/// Settings are read from `~/.config/dropset/config.toml` (or `--config`), e.g.:
+///
+/// ```toml
+/// rpc_url = "http://localhost:8899"
+/// keypair = "~/.config/solana/id.json"
+/// output = "table"
+/// ```
+///
+/// and can be overridden per invocation with `--url`, `--keypair`, and `--output`.
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let CliArgs {
+        config,
+        url,
+        keypair,
+        output,
+        command,
+    } = CliArgs::parse();
+
+    let mut config = Config::load(config.as_deref())?;
+    config.rpc_url = url.or(config.rpc_url);
+    config.keypair = keypair.or(config.keypair);
+    let output = output.or(config.output).unwrap_or_default();
+
+    let rpc = CustomRpcClient::new_from_url(
+        config.rpc_url(),
+        SendTransactionConfig {
+            // The pretty transaction logs would interleave with the command's own output.
+            debug_logs: Some(false),
+            ..Default::default()
+        },
+    );
+    let session = Session {
+        rpc,
+        config,
+        output,
+    };
+
+    match command {
+        Command::Market(command) => command.run(session).await,
+        Command::Seat(command) => command.run(session).await,
+        Command::Order(command) => command.run(session).await,
+        Command::Trade(command) => command.run(session).await,
+        Command::Portfolio(command) => command.run(session).await,
+        Command::Events(command) => command.run(session).await,
+        Command::Tx(command) => command.run(session).await,
+    }
+}
@@ -0,0 +1,122 @@
+//! Prints command results as aligned tables or JSON.
+
+use std::fmt::Display;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+impl OutputFormat {
+    /// Prints `value` as pretty JSON, or as the table built from it by `table`.
+    pub fn print<T: Serialize>(
+        self,
+        value: &T,
+        table: impl FnOnce(&T) -> Table,
+    ) -> anyhow::Result<()> {
+        self.print_tables(value, |value| vec![table(value)])
+    }
+
+    /// Like [`OutputFormat::print`], but prints several tables separated by blank lines.
+    pub fn print_tables<T: Serialize>(
+        self,
+        value: &T,
+        tables: impl FnOnce(&T) -> Vec<Table>,
+    ) -> anyhow::Result<()> {
+        match self {
+            Self::Table => {
+                let tables = tables(value)
+                    .iter()
+                    .map(Table::to_string)
+                    .collect::<Vec<_>>();
+                print!("{}", tables.join("\n"));
+            }
+            Self::Json => println!("{}", serde_json::to_string_pretty(value)?),
+        }
+
+        Ok(())
+    }
+}
+
+/// A table with left-aligned, space-separated columns sized to their widest cell.
+pub struct Table {
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: impl IntoIterator<Item = &'static str>) -> Self {
+        Self {
+            headers: headers.into_iter().collect(),
+            rows: vec![],
+        }
+    }
+
+    /// A two column table of field names and values.
+    pub fn key_values<'a>(rows: impl IntoIterator<Item = (&'a str, String)>) -> Self {
+        let mut table = Self::new(["FIELD", "VALUE"]);
+        for (key, value) in rows {
+            table.row([key.to_string(), value]);
+        }
+        table
+    }
+
+    pub fn row<T: Display>(&mut self, cells: impl IntoIterator<Item = T>) {
+        self.rows
+            .push(cells.into_iter().map(|cell| cell.to_string()).collect());
+    }
+}
+
+impl Display for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let widths = (0..self.headers.len())
+            .map(|column| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| cell.chars().count())
+                    .chain([self.headers[column].len()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+
+        let mut write_row = |cells: &mut dyn Iterator<Item = &str>| -> std::fmt::Result {
+            let line = cells
+                .zip(widths.iter())
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(f, "{}", line.trim_end())
+        };
+
+        write_row(&mut self.headers.iter().copied())?;
+        for row in self.rows.iter() {
+            write_row(&mut row.iter().map(String::as_str))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligns_columns() {
+        let mut table = Table::new(["SIDE", "PRICE"]);
+        table.row(["bid", "1.25"]);
+        table.row(["ask", "10.5"]);
+
+        assert_eq!(table.to_string(), "SIDE  PRICE\nbid   1.25\nask   10.5\n");
+    }
+}
@@ -25,6 +25,7 @@ async fn main() -> anyhow::Result<()> {
             compute_budget: None,
             debug_logs: Some(true),
             program_id_filter: HashSet::from([dropset_interface::program::ID]),
+            ..Default::default()
         }),
     );
 
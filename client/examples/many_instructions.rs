@@ -28,6 +28,7 @@ async fn main() -> anyhow::Result<()> {
             compute_budget: Some(2000000),
             debug_logs: Some(true),
             program_id_filter: HashSet::from([dropset_interface::program::ID]),
+            ..Default::default()
         }),
     );
     // Create the collection of traders out of order so that the order must change when they're
@@ -46,8 +47,9 @@ async fn main() -> anyhow::Result<()> {
         .iter()
         .map(|pk| -> Instruction { e2e.market.create_seat(pk.address()).into() })
         .collect();
-    e2e.rpc
+    e2e.market
         .send_and_confirm_txn(
+            &e2e.rpc,
             test_accounts::default_payer(),
             &traders.iter().map(|tr| tr.keypair).collect_vec(),
             &seat_creations,
@@ -89,12 +91,22 @@ async fn main() -> anyhow::Result<()> {
         .unzip();
 
     let trader_keypairs = &traders.into_iter().map(|tr| tr.keypair).collect_vec();
-    e2e.rpc
-        .send_and_confirm_txn(test_accounts::default_payer(), trader_keypairs, &deposits)
+    e2e.market
+        .send_and_confirm_txn(
+            &e2e.rpc,
+            test_accounts::default_payer(),
+            trader_keypairs,
+            &deposits,
+        )
         .await?;
 
-    e2e.rpc
-        .send_and_confirm_txn(test_accounts::default_payer(), trader_keypairs, &withdraws)
+    e2e.market
+        .send_and_confirm_txn(
+            &e2e.rpc,
+            test_accounts::default_payer(),
+            trader_keypairs,
+            &withdraws,
+        )
         .await?;
 
     let expected_base = base_amounts
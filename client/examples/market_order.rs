@@ -11,7 +11,6 @@ use client::{
     transactions::ParsedTransactionWithEvents,
 };
 use dropset_interface::{
-    events::MarketOrderEventInstructionData,
     instructions::{
         MarketOrderInstructionData,
         PostOrderInstructionData,
@@ -31,7 +30,10 @@ use solana_sdk::{
     signer::Signer,
 };
 use transaction_parser::{
-    events::dropset_event::DropsetEvent,
+    events::{
+        display_types::DisplayMarketOrderData,
+        dropset_event::DropsetEvent,
+    },
     views::{
         MarketSeatView,
         OrderView,
@@ -392,19 +394,18 @@ async fn main() -> anyhow::Result<()> {
 
         // Ensure that there's a single market order event in each transaction, both with the same
         // exact fill sizes.
-        let get_market_order_event =
-            |txn: &ParsedTransactionWithEvents| -> MarketOrderEventInstructionData {
-                let mut orders: Vec<&MarketOrderEventInstructionData> = txn
-                    .events
-                    .iter()
-                    .filter_map(|ev| match ev {
-                        DropsetEvent::MarketOrder(m) => Some(m),
-                        _ => None,
-                    })
-                    .collect_vec();
-                assert_eq!(orders.len(), 1);
-                orders.pop().unwrap().clone()
-            };
+        let get_market_order_event = |txn: &ParsedTransactionWithEvents| -> DisplayMarketOrderData {
+            let mut orders: Vec<&DisplayMarketOrderData> = txn
+                .events
+                .iter()
+                .filter_map(|ev| match ev {
+                    DropsetEvent::MarketOrder(m) => Some(m),
+                    _ => None,
+                })
+                .collect_vec();
+            assert_eq!(orders.len(), 1);
+            orders.pop().unwrap().clone()
+        };
 
         let event_1 = get_market_order_event(&fill_1);
         let event_2 = get_market_order_event(&fill_2);
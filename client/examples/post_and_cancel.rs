@@ -32,6 +32,7 @@ async fn main() -> anyhow::Result<()> {
             compute_budget: Some(2000000),
             debug_logs: Some(true),
             program_id_filter: HashSet::from([dropset_interface::program::ID]),
+            ..Default::default()
         }),
     );
 
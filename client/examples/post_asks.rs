@@ -27,6 +27,7 @@ async fn main() -> anyhow::Result<()> {
             compute_budget: Some(2000000),
             debug_logs: Some(true),
             program_id_filter: HashSet::from([dropset_interface::program::ID]),
+            ..Default::default()
         }),
     );
 
@@ -94,8 +95,8 @@ async fn main() -> anyhow::Result<()> {
         })
         .collect_vec();
 
-    e2e.rpc
-        .send_and_confirm_txn(trader, &[trader], &ask_instructions)
+    e2e.market
+        .send_and_confirm_txn(&e2e.rpc, trader, &[trader], &ask_instructions)
         .await?;
 
     println!(
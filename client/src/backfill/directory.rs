@@ -0,0 +1,112 @@
+//! See [`DirectoryTransactionSource`].
+
+use std::{
+    fs,
+    future::{
+        ready,
+        Future,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use anyhow::Context;
+use solana_address::Address;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use transaction_parser::client_rpc::parse_transaction;
+
+use crate::backfill::TransactionSource;
+
+/// A [`TransactionSource`] backed by a local directory of saved transactions, one JSON file per
+/// transaction in the same format `getTransaction` returns. Useful for replaying history offline.
+///
+/// Transactions are ordered by slot, then by file name for transactions in the same slot. Since
+/// saved transactions don't record their position within a block, files written with
+/// [`DirectoryTransactionSource::save`] are named so that this matches the order they were saved
+/// in. Every saved transaction is returned regardless of the address being backfilled.
+pub struct DirectoryTransactionSource {
+    transactions: Vec<(Signature, EncodedConfirmedTransactionWithStatusMeta)>,
+}
+
+impl DirectoryTransactionSource {
+    /// Loads every `.json` file in `dir` as a transaction.
+    pub fn load(dir: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let mut files = fs::read_dir(dir.as_ref())
+            .with_context(|| format!("Couldn't read directory {}", dir.as_ref().display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        files.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+        files.sort();
+
+        let mut transactions = files
+            .iter()
+            .map(|path| {
+                let json = fs::read_to_string(path)?;
+                let encoded: EncodedConfirmedTransactionWithStatusMeta =
+                    serde_json::from_str(&json)
+                        .with_context(|| format!("Invalid transaction in {}", path.display()))?;
+                let signature = parse_transaction(encoded.clone())?.signature;
+                anyhow::Ok((signature, encoded))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        // Stable, so transactions in the same slot keep their file name order.
+        transactions.sort_by_key(|(_, encoded)| encoded.slot);
+
+        Ok(Self { transactions })
+    }
+
+    /// Saves a transaction to `dir` as `<slot>-<index>-<signature>.json`, where `index` is the
+    /// number of transactions already saved in `dir` for tie-breaking within a slot.
+    pub fn save(
+        dir: impl AsRef<Path>,
+        encoded: &EncodedConfirmedTransactionWithStatusMeta,
+    ) -> anyhow::Result<PathBuf> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let index = fs::read_dir(dir)?.count();
+        let signature = parse_transaction(encoded.clone())?.signature;
+        let path = dir.join(format!("{:020}-{index:08}-{signature}.json", encoded.slot));
+        fs::write(&path, serde_json::to_vec_pretty(encoded)?)?;
+
+        Ok(path)
+    }
+}
+
+impl TransactionSource for DirectoryTransactionSource {
+    fn signatures(
+        &self,
+        _address: &Address,
+        after: Option<Signature>,
+    ) -> impl Future<Output = anyhow::Result<Vec<Signature>>> + Send {
+        let signatures = self.transactions.iter().map(|(signature, _)| *signature);
+        let res = match after {
+            Some(after) => match signatures.clone().position(|s| s == after) {
+                Some(i) => Ok(signatures.skip(i + 1).collect()),
+                None => Err(anyhow::anyhow!(
+                    "Transaction {after} isn't in the directory"
+                )),
+            },
+            None => Ok(signatures.collect()),
+        };
+
+        ready(res)
+    }
+
+    fn transaction(
+        &self,
+        signature: &Signature,
+    ) -> impl Future<Output = anyhow::Result<EncodedConfirmedTransactionWithStatusMeta>> + Send
+    {
+        let res = self
+            .transactions
+            .iter()
+            .find(|(s, _)| s == signature)
+            .map(|(_, encoded)| encoded.clone())
+            .with_context(|| format!("Transaction {signature} isn't in the directory"));
+
+        ready(res)
+    }
+}
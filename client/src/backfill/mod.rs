@@ -0,0 +1,139 @@
+//! Historical event reconstruction for a single market by walking its transaction history.
+//!
+//! See [`backfill`] for the entry point and [`TransactionSource`] for where the history comes from.
+
+mod directory;
+mod rpc;
+
+use std::future::Future;
+
+pub use directory::*;
+use futures::{
+    stream,
+    Stream,
+    StreamExt,
+    TryStreamExt,
+};
+pub use rpc::*;
+use solana_address::Address;
+use solana_sdk::{
+    clock::UnixTimestamp,
+    signature::Signature,
+};
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use transaction_parser::{
+    client_rpc::parse_transaction,
+    events::dropset_event::SequencedEvent,
+    ParseDropsetEvents,
+};
+
+/// A source of historical transactions, e.g. an RPC node or transactions saved to disk.
+pub trait TransactionSource {
+    /// Returns the signatures of the transactions that reference `address`, oldest first.
+    ///
+    /// If `after` is provided, only transactions that landed strictly after it are returned.
+    /// Sources may return unrelated or failed transactions; they're filtered out while
+    /// backfilling.
+    fn signatures(
+        &self,
+        address: &Address,
+        after: Option<Signature>,
+    ) -> impl Future<Output = anyhow::Result<Vec<Signature>>> + Send;
+
+    /// Fetches a single transaction by its signature.
+    fn transaction(
+        &self,
+        signature: &Signature,
+    ) -> impl Future<Output = anyhow::Result<EncodedConfirmedTransactionWithStatusMeta>> + Send;
+}
+
+/// The position to resume a backfill from: the last event that was fully processed and the
+/// transaction it was emitted in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BackfillCheckpoint {
+    pub signature: Signature,
+    pub seq: u64,
+}
+
+/// A market event reconstructed from transaction history.
+#[derive(Debug)]
+pub struct BackfilledEvent {
+    pub slot: u64,
+    pub signature: Signature,
+    pub block_time: Option<UnixTimestamp>,
+    pub event: SequencedEvent,
+}
+
+impl BackfilledEvent {
+    /// The checkpoint to persist once this event has been processed.
+    pub fn checkpoint(&self) -> BackfillCheckpoint {
+        BackfillCheckpoint {
+            signature: self.signature,
+            seq: self.event.seq,
+        }
+    }
+}
+
+/// Streams every event emitted for `market` in the order they were emitted, starting after
+/// `checkpoint` if provided and from the market's first transaction otherwise.
+///
+/// The checkpoint's own transaction is fetched again so that any events it emitted after the
+/// checkpoint's sequence number aren't skipped.
+pub fn backfill<S: TransactionSource>(
+    source: &S,
+    market: Address,
+    checkpoint: Option<BackfillCheckpoint>,
+) -> impl Stream<Item = anyhow::Result<BackfilledEvent>> + '_ {
+    let after_seq = checkpoint.map_or(0, |checkpoint| checkpoint.seq);
+
+    stream::once(async move {
+        let after = checkpoint.map(|checkpoint| checkpoint.signature);
+        let signatures = source.signatures(&market, after).await?;
+        let signatures = after.into_iter().chain(signatures).map(anyhow::Ok);
+        anyhow::Ok(stream::iter(signatures))
+    })
+    .try_flatten()
+    .and_then(move |signature| async move {
+        let encoded = source.transaction(&signature).await?;
+        market_events(encoded, &market, after_seq)
+    })
+    .map_ok(|events| stream::iter(events).map(anyhow::Ok))
+    .try_flatten()
+}
+
+/// Parses all of the events emitted for `market` in a transaction with sequence numbers greater
+/// than `after_seq`.
+pub fn market_events(
+    encoded: EncodedConfirmedTransactionWithStatusMeta,
+    market: &Address,
+    after_seq: u64,
+) -> anyhow::Result<Vec<BackfilledEvent>> {
+    let transaction = parse_transaction(encoded)?;
+    if transaction.err.is_some() {
+        return Ok(vec![]);
+    }
+
+    let mut events = vec![];
+    for inner in transaction
+        .instructions
+        .iter()
+        .flat_map(|outer| outer.inner_instructions.iter())
+    {
+        let sequenced = inner
+            .parse_sequenced_events()
+            .map_err(|e| anyhow::anyhow!("Failed to parse events: {e:?}"))?;
+        events.extend(
+            sequenced
+                .into_iter()
+                .filter(|event| event.market == *market && event.seq > after_seq)
+                .map(|event| BackfilledEvent {
+                    slot: transaction.slot,
+                    signature: transaction.signature,
+                    block_time: transaction.block_time,
+                    event,
+                }),
+        );
+    }
+
+    Ok(events)
+}
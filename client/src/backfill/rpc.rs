@@ -0,0 +1,91 @@
+//! See [`RpcTransactionSource`].
+
+use std::str::FromStr;
+
+use anyhow::Context;
+use solana_address::Address;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+};
+use solana_commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+use crate::{
+    backfill::TransactionSource,
+    transactions::fetch_transaction_json,
+};
+
+/// The maximum number of signatures `getSignaturesForAddress` returns per request.
+pub const MAX_SIGNATURES_PAGE_SIZE: usize = 1000;
+
+/// A [`TransactionSource`] that pages through `getSignaturesForAddress` and fetches each
+/// transaction from an RPC node.
+pub struct RpcTransactionSource<'a> {
+    pub rpc: &'a RpcClient,
+    pub page_size: usize,
+}
+
+impl<'a> RpcTransactionSource<'a> {
+    pub fn new(rpc: &'a RpcClient) -> Self {
+        Self {
+            rpc,
+            page_size: MAX_SIGNATURES_PAGE_SIZE,
+        }
+    }
+}
+
+impl TransactionSource for RpcTransactionSource<'_> {
+    async fn signatures(
+        &self,
+        address: &Address,
+        after: Option<Signature>,
+    ) -> anyhow::Result<Vec<Signature>> {
+        let mut signatures = vec![];
+        let mut before = None;
+
+        // Signatures are returned newest first, so page backwards until `after` is reached or the
+        // history runs out.
+        loop {
+            let page = self
+                .rpc
+                .get_signatures_for_address_with_config(
+                    address,
+                    GetConfirmedSignaturesForAddress2Config {
+                        before,
+                        until: after,
+                        limit: Some(self.page_size),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                    },
+                )
+                .await
+                .context("Failed to fetch signatures for address")?;
+
+            let page_len = page.len();
+            for status in page {
+                let signature = Signature::from_str(&status.signature)
+                    .context("RPC returned an invalid signature")?;
+                before = Some(signature);
+                // Failed transactions can't emit events, so skip fetching them at all.
+                if status.err.is_none() {
+                    signatures.push(signature);
+                }
+            }
+
+            if page_len == 0 || page_len < self.page_size {
+                break;
+            }
+        }
+
+        signatures.reverse();
+        Ok(signatures)
+    }
+
+    async fn transaction(
+        &self,
+        signature: &Signature,
+    ) -> anyhow::Result<EncodedConfirmedTransactionWithStatusMeta> {
+        fetch_transaction_json(self.rpc, *signature).await
+    }
+}
@@ -8,18 +8,38 @@ use dropset_interface::{
         CloseSeatInstructionData,
         DepositInstructionData,
         MarketOrderInstructionData,
+        MigrateMarketInstructionData,
         PostOrderInstructionData,
         RegisterMarketInstructionData,
         WithdrawInstructionData,
     },
     seeds::event_authority,
     state::{
-        sector::NIL,
+        market::MarketRef,
+        market_header::MarketHeader,
+        market_invariants::{
+            check_market_balances,
+            MarketLiabilities,
+        },
+        sector::{
+            SectorIndex,
+            NIL,
+        },
+        transmutable::Transmutable,
         SYSTEM_PROGRAM_ID,
     },
 };
+use price::client_helpers::{
+    token_to_atoms_price,
+    try_encoded_u32_to_token_price,
+};
+use rust_decimal::Decimal;
 use solana_address::Address;
-use solana_sdk::signature::Keypair;
+use solana_instruction::Instruction;
+use solana_sdk::{
+    message::AddressLookupTableAccount,
+    signature::Keypair,
+};
 use transaction_parser::views::{
     try_market_view_all_from_owner_and_data,
     MarketSeatView,
@@ -28,9 +48,23 @@ use transaction_parser::views::{
 
 use crate::{
     context::token::TokenContext,
+    fetch::{
+        self,
+        MarketBookData,
+        MarketHeaderData,
+        MarketSeatData,
+    },
+    lookup_table::{
+        create_lookup_table,
+        extend_lookup_table,
+        fetch_lookup_table,
+    },
     pda::find_market_address,
     single_signer_instruction::SingleSignerInstruction,
-    transactions::CustomRpcClient,
+    transactions::{
+        CustomRpcClient,
+        ParsedTransactionWithEvents,
+    },
 };
 
 /// A struct containing contextual fields for a market.
@@ -42,15 +76,19 @@ pub struct MarketContext {
     pub quote: TokenContext,
     pub base_market_ata: Address,
     pub quote_market_ata: Address,
+    /// A lookup table holding the market's [static accounts](Self::static_accounts). Instructions
+    /// built by this context and transactions sent through [`MarketContext::send_and_confirm_txn`]
+    /// load accounts from it.
+    pub lookup_table: Option<AddressLookupTableAccount>,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum BookSide {
     Ask,
     Bid,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Denomination {
     Base,
     Quote,
@@ -85,9 +123,26 @@ impl MarketContext {
             quote,
             base_market_ata,
             quote_market_ata,
+            lookup_table: None,
         })
     }
 
+    /// Creates a new [`MarketContext`] for an existing market account, reading its token pair from
+    /// the market header.
+    pub async fn new_from_market(rpc: &CustomRpcClient, market: Address) -> anyhow::Result<Self> {
+        let market_account = rpc.client.get_account(&market).await?;
+        let header =
+            try_market_view_all_from_owner_and_data(market_account.owner, &market_account.data)?
+                .header;
+        let ctx =
+            Self::new_from_token_pair(rpc, header.base_mint, header.quote_mint, None, None).await?;
+        if ctx.market != market {
+            anyhow::bail!("{market} isn't the market PDA for its base and quote mints");
+        }
+
+        Ok(ctx)
+    }
+
     /// Creates a market with random tokens generated by airdropping funds and returns the
     /// [`MarketContext`] resulting from it.
     pub async fn create_market(rpc: &CustomRpcClient) -> anyhow::Result<Self> {
@@ -102,9 +157,80 @@ impl MarketContext {
             quote,
             base_market_ata,
             quote_market_ata,
+            lookup_table: None,
         })
     }
 
+    /// The accounts every trader's market instructions share: the market, its ATAs, the mints,
+    /// the token programs, any transfer hook accounts, the event authority, and the program.
+    pub fn static_accounts(&self) -> Vec<Address> {
+        [
+            self.market,
+            self.base_market_ata,
+            self.quote_market_ata,
+            self.base.mint_address,
+            self.quote.mint_address,
+            self.base.token_program,
+            self.quote.token_program,
+            event_authority::ID,
+            dropset::ID,
+        ]
+        .into_iter()
+        .chain(
+            [&self.base, &self.quote]
+                .into_iter()
+                .flat_map(|token| token.transfer_hook_accounts.iter().map(|meta| meta.pubkey)),
+        )
+        .collect()
+    }
+
+    /// Creates a lookup table holding the market's [static accounts](Self::static_accounts) and
+    /// uses it for this context's transactions.
+    pub async fn create_lookup_table(
+        &mut self,
+        rpc: &CustomRpcClient,
+        authority: &Keypair,
+    ) -> anyhow::Result<Address> {
+        let table = create_lookup_table(rpc, authority, &self.static_accounts()).await?;
+        let address = table.key;
+        self.lookup_table = Some(table);
+
+        Ok(address)
+    }
+
+    /// Loads an existing lookup table for this context's transactions, appending any of the
+    /// market's static accounts it's missing if `authority` is passed.
+    pub async fn load_lookup_table(
+        &mut self,
+        rpc: &CustomRpcClient,
+        table: Address,
+        authority: Option<&Keypair>,
+    ) -> anyhow::Result<()> {
+        let table = match authority {
+            Some(authority) => {
+                extend_lookup_table(rpc, authority, table, &self.static_accounts()).await?
+            }
+            None => fetch_lookup_table(rpc, &table).await?,
+        };
+        self.lookup_table = Some(table);
+
+        Ok(())
+    }
+
+    /// Sends and confirms a transaction the same way as [`CustomRpcClient::send_and_confirm_txn`],
+    /// additionally loading accounts from the market's lookup table if it has one. Use this to
+    /// batch several of the market's instructions into one transaction.
+    pub async fn send_and_confirm_txn(
+        &self,
+        rpc: &CustomRpcClient,
+        payer: &Keypair,
+        signers: &[&Keypair],
+        instructions: &[Instruction],
+    ) -> anyhow::Result<ParsedTransactionWithEvents> {
+        rpc.send_with_lookup_tables(payer, signers, instructions, self.lookup_table.as_slice())
+            .await
+    }
+
     pub fn get_base_ata(&self, owner: &Address) -> Address {
         self.base.get_ata_for(owner)
     }
@@ -122,7 +248,7 @@ impl MarketContext {
     }
 
     pub fn register_market(&self, payer: Address, num_sectors: u16) -> SingleSignerInstruction {
-        RegisterMarket {
+        let instruction = RegisterMarket {
             event_authority: event_authority::ID,
             user: payer,
             market_account: self.market,
@@ -136,9 +262,22 @@ impl MarketContext {
             system_program: SYSTEM_PROGRAM_ID,
             dropset_program: dropset::ID,
         }
-        .create_instruction(RegisterMarketInstructionData::new(num_sectors))
-        .try_into()
-        .expect("Should be a single signer instruction")
+        .create_instruction(RegisterMarketInstructionData::new(num_sectors));
+
+        self.single_signer(instruction)
+    }
+
+    /// Upgrades the market account to the current layout version. Anyone can migrate a market; the
+    /// payer funds any additional rent required by the new layout.
+    pub fn migrate_market(&self, payer: Address) -> SingleSignerInstruction {
+        let instruction = MigrateMarket {
+            payer,
+            market_account: self.market,
+            system_program: SYSTEM_PROGRAM_ID,
+        }
+        .create_instruction(MigrateMarketInstructionData::new());
+
+        self.single_signer(instruction)
     }
 
     pub async fn view_market(&self, rpc: &CustomRpcClient) -> anyhow::Result<MarketViewAll> {
@@ -146,6 +285,60 @@ impl MarketContext {
         try_market_view_all_from_owner_and_data(market_account.owner, &market_account.data)
     }
 
+    /// Fetches only the market's header. See [`crate::fetch`].
+    pub async fn fetch_header(&self, rpc: &CustomRpcClient) -> anyhow::Result<MarketHeaderData> {
+        fetch::fetch_header(&rpc.client, &self.market).await
+    }
+
+    /// Fetches the market to read up to `depth` levels of each side of its book, without decoding
+    /// its seats or the rest of the book.
+    pub async fn fetch_top_of_book(
+        &self,
+        rpc: &CustomRpcClient,
+        depth: usize,
+    ) -> anyhow::Result<MarketBookData> {
+        fetch::fetch_top_of_book(&rpc.client, &self.market, depth).await
+    }
+
+    /// Fetches only the sector at `index_hint`, if it's still `user`'s seat.
+    pub async fn fetch_seat_by_hint(
+        &self,
+        rpc: &CustomRpcClient,
+        index_hint: SectorIndex,
+        user: &Address,
+    ) -> anyhow::Result<Option<MarketSeatData>> {
+        fetch::fetch_seat(&rpc.client, &self.market, index_hint, user).await
+    }
+
+    /// Fetches the market account and its base and quote token accounts, then checks the market's
+    /// full-book invariants and that the seat balances plus order collateral equal the token
+    /// account balances.
+    pub async fn check_invariants(
+        &self,
+        rpc: &CustomRpcClient,
+    ) -> anyhow::Result<MarketLiabilities> {
+        let market_account = rpc.client.get_account(&self.market).await?;
+        if market_account.data.len() < MarketHeader::LEN {
+            anyhow::bail!("Market account data is too short");
+        }
+        let base_balance = rpc
+            .client
+            .get_token_account_balance(&self.base_market_ata)
+            .await?
+            .amount
+            .parse::<u64>()?;
+        let quote_balance = rpc
+            .client
+            .get_token_account_balance(&self.quote_market_ata)
+            .await?
+            .amount
+            .parse::<u64>()?;
+
+        // Safety: The account data was just checked to be at least `MarketHeader::LEN` bytes.
+        let market = unsafe { MarketRef::from_bytes(&market_account.data) };
+        Ok(check_market_balances(&market, base_balance, quote_balance)?)
+    }
+
     pub async fn fetch_seat(
         &self,
         rpc: &CustomRpcClient,
@@ -160,7 +353,7 @@ impl MarketContext {
     }
 
     pub fn close_seat(&self, user: Address, sector_index_hint: u32) -> SingleSignerInstruction {
-        CloseSeat {
+        let instruction = CloseSeat {
             event_authority: event_authority::ID,
             user,
             market_account: self.market,
@@ -174,9 +367,9 @@ impl MarketContext {
             quote_token_program: self.quote.token_program,
             dropset_program: dropset::ID,
         }
-        .create_instruction(CloseSeatInstructionData::new(sector_index_hint))
-        .try_into()
-        .expect("Should be a single signer instruction")
+        .create_instruction(CloseSeatInstructionData::new(sector_index_hint));
+
+        self.with_transfer_hook_accounts(instruction, &[&self.base, &self.quote])
     }
 
     pub fn deposit_base(
@@ -224,15 +417,15 @@ impl MarketContext {
         user: Address,
         data: PostOrderInstructionData,
     ) -> SingleSignerInstruction {
-        PostOrder {
+        let instruction = PostOrder {
             event_authority: event_authority::ID,
             user,
             market_account: self.market,
             dropset_program: dropset::ID,
         }
-        .create_instruction(data)
-        .try_into()
-        .expect("Should be a single signer instruction")
+        .create_instruction(data);
+
+        self.single_signer(instruction)
     }
 
     pub fn cancel_order(
@@ -240,15 +433,15 @@ impl MarketContext {
         user: Address,
         data: CancelOrderInstructionData,
     ) -> SingleSignerInstruction {
-        CancelOrder {
+        let instruction = CancelOrder {
             event_authority: event_authority::ID,
             user,
             market_account: self.market,
             dropset_program: dropset::ID,
         }
-        .create_instruction(data)
-        .try_into()
-        .expect("Should be a single signer instruction")
+        .create_instruction(data);
+
+        self.single_signer(instruction)
     }
 
     pub fn market_order(
@@ -256,7 +449,7 @@ impl MarketContext {
         user: Address,
         data: MarketOrderInstructionData,
     ) -> SingleSignerInstruction {
-        MarketOrder {
+        let instruction = MarketOrder {
             event_authority: event_authority::ID,
             user,
             market_account: self.market,
@@ -270,9 +463,9 @@ impl MarketContext {
             quote_token_program: self.quote.token_program,
             dropset_program: dropset::ID,
         }
-        .create_instruction(data)
-        .try_into()
-        .expect("Should be a single signer instruction")
+        .create_instruction(data);
+
+        self.with_transfer_hook_accounts(instruction, &[&self.base, &self.quote])
     }
 
     fn deposit(
@@ -281,7 +474,7 @@ impl MarketContext {
         data: DepositInstructionData,
         is_base: bool,
     ) -> SingleSignerInstruction {
-        match is_base {
+        let instruction = match is_base {
             true => Deposit {
                 event_authority: event_authority::ID,
                 user,
@@ -303,9 +496,10 @@ impl MarketContext {
                 dropset_program: dropset::ID,
             },
         }
-        .create_instruction(data)
-        .try_into()
-        .expect("Should be a single signer instruction")
+        .create_instruction(data);
+
+        let token = if is_base { &self.base } else { &self.quote };
+        self.with_transfer_hook_accounts(instruction, &[token])
     }
 
     fn withdraw(
@@ -314,7 +508,7 @@ impl MarketContext {
         data: WithdrawInstructionData,
         is_base: bool,
     ) -> SingleSignerInstruction {
-        match is_base {
+        let instruction = match is_base {
             true => Withdraw {
                 event_authority: event_authority::ID,
                 user,
@@ -336,8 +530,61 @@ impl MarketContext {
                 dropset_program: dropset::ID,
             },
         }
-        .create_instruction(data)
-        .try_into()
-        .expect("Should be a single signer instruction")
+        .create_instruction(data);
+
+        let token = if is_base { &self.base } else { &self.quote };
+        self.with_transfer_hook_accounts(instruction, &[token])
+    }
+
+    /// Converts a price in whole tokens, i.e. quote tokens per base token, to a price in atoms.
+    pub fn to_atoms_price(&self, price: Decimal) -> Decimal {
+        token_to_atoms_price(price, self.base.mint_decimals, self.quote.mint_decimals)
     }
+
+    /// Decodes an encoded on-chain price in atoms to a price in whole tokens.
+    pub fn to_decimal_price(&self, encoded_price: u32) -> anyhow::Result<Decimal> {
+        decode_token_price(
+            encoded_price,
+            self.base.mint_decimals,
+            self.quote.mint_decimals,
+        )
+    }
+
+    /// Appends the transfer hook accounts for each token transferred in the instruction, skipping
+    /// any duplicates.
+    fn with_transfer_hook_accounts(
+        &self,
+        mut instruction: Instruction,
+        tokens: &[&TokenContext],
+    ) -> SingleSignerInstruction {
+        for meta in tokens
+            .iter()
+            .flat_map(|token| &token.transfer_hook_accounts)
+        {
+            if !instruction.accounts.contains(meta) {
+                instruction.accounts.push(meta.clone());
+            }
+        }
+
+        self.single_signer(instruction)
+    }
+
+    /// Wraps an instruction for the market, loading accounts from the market's lookup table when
+    /// it's sent.
+    fn single_signer(&self, instruction: Instruction) -> SingleSignerInstruction {
+        SingleSignerInstruction::try_from(instruction)
+            .expect("Should be a single signer instruction")
+            .with_lookup_table(self.lookup_table.clone())
+    }
+}
+
+/// Decodes an encoded on-chain price in atoms to a price in whole tokens, given each mint's
+/// decimals.
+pub(crate) fn decode_token_price(
+    encoded_price: u32,
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> anyhow::Result<Decimal> {
+    try_encoded_u32_to_token_price(encoded_price, base_decimals, quote_decimals)
+        .map_err(|e| anyhow::anyhow!("Invalid encoded price {encoded_price}: {e}"))
 }
@@ -2,6 +2,7 @@
 //! tests and examples.
 
 use solana_address::Address;
+use solana_instruction::AccountMeta;
 use solana_sdk::{
     program_pack::Pack,
     signature::{
@@ -16,14 +17,23 @@ use spl_associated_token_account_interface::{
 };
 use spl_token_2022_interface::{
     check_spl_token_program_account,
+    extension::{
+        transfer_hook::TransferHook,
+        BaseStateWithExtensions,
+        StateWithExtensions,
+    },
     instruction::mint_to_checked,
+    state::Mint as Token2022Mint,
 };
 use spl_token_interface::state::{
     Account,
     Mint,
 };
 
-use crate::transactions::CustomRpcClient;
+use crate::{
+    pda::find_extra_account_metas_address,
+    transactions::CustomRpcClient,
+};
 
 pub struct TokenContext {
     /// If the mint authority is provided, [`TokenContext`] enables minting tokens directly
@@ -32,6 +42,10 @@ pub struct TokenContext {
     pub mint_address: Address,
     pub token_program: Address,
     pub mint_decimals: u8,
+    /// The trailing accounts appended to instructions that transfer this token. This is empty
+    /// unless the mint has a transfer hook extension, in which case it holds the hook program and
+    /// its extra-account-metas PDA.
+    pub transfer_hook_accounts: Vec<AccountMeta>,
 }
 
 impl TokenContext {
@@ -44,7 +58,8 @@ impl TokenContext {
     ) -> anyhow::Result<Self> {
         let mint_account = rpc.client.get_account(&mint_token).await?;
         check_spl_token_program_account(&mint_account.owner)?;
-        let mint = Mint::unpack(&mint_account.data)?;
+        let mint_state = StateWithExtensions::<Token2022Mint>::unpack(&mint_account.data)?;
+        let mint = mint_state.base;
 
         let auth_1 = mint_authority.as_ref().map(|kp| kp.pubkey());
         let auth_2 = mint.mint_authority.into();
@@ -60,6 +75,7 @@ impl TokenContext {
             mint_address: mint_token,
             token_program: mint_account.owner,
             mint_decimals: mint.decimals,
+            transfer_hook_accounts: resolve_transfer_hook_accounts(&mint_token, &mint_state),
         })
     }
 
@@ -112,6 +128,7 @@ impl TokenContext {
             mint_address: mint.pubkey(),
             token_program,
             mint_decimals: decimals,
+            transfer_hook_accounts: vec![],
         })
     }
 
@@ -180,3 +197,29 @@ impl TokenContext {
         Ok(account_data.amount)
     }
 }
+
+/// Resolves the accounts a transfer hook program needs forwarded to it in `TransferChecked` CPIs:
+/// the hook program itself and its extra-account-metas PDA.
+///
+/// Returns an empty vec for mints without a transfer hook or with the hook program unset.
+fn resolve_transfer_hook_accounts<S: BaseStateWithExtensions<Token2022Mint>>(
+    mint: &Address,
+    mint_state: &S,
+) -> Vec<AccountMeta> {
+    let hook_program = mint_state
+        .get_extension::<TransferHook>()
+        .ok()
+        .and_then(|hook| Option::<Address>::from(hook.program_id));
+
+    match hook_program {
+        Some(hook_program) => {
+            let (extra_account_metas, _bump) =
+                find_extra_account_metas_address(mint, &hook_program);
+            vec![
+                AccountMeta::new_readonly(hook_program, false),
+                AccountMeta::new_readonly(extra_account_metas, false),
+            ]
+        }
+        None => vec![],
+    }
+}
@@ -0,0 +1,512 @@
+//! A high-level client for trading on a single `dropset` market with a single trader.
+//!
+//! [`DropsetClient`] wraps a [`MarketContext`] and takes care of the details its raw instruction
+//! builders leave to the caller: finding and caching the trader's seat index, converting decimal
+//! prices to order arguments, and decoding the emitted events into typed results.
+
+use anyhow::Context;
+use dropset_interface::{
+    error::DropsetError,
+    instructions::{
+        CancelOrderInstructionData,
+        MarketOrderInstructionData,
+        PostOrderInstructionData,
+    },
+    state::sector::{
+        SectorIndex,
+        NIL,
+    },
+};
+use price::{
+    client_helpers::{
+        to_order_info_args,
+        try_encoded_u32_to_decoded_decimal,
+        try_to_biased_exponent,
+    },
+    EncodedPrice,
+    ValidatedPriceMantissa,
+};
+use rust_decimal::Decimal;
+use solana_address::Address;
+use solana_sdk::{
+    signature::Keypair,
+    signer::Signer,
+};
+use transaction_parser::{
+    events::dropset_event::DropsetEvent,
+    views::{
+        MarketSeatView,
+        OrderView,
+    },
+};
+
+use crate::{
+    context::market::{
+        BookSide,
+        Denomination,
+        MarketContext,
+    },
+    sender::SendError,
+    single_signer_instruction::SingleSignerInstruction,
+    transactions::{
+        CustomRpcClient,
+        ParsedTransactionWithEvents,
+    },
+};
+
+/// Trades on one market on behalf of one trader.
+///
+/// Prices are decimal prices in whole tokens, i.e. quote tokens per base token, and are converted
+/// to atoms with the mints' decimals. Sizes and amounts are in atoms.
+pub struct DropsetClient {
+    pub rpc: CustomRpcClient,
+    pub market: MarketContext,
+    trader: Keypair,
+    /// The trader's last known seat index, used as the sector index hint.
+    seat_hint: Option<SectorIndex>,
+}
+
+/// An order posted to the book.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlacedOrder {
+    pub side: BookSide,
+    pub price: Decimal,
+    pub encoded_price: u32,
+    pub base_atoms: u64,
+    pub quote_atoms: u64,
+}
+
+/// An order removed from the book, with the amounts that were still unfilled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CanceledOrder {
+    pub side: BookSide,
+    pub price: Decimal,
+    pub base_remaining: u64,
+    pub quote_remaining: u64,
+}
+
+/// The amounts a market order filled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarketOrderFill {
+    pub base_filled: u64,
+    pub quote_filled: u64,
+}
+
+/// The trader's available seat balances, i.e. what's deposited and not locked in open orders.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Balances {
+    pub base_available: u64,
+    pub quote_available: u64,
+}
+
+/// One of the trader's resting orders.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpenOrder {
+    pub side: BookSide,
+    pub price: Decimal,
+    pub encoded_price: u32,
+    pub base_remaining: u64,
+    pub quote_remaining: u64,
+}
+
+impl DropsetClient {
+    pub fn new(rpc: CustomRpcClient, market: MarketContext, trader: Keypair) -> Self {
+        Self {
+            rpc,
+            market,
+            trader,
+            seat_hint: None,
+        }
+    }
+
+    pub fn trader(&self) -> Address {
+        self.trader.pubkey()
+    }
+
+    /// Returns the trader's seat index, fetching the market to find it if it isn't cached.
+    pub async fn seat_index(&mut self) -> anyhow::Result<SectorIndex> {
+        match self.seat_hint {
+            Some(index) => Ok(index),
+            None => Ok(self.fetch_seat().await?.index),
+        }
+    }
+
+    /// Deposits `amount` atoms into the trader's seat, creating the seat if it doesn't exist yet.
+    pub async fn deposit(&mut self, denomination: Denomination, amount: u64) -> anyhow::Result<()> {
+        let hint = match self.seat_hint {
+            Some(index) => index,
+            None => self
+                .market
+                .fetch_seat(&self.rpc, &self.trader())
+                .await?
+                .map_or(NIL, |seat| seat.index),
+        };
+
+        let res = self
+            .send_with_seat_hint(hint, |market, user, hint| match denomination {
+                Denomination::Base => market.deposit_base(user, amount, hint),
+                Denomination::Quote => market.deposit_quote(user, amount, hint),
+            })
+            .await?;
+
+        // A new seat's index is only known once the deposit lands.
+        if let Some(index) = res.events.iter().find_map(|event| match event {
+            DropsetEvent::Deposit(deposit) => Some(deposit.seat_sector_index),
+            _ => None,
+        }) {
+            self.seat_hint = Some(index);
+        }
+
+        Ok(())
+    }
+
+    /// Withdraws `amount` atoms from the trader's seat.
+    pub async fn withdraw(
+        &mut self,
+        denomination: Denomination,
+        amount: u64,
+    ) -> anyhow::Result<()> {
+        self.send_with_seat(|market, user, hint| match denomination {
+            Denomination::Base => market.withdraw_base(user, amount, hint),
+            Denomination::Quote => market.withdraw_quote(user, amount, hint),
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    /// Closes the trader's seat, withdrawing its remaining balances.
+    pub async fn close_seat(&mut self) -> anyhow::Result<()> {
+        self.send_with_seat(|market, user, hint| market.close_seat(user, hint))
+            .await?;
+        self.seat_hint = None;
+
+        Ok(())
+    }
+
+    /// Posts a limit order for `size` base atoms at `price`.
+    pub async fn place_limit(
+        &mut self,
+        side: BookSide,
+        price: Decimal,
+        size: u64,
+    ) -> anyhow::Result<PlacedOrder> {
+        let args = to_order_info_args(self.market.to_atoms_price(price), size)
+            .map_err(|e| anyhow::anyhow!("Invalid order price or size: {e}"))?;
+        let is_bid = matches!(side, BookSide::Bid);
+
+        let res = self
+            .send_with_seat(|market, user, hint| {
+                market.post_order(
+                    user,
+                    PostOrderInstructionData::new(args.clone(), is_bid, hint),
+                )
+            })
+            .await?;
+
+        let posted = res
+            .events
+            .iter()
+            .find_map(|event| match event {
+                DropsetEvent::PostOrder(posted) => Some(posted),
+                _ => None,
+            })
+            .context("Post order transaction didn't emit a post order event")?;
+
+        Ok(PlacedOrder {
+            side,
+            price: self.market.to_decimal_price(posted.encoded_price)?,
+            encoded_price: posted.encoded_price,
+            base_atoms: posted.base_atoms,
+            quote_atoms: posted.quote_atoms,
+        })
+    }
+
+    /// Cancels the trader's order on `side` at `price`.
+    pub async fn cancel(
+        &mut self,
+        side: BookSide,
+        price: Decimal,
+    ) -> anyhow::Result<CanceledOrder> {
+        let encoded_price = encode_price(self.market.to_atoms_price(price))?;
+        self.cancel_encoded(side, encoded_price).await
+    }
+
+    /// Cancels the trader's order on `side` at the exact `encoded_price`, such as one from
+    /// [`OpenOrder::encoded_price`].
+    pub async fn cancel_encoded(
+        &mut self,
+        side: BookSide,
+        encoded_price: u32,
+    ) -> anyhow::Result<CanceledOrder> {
+        let is_bid = matches!(side, BookSide::Bid);
+
+        let res = self
+            .send_with_seat(|market, user, hint| {
+                market.cancel_order(
+                    user,
+                    CancelOrderInstructionData::new(encoded_price, is_bid, hint),
+                )
+            })
+            .await?;
+
+        let canceled = res
+            .events
+            .iter()
+            .find_map(|event| match event {
+                DropsetEvent::CancelOrder(canceled) => Some(canceled),
+                _ => None,
+            })
+            .context("Cancel order transaction didn't emit a cancel order event")?;
+
+        Ok(CanceledOrder {
+            side,
+            price: self.market.to_decimal_price(canceled.encoded_price)?,
+            base_remaining: canceled.base_remaining,
+            quote_remaining: canceled.quote_remaining,
+        })
+    }
+
+    /// Spends `quote_amount` quote atoms buying base from the asks.
+    ///
+    /// The program has no price limit for market orders, so the book is checked first and the
+    /// order isn't sent if filling it would take asks above `max_price`. The book can still change
+    /// before the order lands.
+    pub async fn market_buy(
+        &mut self,
+        quote_amount: u64,
+        max_price: Decimal,
+    ) -> anyhow::Result<MarketOrderFill> {
+        let max_price = self.market.to_atoms_price(max_price);
+        let asks = self.market.view_market(&self.rpc).await?.asks;
+        let fillable =
+            fillable_within(&asks, |price| price <= max_price, |ask| ask.quote_remaining)?;
+        if fillable < quote_amount {
+            anyhow::bail!(
+                "Only {fillable} of {quote_amount} quote atoms can be filled at or below the max price"
+            );
+        }
+
+        self.market_order(MarketOrderInstructionData::new(quote_amount, true, false))
+            .await
+    }
+
+    /// Sells `base_amount` base atoms into the bids.
+    ///
+    /// Like [`DropsetClient::market_buy`], the book is checked first and the order isn't sent if
+    /// filling it would take bids below `min_price`.
+    pub async fn market_sell(
+        &mut self,
+        base_amount: u64,
+        min_price: Decimal,
+    ) -> anyhow::Result<MarketOrderFill> {
+        let min_price = self.market.to_atoms_price(min_price);
+        let bids = self.market.view_market(&self.rpc).await?.bids;
+        let fillable =
+            fillable_within(&bids, |price| price >= min_price, |bid| bid.base_remaining)?;
+        if fillable < base_amount {
+            anyhow::bail!(
+                "Only {fillable} of {base_amount} base atoms can be filled at or above the min price"
+            );
+        }
+
+        self.market_order(MarketOrderInstructionData::new(base_amount, false, true))
+            .await
+    }
+
+    /// Fetches the trader's available seat balances.
+    pub async fn balances(&mut self) -> anyhow::Result<Balances> {
+        // With a cached hint, only the seat's sector needs to be fetched.
+        if let Some(hint) = self.seat_hint {
+            let seat = self
+                .market
+                .fetch_seat_by_hint(&self.rpc, hint, &self.trader())
+                .await?;
+            if let Some(seat) = seat {
+                return Ok(Balances {
+                    base_available: seat.seat().base_available(),
+                    quote_available: seat.seat().quote_available(),
+                });
+            }
+        }
+        let seat = self.fetch_seat().await?;
+
+        Ok(Balances {
+            base_available: seat.base_available,
+            quote_available: seat.quote_available,
+        })
+    }
+
+    /// Fetches the trader's resting orders, bids first.
+    pub async fn open_orders(&mut self) -> anyhow::Result<Vec<OpenOrder>> {
+        let market = self.market.view_market(&self.rpc).await?;
+        let Some(user_data) = market.users.get(&self.trader()) else {
+            self.seat_hint = None;
+            return Ok(vec![]);
+        };
+        self.seat_hint = Some(user_data.seat.index);
+
+        let bids = user_data.bids.iter().map(|order| (BookSide::Bid, order));
+        let asks = user_data.asks.iter().map(|order| (BookSide::Ask, order));
+        bids.chain(asks)
+            .map(|(side, order)| {
+                Ok(OpenOrder {
+                    side,
+                    price: self.market.to_decimal_price(order.encoded_price)?,
+                    encoded_price: order.encoded_price,
+                    base_remaining: order.base_remaining,
+                    quote_remaining: order.quote_remaining,
+                })
+            })
+            .collect()
+    }
+
+    async fn market_order(
+        &mut self,
+        data: MarketOrderInstructionData,
+    ) -> anyhow::Result<MarketOrderFill> {
+        let instruction = self.market.market_order(self.trader(), data);
+        let res = self.send(instruction).await?;
+
+        let fill = res
+            .events
+            .iter()
+            .find_map(|event| match event {
+                DropsetEvent::MarketOrder(fill) => Some(fill),
+                _ => None,
+            })
+            .context("Market order transaction didn't emit a market order event")?;
+
+        Ok(MarketOrderFill {
+            base_filled: fill.base_filled,
+            quote_filled: fill.quote_filled,
+        })
+    }
+
+    /// Fetches the trader's seat and caches its index.
+    async fn fetch_seat(&mut self) -> anyhow::Result<MarketSeatView> {
+        let seat = self.market.fetch_seat(&self.rpc, &self.trader()).await?;
+        self.seat_hint = seat.as_ref().map(|seat| seat.index);
+
+        seat.with_context(|| format!("{} has no seat on this market", self.trader()))
+    }
+
+    /// Sends the instruction built with the trader's seat index as the hint.
+    async fn send_with_seat(
+        &mut self,
+        build: impl Fn(&MarketContext, Address, SectorIndex) -> SingleSignerInstruction,
+    ) -> anyhow::Result<ParsedTransactionWithEvents> {
+        let hint = self.seat_index().await?;
+        self.send_with_seat_hint(hint, build).await
+    }
+
+    /// Sends the instruction built with `hint`. If the hint turns out to be stale, e.g. because
+    /// the seat moved after being closed and re-created, the seat is fetched again and the
+    /// instruction is rebuilt and resent once.
+    async fn send_with_seat_hint(
+        &mut self,
+        hint: SectorIndex,
+        build: impl Fn(&MarketContext, Address, SectorIndex) -> SingleSignerInstruction,
+    ) -> anyhow::Result<ParsedTransactionWithEvents> {
+        match self.send(build(&self.market, self.trader(), hint)).await {
+            Err(e) if is_invalid_index_hint(&e) => {
+                let hint = self.fetch_seat().await?.index;
+                self.send(build(&self.market, self.trader(), hint)).await
+            }
+            res => res,
+        }
+    }
+
+    async fn send(
+        &self,
+        instruction: SingleSignerInstruction,
+    ) -> anyhow::Result<ParsedTransactionWithEvents> {
+        self.market
+            .send_and_confirm_txn(&self.rpc, &self.trader, &[], &[instruction.into()])
+            .await
+    }
+}
+
+/// Encodes a price in atoms the same way posting an order at that price would.
+fn encode_price(atoms_price: Decimal) -> anyhow::Result<u32> {
+    let (mantissa, scale) = ValidatedPriceMantissa::try_into_with_scale(atoms_price)
+        .map_err(|e| anyhow::anyhow!("Invalid price {atoms_price}: {e}"))?;
+    let exponent = try_to_biased_exponent(scale)
+        .map_err(|e| anyhow::anyhow!("Invalid price {atoms_price}: {e}"))?;
+
+    Ok(EncodedPrice::new(mantissa, exponent).as_u32())
+}
+
+/// Sums `amount` over the orders, best price first, whose atoms price satisfies `within`.
+fn fillable_within(
+    orders: &[OrderView],
+    within: impl Fn(Decimal) -> bool,
+    amount: impl Fn(&OrderView) -> u64,
+) -> anyhow::Result<u64> {
+    let mut fillable = 0u64;
+    for order in orders {
+        let price = try_encoded_u32_to_decoded_decimal(order.encoded_price)
+            .map_err(|e| anyhow::anyhow!("Invalid encoded price {}: {e}", order.encoded_price))?;
+        if !within(price) {
+            break;
+        }
+        fillable = fillable.saturating_add(amount(order));
+    }
+
+    Ok(fillable)
+}
+
+fn is_invalid_index_hint(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<SendError>()
+        .and_then(SendError::dropset_error)
+        .is_some_and(|e| e.error == DropsetError::InvalidIndexHint)
+}
+
+#[cfg(test)]
+mod tests {
+    use price::to_order_info;
+    use rust_decimal::dec;
+
+    use super::*;
+
+    fn order(price: Decimal, base_remaining: u64) -> OrderView {
+        OrderView {
+            prev_index: NIL,
+            index: 0,
+            next_index: NIL,
+            encoded_price: encode_price(price).unwrap(),
+            user_seat: 0,
+            base_remaining,
+            quote_remaining: 0,
+        }
+    }
+
+    #[test]
+    fn encode_price_matches_order_info_args() {
+        for price in [dec!(1.25), dec!(0.0375), dec!(42), dec!(123456.78)] {
+            let args = to_order_info_args(price, 500_000).unwrap();
+            let posted = to_order_info(args).unwrap().encoded_price.as_u32();
+            assert_eq!(encode_price(price).unwrap(), posted, "{price}");
+            assert_eq!(try_encoded_u32_to_decoded_decimal(posted).unwrap(), price);
+        }
+    }
+
+    #[test]
+    fn fillable_within_stops_at_price_cutoff() {
+        let size = |order: &OrderView| order.base_remaining;
+
+        // Asks, lowest first, filled up to a max price.
+        let asks = [order(dec!(1), 10), order(dec!(2), 20), order(dec!(3), 30)];
+        let max = |max: Decimal| move |price: Decimal| price <= max;
+        assert_eq!(fillable_within(&asks, max(dec!(0.5)), size).unwrap(), 0);
+        assert_eq!(fillable_within(&asks, max(dec!(2)), size).unwrap(), 30);
+        assert_eq!(fillable_within(&asks, max(dec!(10)), size).unwrap(), 60);
+
+        // Bids, highest first, filled down to a min price.
+        let bids = [order(dec!(3), 30), order(dec!(2), 20), order(dec!(1), 10)];
+        let min = |min: Decimal| move |price: Decimal| price >= min;
+        assert_eq!(fillable_within(&bids, min(dec!(2)), size).unwrap(), 50);
+        assert_eq!(fillable_within(&bids, min(dec!(4)), size).unwrap(), 0);
+    }
+}
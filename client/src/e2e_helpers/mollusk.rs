@@ -3,12 +3,27 @@ use std::{
     path::PathBuf,
 };
 
+use dropset_interface::state::{
+    market::MarketRefMut,
+    market_header::MarketHeader,
+    sector::SECTOR_SIZE,
+    transmutable::Transmutable,
+};
 use mollusk_svm::{
     Mollusk,
     MolluskContext,
 };
 use solana_account::Account;
 use solana_address::Address;
+use solana_sdk::{
+    program_pack::Pack,
+    rent::Rent,
+};
+use spl_token_interface::state::{
+    Account as TokenAccount,
+    AccountState,
+    Mint,
+};
 
 /// Converts an input deploy file to a program name used by the [`Mollusk::new`] function.
 ///
@@ -49,6 +64,77 @@ pub fn new_dropset_mollusk_context(
     context
 }
 
+/// Builds an initialized, rent-exempt SPL token mint account with 6 decimals.
+pub fn mint_account(supply: u64) -> Account {
+    let mut data = vec![0; Mint::LEN];
+    Mint {
+        supply,
+        decimals: 6,
+        is_initialized: true,
+        ..Default::default()
+    }
+    .pack_into_slice(&mut data);
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: spl_token_interface::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Builds an initialized, rent-exempt SPL token account holding `amount` of `mint`.
+pub fn token_account(mint: Address, owner: Address, amount: u64) -> Account {
+    let mut data = vec![0; TokenAccount::LEN];
+    TokenAccount {
+        mint,
+        owner,
+        amount,
+        state: AccountState::Initialized,
+        ..Default::default()
+    }
+    .pack_into_slice(&mut data);
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: spl_token_interface::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Builds the account for a freshly registered market with `num_sectors` free sectors, equivalent
+/// to what `RegisterMarket` creates, without needing the associated token account program.
+pub fn market_account(
+    bump: u8,
+    base_mint: &Address,
+    quote_mint: &Address,
+    num_sectors: u32,
+) -> Account {
+    let mut data = vec![0u8; MarketHeader::LEN + num_sectors as usize * SECTOR_SIZE];
+    // Safety: `data` is zeroed, long enough for the header, and isn't borrowed elsewhere.
+    unsafe {
+        MarketHeader::init(
+            data.as_mut_ptr() as *mut MarketHeader,
+            bump,
+            base_mint,
+            quote_mint,
+        );
+        MarketRefMut::from_bytes_mut(&mut data)
+            .free_stack()
+            .convert_zeroed_bytes_to_free_sectors(0, num_sectors)
+            .expect("Should initialize free sectors");
+    }
+    Account {
+        // Exactly rent-exempt, so that growing the market requires the payer to fund it.
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: dropset::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
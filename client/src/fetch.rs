@@ -0,0 +1,313 @@
+//! Lightweight market account fetchers for callers that only need part of a market, such as its
+//! header, top of book, or a single seat, without decoding the whole account into a
+//! [`transaction_parser::views::MarketViewAll`].
+//!
+//! Each fetcher owns the fetched bytes and hands out zero-copy views borrowing from them.
+
+use anyhow::bail;
+use dropset_interface::state::{
+    market_header::{
+        MarketHeader,
+        MARKET_ACCOUNT_DISCRIMINANT,
+    },
+    market_seat::MarketSeat,
+    order::Order,
+    sector::{
+        Sector,
+        SectorIndex,
+        NIL,
+    },
+    transmutable::Transmutable,
+};
+use solana_address::Address;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{
+        RpcAccountInfoConfig,
+        UiAccountEncoding,
+        UiDataSliceConfig,
+    },
+};
+
+/// A market's header, fetched with a data slice over the first [`MarketHeader::LEN`] bytes.
+pub struct MarketHeaderData {
+    data: Vec<u8>,
+}
+
+impl MarketHeaderData {
+    pub fn header(&self) -> &MarketHeader {
+        // Safety: `fetch_header` checked that the data is exactly `MarketHeader::LEN` bytes, and
+        // all bit patterns are valid for a `MarketHeader`.
+        unsafe { MarketHeader::load_unchecked(&self.data) }
+    }
+}
+
+/// A market account fetched to read the first `depth` levels of each side of its book.
+///
+/// Orders are scattered across sectors, so the whole account is still fetched, but only the
+/// levels walked from each side's head are ever read.
+pub struct MarketBookData {
+    data: Vec<u8>,
+    depth: usize,
+}
+
+impl MarketBookData {
+    pub fn header(&self) -> &MarketHeader {
+        // Safety: `fetch_top_of_book` checked that the data is at least `MarketHeader::LEN` bytes.
+        unsafe { MarketHeader::load_unchecked(&self.data[..MarketHeader::LEN]) }
+    }
+
+    /// The best bids, highest price first.
+    pub fn bids(&self) -> BookIter<'_> {
+        self.iter_from(self.header().bids_dll_head())
+    }
+
+    /// The best asks, lowest price first.
+    pub fn asks(&self) -> BookIter<'_> {
+        self.iter_from(self.header().asks_dll_head())
+    }
+
+    fn iter_from(&self, head: SectorIndex) -> BookIter<'_> {
+        BookIter {
+            curr: head,
+            sectors: &self.data[MarketHeader::LEN..],
+            remaining: self.depth,
+        }
+    }
+}
+
+/// An order borrowed from fetched market data.
+#[derive(Clone, Copy, Debug)]
+pub struct OrderRef<'a> {
+    pub index: SectorIndex,
+    pub order: &'a Order,
+}
+
+/// Walks a side of the book over raw sector bytes, stopping after the requested depth.
+///
+/// Unlike [`dropset_interface::state::linked_list::LinkedListIter`], every index is bounds
+/// checked, since the bytes came from an RPC node rather than the program's own account.
+pub struct BookIter<'a> {
+    curr: SectorIndex,
+    sectors: &'a [u8],
+    remaining: usize,
+}
+
+impl<'a> Iterator for BookIter<'a> {
+    type Item = OrderRef<'a>;
+
+    fn next(&mut self) -> Option<OrderRef<'a>> {
+        if self.remaining == 0 || self.curr == NIL {
+            return None;
+        }
+        Sector::check_in_bounds(self.sectors, self.curr).ok()?;
+
+        // Safety: `self.curr` was just checked to be in-bounds.
+        let sector = unsafe { Sector::from_sector_index(self.sectors, self.curr) };
+        let order = OrderRef {
+            index: self.curr,
+            order: sector.load_payload::<Order>(),
+        };
+
+        self.curr = sector.next();
+        self.remaining -= 1;
+        Some(order)
+    }
+}
+
+/// A single seat sector, fetched with a data slice at its sector index.
+pub struct MarketSeatData {
+    index: SectorIndex,
+    data: Vec<u8>,
+}
+
+impl MarketSeatData {
+    pub fn index(&self) -> SectorIndex {
+        self.index
+    }
+
+    pub fn seat(&self) -> &MarketSeat {
+        self.sector().load_payload::<MarketSeat>()
+    }
+
+    /// The seat's sector, for its neighbors in the seats list.
+    pub fn sector(&self) -> &Sector {
+        // Safety: `fetch_seat` checked that the data is exactly `Sector::LEN` bytes, and all bit
+        // patterns are valid for a `Sector`.
+        unsafe { Sector::load_unchecked(&self.data) }
+    }
+}
+
+/// Fetches only a market's header.
+pub async fn fetch_header(rpc: &RpcClient, market: &Address) -> anyhow::Result<MarketHeaderData> {
+    let data = fetch_market_data(rpc, market, Some((0, MarketHeader::LEN))).await?;
+    if data.len() != MarketHeader::LEN {
+        bail!("Market account is uninitialized");
+    }
+    let header = MarketHeaderData { data };
+    if header.header().discriminant() != MARKET_ACCOUNT_DISCRIMINANT {
+        bail!("Account isn't a market");
+    }
+
+    Ok(header)
+}
+
+/// Fetches a market to read up to `depth` levels of each side of its book.
+pub async fn fetch_top_of_book(
+    rpc: &RpcClient,
+    market: &Address,
+    depth: usize,
+) -> anyhow::Result<MarketBookData> {
+    let data = fetch_market_data(rpc, market, None).await?;
+    if data.len() < MarketHeader::LEN {
+        bail!("Market account is uninitialized");
+    }
+    let book = MarketBookData { data, depth };
+    if book.header().discriminant() != MARKET_ACCOUNT_DISCRIMINANT {
+        bail!("Account isn't a market");
+    }
+
+    Ok(book)
+}
+
+/// Fetches only the sector at `index_hint`, returning `None` if it's out of bounds or isn't
+/// `user`'s seat, e.g. because the hint is stale.
+pub async fn fetch_seat(
+    rpc: &RpcClient,
+    market: &Address,
+    index_hint: SectorIndex,
+    user: &Address,
+) -> anyhow::Result<Option<MarketSeatData>> {
+    if index_hint == NIL {
+        return Ok(None);
+    }
+    let offset = MarketHeader::LEN + index_hint as usize * Sector::LEN;
+    let data = fetch_market_data(rpc, market, Some((offset, Sector::LEN))).await?;
+
+    Ok(seat_from_slice(index_hint, data, user))
+}
+
+/// Checks that a fetched sector slice is `user`'s seat.
+fn seat_from_slice(index: SectorIndex, data: Vec<u8>, user: &Address) -> Option<MarketSeatData> {
+    // Slices past the end of the account come back short or empty.
+    if data.len() != Sector::LEN {
+        return None;
+    }
+
+    let seat = MarketSeatData { index, data };
+    (&seat.seat().user == user).then_some(seat)
+}
+
+/// Fetches a market account's data, or the `(offset, length)` slice of it, after checking the
+/// account is owned by the `dropset` program.
+async fn fetch_market_data(
+    rpc: &RpcClient,
+    market: &Address,
+    slice: Option<(usize, usize)>,
+) -> anyhow::Result<Vec<u8>> {
+    let account = rpc
+        .get_account_with_config(
+            market,
+            RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice: slice.map(|(offset, length)| UiDataSliceConfig { offset, length }),
+                commitment: Some(rpc.commitment()),
+                min_context_slot: None,
+            },
+        )
+        .await?
+        .value;
+    let Some(account) = account else {
+        bail!("Market account {market} doesn't exist");
+    };
+    if account.owner != dropset::ID {
+        bail!("Account isn't owned by dropset program");
+    }
+
+    Ok(account.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use dropset_interface::state::sector::SECTOR_SIZE;
+
+    use super::*;
+
+    /// Builds a market with one order sector per entry in `next`, where sector `i` links to
+    /// `next[i]`.
+    fn market_data(next: &[SectorIndex], bids_head: SectorIndex) -> Vec<u8> {
+        let mut data = vec![0u8; MarketHeader::LEN + next.len() * SECTOR_SIZE];
+        // Safety: `data` is long enough for the header and isn't borrowed elsewhere.
+        unsafe {
+            MarketHeader::init(
+                data.as_mut_ptr() as *mut MarketHeader,
+                0,
+                &Address::new_unique(),
+                &Address::new_unique(),
+            );
+            MarketHeader::load_unchecked_mut(&mut data[..MarketHeader::LEN])
+                .set_bids_dll_head(bids_head);
+        }
+        let sectors = &mut data[MarketHeader::LEN..];
+        for (index, next) in next.iter().enumerate() {
+            // Safety: `index` is less than the number of sectors.
+            let sector = unsafe { Sector::from_sector_index_mut(sectors, index as SectorIndex) };
+            sector.set_next(*next);
+            sector
+                .load_payload_mut::<Order>()
+                .set_base_remaining(index as u64 + 1);
+        }
+        data
+    }
+
+    fn bid_indices(data: Vec<u8>, depth: usize) -> Vec<SectorIndex> {
+        MarketBookData { data, depth }
+            .bids()
+            .map(|order| order.index)
+            .collect()
+    }
+
+    #[test]
+    fn walks_book_to_depth() {
+        let data = market_data(&[1, 2, NIL], 0);
+        assert_eq!(bid_indices(data.clone(), 10), [0, 1, 2]);
+        assert_eq!(bid_indices(data.clone(), 2), [0, 1]);
+
+        let book = MarketBookData { data, depth: 1 };
+        let best = book.bids().next().unwrap();
+        assert_eq!(best.order.base_remaining(), 1);
+        assert_eq!(book.asks().count(), 0);
+    }
+
+    #[test]
+    fn stops_at_out_of_range_indices() {
+        assert!(bid_indices(market_data(&[1, NIL], 2), 10).is_empty());
+        assert!(bid_indices(market_data(&[1, NIL], 7), 10).is_empty());
+        // A link past the last sector ends the walk after the orders before it.
+        assert_eq!(bid_indices(market_data(&[1, 5], 0), 10), [0, 1]);
+    }
+
+    #[test]
+    fn cycles_are_truncated_by_depth() {
+        assert_eq!(bid_indices(market_data(&[1, 0], 0), 5), [0, 1, 0, 1, 0]);
+    }
+
+    #[test]
+    fn seat_hint_must_match_user() {
+        let user = Address::new_unique();
+        let mut sector = vec![0u8; Sector::LEN];
+        // Safety: `sector` is exactly one sector long.
+        unsafe { Sector::from_sector_index_mut(&mut sector, 0) }
+            .set_payload(MarketSeat::new(user, 10, 20).as_bytes());
+
+        let seat = seat_from_slice(3, sector.clone(), &user).unwrap();
+        assert_eq!(seat.index(), 3);
+        assert_eq!(seat.seat().base_available(), 10);
+
+        // A stale hint pointing at someone else's seat.
+        assert!(seat_from_slice(3, sector.clone(), &Address::new_unique()).is_none());
+        // Hints past the end of the account fetch short or empty slices.
+        assert!(seat_from_slice(3, sector[..Sector::LEN / 2].to_vec(), &user).is_none());
+        assert!(seat_from_slice(3, vec![], &user).is_none());
+    }
+}
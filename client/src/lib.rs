@@ -2,11 +2,21 @@
 //!
 //! Includes context helpers, pretty-printing utilities, and PDA derivations.
 
+pub mod backfill;
 pub mod context;
+pub mod dropset_client;
 pub mod e2e_helpers;
+pub mod fetch;
 pub mod logs;
+pub mod lookup_table;
+pub mod offline;
 pub mod pda;
+pub mod portfolio;
 pub mod pretty;
+pub mod quote;
+pub mod registry;
+pub mod sender;
+pub mod simulate;
 pub mod single_signer_instruction;
 pub mod transactions;
 
@@ -0,0 +1,212 @@
+//! Helpers for creating, extending, and fetching address lookup tables.
+//!
+//! A v0 transaction references an address stored in a lookup table with a one byte index instead
+//! of the full 32 byte address, which is what lets several 12-account `dropset` instructions fit in
+//! a single transaction.
+
+use std::time::Duration;
+
+use anyhow::Context;
+use itertools::Itertools;
+use solana_address::Address;
+use solana_address_lookup_table_interface::{
+    instruction::{
+        create_lookup_table as create_lookup_table_instruction,
+        extend_lookup_table as extend_lookup_table_instruction,
+    },
+    state::AddressLookupTable,
+};
+use solana_commitment_config::CommitmentConfig;
+use solana_sdk::{
+    message::{
+        v0,
+        AddressLookupTableAccount,
+    },
+    signature::Keypair,
+    signer::Signer,
+};
+
+use crate::transactions::CustomRpcClient;
+
+/// The most addresses appended per extend transaction, which keeps each one well under the
+/// transaction size limit.
+const MAX_ADDRESSES_PER_EXTEND: usize = 20;
+
+const MAX_ACTIVATION_POLLS: u8 = 50;
+
+/// Creates a lookup table owned by `authority` holding `addresses`, and waits until the table can
+/// be used in transactions.
+pub async fn create_lookup_table(
+    rpc: &CustomRpcClient,
+    authority: &Keypair,
+    addresses: &[Address],
+) -> anyhow::Result<AddressLookupTableAccount> {
+    // The recent slot is only used to derive the table's address and must be in the slot hashes
+    // sysvar, so a finalized slot is always safe.
+    let recent_slot = rpc
+        .client
+        .get_slot_with_commitment(CommitmentConfig::finalized())
+        .await
+        .context("Failed to fetch a recent slot")?;
+    let (create, table) =
+        create_lookup_table_instruction(authority.pubkey(), authority.pubkey(), recent_slot);
+    rpc.send_single_signer(authority, [create]).await?;
+
+    extend_lookup_table(rpc, authority, table, addresses).await
+}
+
+/// Appends whichever of `addresses` the table doesn't already hold, and waits until the new
+/// entries can be used in transactions.
+pub async fn extend_lookup_table(
+    rpc: &CustomRpcClient,
+    authority: &Keypair,
+    table: Address,
+    addresses: &[Address],
+) -> anyhow::Result<AddressLookupTableAccount> {
+    let existing = fetch_lookup_table(rpc, &table).await?;
+    let missing = addresses
+        .iter()
+        .copied()
+        .unique()
+        .filter(|address| !existing.addresses.contains(address))
+        .collect_vec();
+
+    for chunk in missing.chunks(MAX_ADDRESSES_PER_EXTEND) {
+        let extend = extend_lookup_table_instruction(
+            table,
+            authority.pubkey(),
+            Some(authority.pubkey()),
+            chunk.to_vec(),
+        );
+        rpc.send_single_signer(authority, [extend]).await?;
+    }
+
+    let (table_account, last_extended_slot) = fetch_lookup_table_with_slot(rpc, &table).await?;
+    if !missing.is_empty() {
+        wait_for_slot_after(rpc, last_extended_slot).await?;
+    }
+
+    Ok(table_account)
+}
+
+/// Fetches a lookup table's addresses so it can be passed to v0 message compilation.
+pub async fn fetch_lookup_table(
+    rpc: &CustomRpcClient,
+    table: &Address,
+) -> anyhow::Result<AddressLookupTableAccount> {
+    Ok(fetch_lookup_table_with_slot(rpc, table).await?.0)
+}
+
+async fn fetch_lookup_table_with_slot(
+    rpc: &CustomRpcClient,
+    table: &Address,
+) -> anyhow::Result<(AddressLookupTableAccount, u64)> {
+    let account = rpc
+        .client
+        .get_account(table)
+        .await
+        .with_context(|| format!("Failed to fetch lookup table {table}"))?;
+    let state = AddressLookupTable::deserialize(&account.data)
+        .map_err(|e| anyhow::anyhow!("Invalid lookup table {table}: {e}"))?;
+
+    Ok((
+        AddressLookupTableAccount {
+            key: *table,
+            addresses: state.addresses.to_vec(),
+        },
+        state.meta.last_extended_slot,
+    ))
+}
+
+/// Addresses appended to a table can only be looked up once the slot they were appended in has
+/// passed.
+async fn wait_for_slot_after(rpc: &CustomRpcClient, slot: u64) -> anyhow::Result<()> {
+    for _ in 0..MAX_ACTIVATION_POLLS {
+        if rpc.client.get_slot().await? > slot {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(400)).await;
+    }
+
+    anyhow::bail!("Lookup table entries weren't activated after slot {slot}")
+}
+
+/// Resolves the full account key list of a v0 message: its static keys followed by the writable
+/// and then the readonly addresses it loads from `tables`. Returns `None` if a lookup refers to a
+/// table or index that isn't in `tables`.
+pub(crate) fn resolve_account_keys(
+    message: &v0::Message,
+    tables: &[AddressLookupTableAccount],
+) -> Option<Vec<Address>> {
+    let lookup = |key: &Address, indexes: &[u8]| -> Option<Vec<Address>> {
+        let table = tables.iter().find(|table| table.key == *key)?;
+        indexes
+            .iter()
+            .map(|index| table.addresses.get(*index as usize).copied())
+            .collect()
+    };
+
+    let mut writable = vec![];
+    let mut readonly = vec![];
+    for table_lookup in message.address_table_lookups.iter() {
+        writable.extend(lookup(
+            &table_lookup.account_key,
+            &table_lookup.writable_indexes,
+        )?);
+        readonly.extend(lookup(
+            &table_lookup.account_key,
+            &table_lookup.readonly_indexes,
+        )?);
+    }
+
+    Some([message.account_keys.clone(), writable, readonly].concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_instruction::{
+        AccountMeta,
+        Instruction,
+    };
+    use solana_sdk::{
+        hash::Hash,
+        message::VersionedMessage,
+    };
+
+    use super::*;
+    use crate::sender::compile_message;
+
+    #[test]
+    fn resolves_loaded_account_keys() {
+        let payer = Address::new_unique();
+        let program = Address::new_unique();
+        let shared = (0..4).map(|_| Address::new_unique()).collect_vec();
+        let table = AddressLookupTableAccount {
+            key: Address::new_unique(),
+            addresses: shared.clone(),
+        };
+        let instruction = Instruction::new_with_bytes(
+            program,
+            &[],
+            vec![
+                AccountMeta::new(payer, true),
+                AccountMeta::new_readonly(shared[0], false),
+                AccountMeta::new(shared[2], false),
+                AccountMeta::new_readonly(shared[3], false),
+            ],
+        );
+
+        let message =
+            compile_message(&payer, &[instruction], &[table.clone()], Hash::default()).unwrap();
+        let VersionedMessage::V0(message) = message else {
+            panic!("Expected a v0 message");
+        };
+
+        // The loaded writable addresses come before the loaded readonly ones.
+        assert_eq!(
+            resolve_account_keys(&message, &[table]).unwrap(),
+            vec![payer, program, shared[2], shared[0], shared[3]]
+        );
+        assert!(resolve_account_keys(&message, &[]).is_none());
+    }
+}
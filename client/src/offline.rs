@@ -0,0 +1,370 @@
+//! Offline and multisig signing. An [`OfflineTransaction`] is built online, exported as base64
+//! to signers that may never touch the network, and submitted with [`submit`] once every
+//! signature is in.
+//!
+//! A cold wallet usually takes longer to sign than a blockhash lives, so [`Lifetime::Nonce`]
+//! builds the transaction against a durable nonce instead.
+
+use std::fmt::Display;
+
+use base64::{
+    prelude::BASE64_STANDARD,
+    Engine,
+};
+use solana_address::Address;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_nonce::{
+    state::State,
+    versions::Versions,
+};
+use solana_sdk::{
+    hash::Hash,
+    message::{
+        AddressLookupTableAccount,
+        CompileError,
+        Instruction,
+        VersionedMessage,
+    },
+    signature::{
+        Keypair,
+        Signature,
+    },
+    signer::{
+        Signer,
+        SignerError,
+    },
+    transaction::VersionedTransaction,
+};
+use solana_system_interface::instruction::{
+    advance_nonce_account,
+    create_nonce_account,
+    SystemInstruction,
+};
+
+use crate::sender::{
+    compile_message,
+    send_until_confirmed,
+    Confirmation,
+    SendError,
+    SenderConfig,
+};
+
+/// What keeps a transaction valid until it's submitted.
+#[derive(Clone, Copy, Debug)]
+pub enum Lifetime {
+    /// The latest blockhash, which expires after about 150 blocks.
+    Blockhash,
+    /// The blockhash stored in a durable nonce account, which stays valid until the nonce is
+    /// advanced. An instruction advancing the nonce is prepended, so `authority` must sign too.
+    Nonce {
+        account: Address,
+        authority: Address,
+    },
+}
+
+#[derive(Debug)]
+pub enum OfflineError {
+    /// The address isn't one of the transaction's signers.
+    UnknownSigner(Address),
+    /// The signature doesn't verify against the transaction's message for the address.
+    InvalidSignature(Address),
+    /// The transaction can't be submitted until these signers have signed.
+    MissingSignatures(Vec<Address>),
+    Signing(SignerError),
+    /// The exported transaction isn't valid base64 or doesn't deserialize.
+    Decode(String),
+}
+
+impl Display for OfflineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownSigner(address) => write!(f, "{address} isn't a required signer"),
+            Self::InvalidSignature(address) => write!(f, "Invalid signature for {address}"),
+            Self::MissingSignatures(addresses) => {
+                write!(f, "Missing signatures from ")?;
+                for (i, address) in addresses.iter().enumerate() {
+                    match i {
+                        0 => write!(f, "{address}")?,
+                        _ => write!(f, ", {address}")?,
+                    }
+                }
+                Ok(())
+            }
+            Self::Signing(e) => write!(f, "Failed to sign transaction: {e}"),
+            Self::Decode(e) => write!(f, "Failed to decode transaction: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for OfflineError {}
+
+impl From<SignerError> for OfflineError {
+    fn from(e: SignerError) -> Self {
+        Self::Signing(e)
+    }
+}
+
+/// A transaction whose signatures are collected one signer at a time. Missing signatures are
+/// left as [`Signature::default`], so a partially signed transaction round trips through
+/// [`Self::to_base64`] and can be passed from one signer to the next.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OfflineTransaction {
+    transaction: VersionedTransaction,
+}
+
+impl OfflineTransaction {
+    /// Compiles an unsigned v0 transaction against `blockhash`. For a durable nonce, pass the
+    /// nonce's blockhash and put the [`advance_nonce_account`] instruction first.
+    pub fn new(
+        payer: &Address,
+        instructions: &[Instruction],
+        lookup_tables: &[AddressLookupTableAccount],
+        blockhash: Hash,
+    ) -> Result<Self, CompileError> {
+        let message = compile_message(payer, instructions, lookup_tables, blockhash)?;
+        let transaction = VersionedTransaction {
+            signatures: vec![
+                Signature::default();
+                message.header().num_required_signatures as usize
+            ],
+            message,
+        };
+
+        Ok(Self { transaction })
+    }
+
+    /// Fetches the blockhash for `lifetime` and compiles an unsigned transaction with it.
+    pub async fn build(
+        rpc: &RpcClient,
+        payer: &Address,
+        instructions: &[Instruction],
+        lookup_tables: &[AddressLookupTableAccount],
+        lifetime: Lifetime,
+    ) -> anyhow::Result<Self> {
+        match lifetime {
+            Lifetime::Blockhash => {
+                let blockhash = rpc.get_latest_blockhash().await?;
+                Ok(Self::new(payer, instructions, lookup_tables, blockhash)?)
+            }
+            Lifetime::Nonce { account, authority } => {
+                let blockhash = fetch_nonce_blockhash(rpc, &account).await?;
+                let instructions = [
+                    vec![advance_nonce_account(&account, &authority)],
+                    instructions.to_vec(),
+                ]
+                .concat();
+                Ok(Self::new(payer, &instructions, lookup_tables, blockhash)?)
+            }
+        }
+    }
+
+    /// The serialized message, i.e. the bytes each signer signs.
+    pub fn message_data(&self) -> Vec<u8> {
+        self.transaction.message.serialize()
+    }
+
+    pub fn message(&self) -> &VersionedMessage {
+        &self.transaction.message
+    }
+
+    /// The addresses that must sign, in signature order. The first is the fee payer.
+    pub fn signers(&self) -> &[Address] {
+        let keys = self.transaction.message.static_account_keys();
+        &keys[..self.transaction.signatures.len().min(keys.len())]
+    }
+
+    pub fn missing_signers(&self) -> Vec<Address> {
+        self.signers()
+            .iter()
+            .zip(&self.transaction.signatures)
+            .filter(|(_, signature)| **signature == Signature::default())
+            .map(|(signer, _)| *signer)
+            .collect()
+    }
+
+    /// Signs the message with `signer`, which works offline since the blockhash is already set.
+    pub fn sign(&mut self, signer: &impl Signer) -> Result<Signature, OfflineError> {
+        let signature = signer.try_sign_message(&self.message_data())?;
+        self.add_signature(&signer.pubkey(), signature)?;
+
+        Ok(signature)
+    }
+
+    /// Adds a signature produced elsewhere, e.g. by a hardware wallet, after verifying it.
+    pub fn add_signature(
+        &mut self,
+        signer: &Address,
+        signature: Signature,
+    ) -> Result<(), OfflineError> {
+        let index = self
+            .signers()
+            .iter()
+            .position(|address| address == signer)
+            .ok_or(OfflineError::UnknownSigner(*signer))?;
+        if !signature.verify(signer.as_ref(), &self.message_data()) {
+            return Err(OfflineError::InvalidSignature(*signer));
+        }
+        self.transaction.signatures[index] = signature;
+
+        Ok(())
+    }
+
+    /// The fully signed transaction, ready for [`submit`].
+    pub fn into_signed(self) -> Result<VersionedTransaction, OfflineError> {
+        match self.missing_signers() {
+            missing if missing.is_empty() => Ok(self.transaction),
+            missing => Err(OfflineError::MissingSignatures(missing)),
+        }
+    }
+
+    pub fn to_base64(&self) -> String {
+        let bytes = bincode::serialize(&self.transaction).expect("Should serialize transaction");
+        BASE64_STANDARD.encode(bytes)
+    }
+
+    /// Decodes a transaction exported with [`Self::to_base64`], keeping its existing signatures.
+    pub fn from_base64(encoded: &str) -> Result<Self, OfflineError> {
+        let bytes = BASE64_STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| OfflineError::Decode(e.to_string()))?;
+        let transaction: VersionedTransaction =
+            bincode::deserialize(&bytes).map_err(|e| OfflineError::Decode(e.to_string()))?;
+        if transaction.signatures.len()
+            != transaction.message.header().num_required_signatures as usize
+        {
+            return Err(OfflineError::Decode(
+                "Signature count doesn't match the message header".into(),
+            ));
+        }
+
+        Ok(Self { transaction })
+    }
+}
+
+/// Sends a signed transaction and waits for it to be confirmed, resending it every
+/// [`SenderConfig::resend_interval`]. Unlike [`crate::sender::TransactionSender`], it can't be
+/// re-signed with a fresh blockhash, so it fails with [`SendError::Expired`] as soon as its
+/// blockhash expires or its nonce is advanced by another transaction.
+pub async fn submit(
+    rpc: &RpcClient,
+    transaction: &VersionedTransaction,
+    config: &SenderConfig,
+) -> Result<Signature, SendError> {
+    let signature = transaction.signatures[0];
+    let nonce = nonce_account(&transaction.message);
+
+    let blockhash = transaction.message.recent_blockhash();
+    let confirmation = send_until_confirmed(rpc, transaction, config, move || async move {
+        Ok(match nonce {
+            Some(account) => fetch_nonce_blockhash(rpc, &account)
+                .await
+                .is_ok_and(|current| current != *blockhash),
+            None => !rpc.is_blockhash_valid(blockhash, config.commitment).await?,
+        })
+    })
+    .await?;
+    match confirmation {
+        Confirmation::Confirmed => Ok(signature),
+        Confirmation::Expired => Err(SendError::Expired {
+            signature,
+            attempts: 1,
+        }),
+    }
+}
+
+/// Creates a durable nonce account controlled by `authority`, funded with its rent exemption by
+/// `payer`.
+pub async fn create_nonce(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    authority: &Address,
+) -> anyhow::Result<Address> {
+    let nonce = Keypair::new();
+    let lamports = rpc
+        .get_minimum_balance_for_rent_exemption(State::size())
+        .await?;
+    let instructions = create_nonce_account(&payer.pubkey(), &nonce.pubkey(), authority, lamports);
+    let blockhash = rpc.get_latest_blockhash().await?;
+    let mut transaction = OfflineTransaction::new(&payer.pubkey(), &instructions, &[], blockhash)?;
+    transaction.sign(payer)?;
+    transaction.sign(&nonce)?;
+    rpc.send_and_confirm_transaction(&transaction.into_signed()?)
+        .await?;
+
+    Ok(nonce.pubkey())
+}
+
+/// The blockhash currently stored in an initialized nonce account.
+pub async fn fetch_nonce_blockhash(rpc: &RpcClient, account: &Address) -> anyhow::Result<Hash> {
+    let account = rpc.get_account(account).await?;
+    let versions: Versions = bincode::deserialize(&account.data)?;
+    match versions.state() {
+        State::Initialized(data) => Ok(data.blockhash()),
+        State::Uninitialized => anyhow::bail!("Nonce account isn't initialized"),
+    }
+}
+
+/// The nonce account a transaction advances, if its first instruction advances one.
+fn nonce_account(message: &VersionedMessage) -> Option<Address> {
+    let instruction = message.instructions().first()?;
+    let keys = message.static_account_keys();
+    if keys.get(instruction.program_id_index as usize)? != &solana_system_interface::program::ID {
+        return None;
+    }
+
+    match bincode::deserialize(&instruction.data) {
+        Ok(SystemInstruction::AdvanceNonceAccount) => {
+            keys.get(*instruction.accounts.first()? as usize).copied()
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_instruction::AccountMeta;
+
+    use super::*;
+
+    #[test]
+    fn collects_signatures_across_exports() {
+        let payer = Keypair::new();
+        let cold = Keypair::new();
+        let instruction = Instruction::new_with_bytes(
+            Address::new_unique(),
+            &[1],
+            vec![AccountMeta::new_readonly(cold.pubkey(), true)],
+        );
+        let mut transaction = OfflineTransaction::new(
+            &payer.pubkey(),
+            &[instruction],
+            &[],
+            Hash::new_from_array([7; 32]),
+        )
+        .unwrap();
+        assert_eq!(transaction.signers(), &[payer.pubkey(), cold.pubkey()]);
+
+        transaction.sign(&payer).unwrap();
+        let exported = transaction.to_base64();
+
+        // The cold wallet only sees the exported transaction.
+        let mut imported = OfflineTransaction::from_base64(&exported).unwrap();
+        assert_eq!(imported, transaction);
+        assert_eq!(imported.missing_signers(), vec![cold.pubkey()]);
+        assert!(matches!(
+            imported.clone().into_signed(),
+            Err(OfflineError::MissingSignatures(_))
+        ));
+
+        let wrong = payer.sign_message(&imported.message_data());
+        assert!(matches!(
+            imported.add_signature(&cold.pubkey(), wrong),
+            Err(OfflineError::InvalidSignature(_))
+        ));
+
+        let signature = cold.sign_message(&imported.message_data());
+        imported.add_signature(&cold.pubkey(), signature).unwrap();
+        let signed = imported.into_signed().unwrap();
+        assert_eq!(signed.signatures[1], signature);
+    }
+}
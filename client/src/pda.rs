@@ -2,6 +2,9 @@
 
 use solana_address::Address;
 
+/// The seed for a transfer hook program's extra-account-metas PDA.
+const EXTRA_ACCOUNT_METAS_SEED: &[u8] = b"extra-account-metas";
+
 pub fn find_market_address(base_mint: &Address, quote_mint: &Address) -> (Address, u8) {
     Address::find_program_address(
         &[
@@ -12,3 +15,9 @@ pub fn find_market_address(base_mint: &Address, quote_mint: &Address) -> (Addres
         &dropset::ID,
     )
 }
+
+/// Derives the transfer hook program's extra-account-metas PDA for a mint, as defined by the
+/// `spl_transfer_hook_interface`.
+pub fn find_extra_account_metas_address(mint: &Address, hook_program: &Address) -> (Address, u8) {
+    Address::find_program_address(&[EXTRA_ACCOUNT_METAS_SEED, mint.as_ref()], hook_program)
+}
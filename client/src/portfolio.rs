@@ -0,0 +1,552 @@
+//! See [`Portfolio`].
+
+use std::collections::HashMap;
+
+use dropset_interface::state::{
+    market::MarketRef,
+    market_header::MarketHeader,
+    market_seat::MarketSeat,
+    order::Order,
+    sector::{
+        Sector,
+        SectorIndex,
+    },
+    transmutable::Transmutable,
+};
+use rust_decimal::Decimal;
+use solana_address::Address;
+
+use crate::{
+    context::market::BookSide,
+    registry::{
+        MarketEntry,
+        MarketRegistry,
+        MintInfo,
+        MAX_MULTIPLE_ACCOUNTS,
+    },
+    transactions::CustomRpcClient,
+};
+
+/// An amount of a single mint in atoms, split into what's free to withdraw and what's locked as
+/// collateral for resting orders.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Balance {
+    pub free: u64,
+    pub locked: u64,
+}
+
+impl Balance {
+    pub fn total(&self) -> u64 {
+        self.free.saturating_add(self.locked)
+    }
+
+    fn add(&mut self, other: Balance) {
+        self.free = self.free.saturating_add(other.free);
+        self.locked = self.locked.saturating_add(other.locked);
+    }
+}
+
+/// A resting order with its price in quote tokens per base token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PortfolioOrder {
+    pub side: BookSide,
+    pub price: Decimal,
+    pub encoded_price: u32,
+    pub base_remaining: u64,
+    pub quote_remaining: u64,
+}
+
+/// A user's seat on a single market.
+#[derive(Clone, Debug)]
+pub struct MarketPosition {
+    pub market: MarketEntry,
+    pub seat_index: SectorIndex,
+    /// Free base plus the base locked by asks.
+    pub base: Balance,
+    /// Free quote plus the quote locked by bids.
+    pub quote: Balance,
+    /// Bids first, then asks, each best price first.
+    pub orders: Vec<PortfolioOrder>,
+    /// The midpoint of the best bid and ask in quote tokens per base token, if both sides have
+    /// orders.
+    pub mid_price: Option<Decimal>,
+}
+
+/// A mint's balance summed over every market, and its value in the valuation mint.
+#[derive(Clone, Debug)]
+pub struct MintBalance {
+    pub mint: MintInfo,
+    pub balance: Balance,
+    /// The balance's value in whole valuation mint tokens, or `None` if there's no market pairing
+    /// the mint with the valuation mint that has a mid price.
+    pub value: Option<Decimal>,
+}
+
+/// A resting order whose encoded price can't be decoded. Its market is left unpriced and the order
+/// is left out of the position's orders, though its collateral is still counted as locked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidOrder {
+    pub market: Address,
+    pub order_index: SectorIndex,
+    pub encoded_price: u32,
+}
+
+/// Every seat a user holds across all markets, with balances per mint valued in a single quote
+/// mint.
+///
+/// A seat can sit anywhere in a market's sectors, so every market in the registry is fetched
+/// to find the user's seats. The same fetch provides the mid prices used for valuation.
+#[derive(Clone, Debug)]
+pub struct Portfolio {
+    pub user: Address,
+    pub valuation_mint: Address,
+    pub positions: Vec<MarketPosition>,
+    /// Sorted by mint address.
+    pub mints: Vec<MintBalance>,
+    /// Orders skipped while pricing markets and positions.
+    pub invalid_orders: Vec<InvalidOrder>,
+}
+
+impl Portfolio {
+    pub async fn load(
+        rpc: &CustomRpcClient,
+        registry: &MarketRegistry,
+        user: &Address,
+        valuation_mint: &Address,
+    ) -> anyhow::Result<Self> {
+        let entries = registry.markets();
+        let mut positions = vec![];
+        let mut mid_prices = vec![];
+        let mut invalid_orders = vec![];
+
+        for chunk in entries.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let addresses = chunk.iter().map(|entry| entry.market).collect::<Vec<_>>();
+            let accounts = rpc.client.get_multiple_accounts(&addresses).await?;
+            for (entry, account) in chunk.iter().zip(accounts) {
+                let Some(account) = account else {
+                    continue;
+                };
+                if account.owner != dropset::ID || account.data.len() < MarketHeader::LEN {
+                    continue;
+                }
+
+                // Safety: The account data was just checked to be at least `MarketHeader::LEN`
+                // bytes.
+                let market = unsafe { MarketRef::from_bytes(&account.data) };
+                if !market.is_initialized() {
+                    continue;
+                }
+                let mid_price = match mid_price(entry, &market) {
+                    Ok(mid_price) => mid_price,
+                    Err(invalid_order) => {
+                        invalid_orders.push(invalid_order);
+                        None
+                    }
+                };
+                if let Some(mid_price) = mid_price {
+                    mid_prices.push((entry, mid_price));
+                }
+                if let Some(position) =
+                    position(entry, &market, user, mid_price, &mut invalid_orders)
+                {
+                    positions.push(position);
+                }
+            }
+        }
+
+        let mut balances: HashMap<Address, (MintInfo, Balance)> = HashMap::new();
+        for position in positions.iter() {
+            for (mint, balance) in [
+                (position.market.base, position.base),
+                (position.market.quote, position.quote),
+            ] {
+                balances
+                    .entry(mint.address)
+                    .or_insert((mint, Balance::default()))
+                    .1
+                    .add(balance);
+            }
+        }
+        let mut mints = balances
+            .into_values()
+            .map(|(mint, balance)| MintBalance {
+                mint,
+                balance,
+                value: value_in(&mint, balance.total(), valuation_mint, &mid_prices),
+            })
+            .collect::<Vec<_>>();
+        mints.sort_by_key(|mint| mint.mint.address);
+
+        Ok(Self {
+            user: *user,
+            valuation_mint: *valuation_mint,
+            positions,
+            mints,
+            invalid_orders,
+        })
+    }
+
+    /// The summed value of every mint balance that could be valued, in whole valuation mint
+    /// tokens.
+    pub fn total_value(&self) -> Decimal {
+        self.mints.iter().filter_map(|mint| mint.value).sum()
+    }
+
+    /// Mint balances left out of [`Self::total_value`] since they have no price.
+    pub fn unpriced(&self) -> impl Iterator<Item = &MintBalance> {
+        self.mints.iter().filter(|mint| mint.value.is_none())
+    }
+}
+
+/// The user's position on a market, or `None` if they don't have a seat on it. Orders with prices
+/// that can't be decoded are added to `invalid_orders` instead of the position's orders.
+fn position(
+    entry: &MarketEntry,
+    market: &MarketRef<'_>,
+    user: &Address,
+    mid_price: Option<Decimal>,
+    invalid_orders: &mut Vec<InvalidOrder>,
+) -> Option<MarketPosition> {
+    let Some((seat_index, seat)) = market
+        .iter_seats()
+        .map(|(index, sector)| (index, sector.load_payload::<MarketSeat>()))
+        .find(|(_, seat)| &seat.user == user)
+    else {
+        return None;
+    };
+
+    let mut base = Balance {
+        free: seat.base_available(),
+        locked: 0,
+    };
+    let mut quote = Balance {
+        free: seat.quote_available(),
+        locked: 0,
+    };
+    let mut orders = vec![];
+    let bids = market.iter_bids().map(|order| (BookSide::Bid, order));
+    let asks = market.iter_asks().map(|order| (BookSide::Ask, order));
+    for (side, (order_index, sector)) in bids.chain(asks) {
+        let order = sector.load_payload::<Order>();
+        if order.user_seat() != seat_index {
+            continue;
+        }
+
+        // Bids lock quote as collateral and asks lock base.
+        match side {
+            BookSide::Bid => quote.locked = quote.locked.saturating_add(order.quote_remaining()),
+            BookSide::Ask => base.locked = base.locked.saturating_add(order.base_remaining()),
+        }
+        let Ok(price) = entry.to_decimal_price(order.encoded_price()) else {
+            let invalid_order = InvalidOrder {
+                market: entry.market,
+                order_index,
+                encoded_price: order.encoded_price(),
+            };
+            // The market's best orders were already checked for the mid price.
+            if !invalid_orders.contains(&invalid_order) {
+                invalid_orders.push(invalid_order);
+            }
+            continue;
+        };
+        orders.push(PortfolioOrder {
+            side,
+            price,
+            encoded_price: order.encoded_price(),
+            base_remaining: order.base_remaining(),
+            quote_remaining: order.quote_remaining(),
+        });
+    }
+
+    Some(MarketPosition {
+        market: entry.clone(),
+        seat_index,
+        base,
+        quote,
+        orders,
+        mid_price,
+    })
+}
+
+/// The market's mid price, or the best order whose price can't be decoded.
+fn mid_price(entry: &MarketEntry, market: &MarketRef<'_>) -> Result<Option<Decimal>, InvalidOrder> {
+    let best = |order: Option<(SectorIndex, &Sector)>| {
+        order
+            .map(|(order_index, sector)| {
+                let encoded_price = sector.load_payload::<Order>().encoded_price();
+                entry
+                    .to_decimal_price(encoded_price)
+                    .map_err(|_| InvalidOrder {
+                        market: entry.market,
+                        order_index,
+                        encoded_price,
+                    })
+            })
+            .transpose()
+    };
+    let (Some(bid), Some(ask)) = (
+        best(market.iter_bids().next())?,
+        best(market.iter_asks().next())?,
+    ) else {
+        return Ok(None);
+    };
+
+    Ok(Some((bid + ask) / Decimal::TWO))
+}
+
+/// Values `amount` atoms of `mint` in whole `valuation_mint` tokens with the mid price of a market
+/// pairing the two directly, in either direction.
+fn value_in(
+    mint: &MintInfo,
+    amount: u64,
+    valuation_mint: &Address,
+    mid_prices: &[(&MarketEntry, Decimal)],
+) -> Option<Decimal> {
+    let mut tokens = Decimal::from(amount);
+    tokens.set_scale(mint.decimals as u32).ok()?;
+    if &mint.address == valuation_mint {
+        return Some(tokens);
+    }
+
+    mid_prices.iter().find_map(
+        |(entry, mid)| match (entry.base.address, entry.quote.address) {
+            (base, quote) if base == mint.address && &quote == valuation_mint => Some(tokens * mid),
+            (base, quote) if &base == valuation_mint && quote == mint.address && !mid.is_zero() => {
+                Some(tokens / mid)
+            }
+            _ => None,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use dropset_interface::state::sector::{
+        NIL,
+        PAYLOAD_SIZE,
+        SECTOR_SIZE,
+    };
+    use price::{
+        client_helpers::to_order_info_args,
+        to_order_info,
+    };
+    use rust_decimal::dec;
+
+    use super::*;
+
+    fn mint(decimals: u8) -> MintInfo {
+        MintInfo {
+            address: Address::new_unique(),
+            token_program: spl_token_interface::ID,
+            decimals,
+        }
+    }
+
+    fn entry(base: MintInfo, quote: MintInfo) -> MarketEntry {
+        MarketEntry {
+            market: Address::new_unique(),
+            base,
+            quote,
+            num_seats: 1,
+            num_bids: 1,
+            num_asks: 1,
+            layout_version: 1,
+        }
+    }
+
+    #[test]
+    fn values_balances_through_direct_pairs() {
+        let (sol, usdc, bonk) = (mint(9), mint(6), mint(5));
+        let sol_usdc = entry(sol, usdc);
+        let usdc_bonk = entry(usdc, bonk);
+        let mids = [(&sol_usdc, dec!(150)), (&usdc_bonk, dec!(40000))];
+
+        assert_eq!(
+            value_in(&usdc, 2_500_000, &usdc.address, &mids),
+            Some(dec!(2.5))
+        );
+        // 2 SOL at 150 USDC each.
+        assert_eq!(
+            value_in(&sol, 2_000_000_000, &usdc.address, &mids),
+            Some(dec!(300))
+        );
+        // 80,000 BONK at 40,000 BONK per USDC, valued through the inverted pair.
+        assert_eq!(
+            value_in(&bonk, 8_000_000_000, &usdc.address, &mids),
+            Some(dec!(2))
+        );
+        assert_eq!(value_in(&sol, 1, &bonk.address, &mids), None);
+    }
+
+    const USER_SEAT: SectorIndex = 0;
+    const OTHER_SEAT: SectorIndex = 1;
+
+    fn order(price: Decimal, base_atoms: u64, user_seat: SectorIndex) -> [u8; PAYLOAD_SIZE] {
+        let order_info = to_order_info(to_order_info_args(price, base_atoms).unwrap()).unwrap();
+        *Order::new(order_info, user_seat).as_bytes()
+    }
+
+    /// An order whose encoded price of zero has an invalid price mantissa.
+    fn undecodable_order(base_atoms: u64, user_seat: SectorIndex) -> [u8; PAYLOAD_SIZE] {
+        let mut order = order(dec!(1), base_atoms, user_seat);
+        order[..4].copy_from_slice(&0u32.to_le_bytes());
+        order
+    }
+
+    /// Builds a market with two seats in sectors 0 and 1, the user's and someone else's, followed
+    /// by the bids and then the asks, each linked best price first.
+    fn market_data(
+        user: &Address,
+        bids: &[[u8; PAYLOAD_SIZE]],
+        asks: &[[u8; PAYLOAD_SIZE]],
+    ) -> Vec<u8> {
+        let seats = [
+            *MarketSeat::new(*user, 10, 20).as_bytes(),
+            *MarketSeat::new(Address::new_unique(), 30, 40).as_bytes(),
+        ];
+        let lists = [&seats[..], bids, asks];
+        let num_sectors = lists.iter().map(|list| list.len()).sum::<usize>();
+        let mut data = vec![0u8; MarketHeader::LEN + num_sectors * SECTOR_SIZE];
+        // Safety: `data` is long enough for the header and isn't borrowed elsewhere.
+        unsafe {
+            MarketHeader::init(
+                data.as_mut_ptr() as *mut MarketHeader,
+                0,
+                &Address::new_unique(),
+                &Address::new_unique(),
+            );
+        }
+
+        let mut heads = [NIL; 3];
+        let mut index = 0;
+        for (head, list) in heads.iter_mut().zip(lists) {
+            for (i, payload) in list.iter().enumerate() {
+                // Safety: `index` is less than the number of sectors.
+                let sector =
+                    unsafe { Sector::from_sector_index_mut(&mut data[MarketHeader::LEN..], index) };
+                sector.set_payload(payload);
+                sector.set_next(if i + 1 == list.len() { NIL } else { index + 1 });
+                if i == 0 {
+                    *head = index;
+                }
+                index += 1;
+            }
+        }
+        // Safety: `data` is long enough for the header.
+        let header = unsafe { MarketHeader::load_unchecked_mut(&mut data[..MarketHeader::LEN]) };
+        header.set_seats_dll_head(heads[0]);
+        header.set_bids_dll_head(heads[1]);
+        header.set_asks_dll_head(heads[2]);
+        data
+    }
+
+    #[test]
+    fn position_sums_the_users_orders() {
+        let user = Address::new_unique();
+        let market_entry = entry(mint(6), mint(6));
+        let data = market_data(
+            &user,
+            &[
+                order(dec!(2), 100, USER_SEAT),
+                order(dec!(1.5), 100, OTHER_SEAT),
+            ],
+            &[
+                order(dec!(3), 100, OTHER_SEAT),
+                order(dec!(4), 50, USER_SEAT),
+            ],
+        );
+        // Safety: The data is longer than `MarketHeader::LEN`.
+        let market = unsafe { MarketRef::from_bytes(&data) };
+
+        let mid = mid_price(&market_entry, &market).unwrap();
+        assert_eq!(mid, Some(dec!(2.5)));
+
+        let mut invalid_orders = vec![];
+        let user_position =
+            position(&market_entry, &market, &user, mid, &mut invalid_orders).unwrap();
+        assert!(invalid_orders.is_empty());
+        assert_eq!(user_position.seat_index, USER_SEAT);
+        assert_eq!(user_position.mid_price, Some(dec!(2.5)));
+        // The bid locks its quote and the ask locks its base.
+        assert_eq!(
+            user_position.base,
+            Balance {
+                free: 10,
+                locked: 50
+            }
+        );
+        assert_eq!(
+            user_position.quote,
+            Balance {
+                free: 20,
+                locked: 200
+            }
+        );
+        let orders = user_position
+            .orders
+            .iter()
+            .map(|order| {
+                (
+                    order.side,
+                    order.price,
+                    order.base_remaining,
+                    order.quote_remaining,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            orders,
+            [
+                (BookSide::Bid, dec!(2), 100, 200),
+                (BookSide::Ask, dec!(4), 50, 200),
+            ]
+        );
+
+        assert!(position(
+            &market_entry,
+            &market,
+            &Address::new_unique(),
+            mid,
+            &mut invalid_orders
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn undecodable_best_price_leaves_market_unpriced() {
+        let user = Address::new_unique();
+        let market_entry = entry(mint(6), mint(6));
+        let data = market_data(
+            &user,
+            &[order(dec!(2), 100, OTHER_SEAT)],
+            &[undecodable_order(50, USER_SEAT)],
+        );
+        // Safety: The data is longer than `MarketHeader::LEN`.
+        let market = unsafe { MarketRef::from_bytes(&data) };
+
+        // The ask comes after the two seats and the bid.
+        let invalid_order = InvalidOrder {
+            market: market_entry.market,
+            order_index: 3,
+            encoded_price: 0,
+        };
+        assert_eq!(
+            mid_price(&market_entry, &market),
+            Err(invalid_order.clone())
+        );
+
+        // The order's collateral is still locked, and it's only reported once.
+        let mut invalid_orders = vec![invalid_order.clone()];
+        let user_position =
+            position(&market_entry, &market, &user, None, &mut invalid_orders).unwrap();
+        assert_eq!(invalid_orders, [invalid_order]);
+        assert_eq!(
+            user_position.base,
+            Balance {
+                free: 10,
+                locked: 50
+            }
+        );
+        assert!(user_position.orders.is_empty());
+    }
+}
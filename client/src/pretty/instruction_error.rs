@@ -6,29 +6,18 @@ use dropset_interface::{
     error::DropsetError,
     instructions::DropsetInstruction,
 };
-use solana_client::{
-    client_error::{
-        ClientError,
-        ClientErrorKind,
-    },
-    rpc_request::{
-        RpcError::RpcResponseError,
-        RpcResponseErrorData,
-    },
-    rpc_response::RpcSimulateTransactionResult,
-};
-use solana_instruction::Instruction;
 use solana_instruction_error::InstructionError as SolanaInstructionError;
 use solana_transaction_error::TransactionError;
 
 use crate::{
     fmt_kv,
+    sender::SendError,
     LogColor,
 };
 
 enum InstructionError {
     Solana {
-        instruction_tag: u8,
+        instruction_index: u8,
         error: SolanaInstructionError,
     },
     Dropset {
@@ -40,57 +29,34 @@ enum InstructionError {
 pub struct PrettyInstructionError(InstructionError);
 
 impl PrettyInstructionError {
-    pub fn new(error: &ClientError, instructions: &[Instruction]) -> Option<Self> {
-        match error.kind() {
-            ClientErrorKind::RpcError(RpcResponseError {
-                data:
-                    RpcResponseErrorData::SendTransactionPreflightFailure(
-                        RpcSimulateTransactionResult {
-                            err: Some(ui_err), ..
-                        },
-                    ),
-                ..
-            }) => {
-                let transaction_error: TransactionError = ui_err.clone().into();
-                match transaction_error {
-                    TransactionError::InstructionError(instruction_index, instruction_error) => {
-                        let instruction = instructions
-                            .get(instruction_index as usize)
-                            .expect("Instruction index from error should be valid");
-                        let instruction_tag = instruction.data[0];
-
-                        let res = match instruction_error {
-                            SolanaInstructionError::Custom(code) => {
-                                if instruction.program_id == dropset::ID {
-                                    let dropset_error = DropsetError::from_repr(code as u8)
-                                        .expect("Should be valid");
-                                    let dropset_tag = DropsetInstruction::try_from(instruction_tag)
-                                        .expect("Should be valid");
+    /// Returns `None` unless the transaction failed in an instruction, either in simulation or
+    /// after landing.
+    pub fn new(error: &SendError) -> Option<Self> {
+        let (SendError::Simulation {
+            err, dropset_error, ..
+        }
+        | SendError::Failed {
+            err, dropset_error, ..
+        }) = error
+        else {
+            return None;
+        };
+        let TransactionError::InstructionError(instruction_index, instruction_error) = err else {
+            return None;
+        };
 
-                                    Self(InstructionError::Dropset {
-                                        dropset_instruction: dropset_tag,
-                                        error: dropset_error,
-                                    })
-                                } else {
-                                    Self(InstructionError::Solana {
-                                        instruction_tag,
-                                        error: SolanaInstructionError::Custom(code),
-                                    })
-                                }
-                            }
-                            instruction_error => Self(InstructionError::Solana {
-                                instruction_tag,
-                                error: instruction_error,
-                            }),
-                        };
+        let res = match dropset_error {
+            Some(dropset_error) => Self(InstructionError::Dropset {
+                dropset_instruction: dropset_error.instruction,
+                error: dropset_error.error.clone(),
+            }),
+            None => Self(InstructionError::Solana {
+                instruction_index: *instruction_index,
+                error: instruction_error.clone(),
+            }),
+        };
 
-                        Some(res)
-                    }
-                    _ => None,
-                }
-            }
-            _ => None,
-        }
+        Some(res)
     }
 }
 
@@ -98,11 +64,11 @@ impl Display for PrettyInstructionError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let (error_type, instruction, error) = match &self.0 {
             InstructionError::Solana {
-                instruction_tag,
+                instruction_index,
                 error,
             } => (
                 "SolanaInstructionError",
-                instruction_tag.to_string(),
+                format!("instruction {instruction_index}"),
                 error.to_string(),
             ),
             InstructionError::Dropset {
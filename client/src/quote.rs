@@ -0,0 +1,355 @@
+//! Pre-trade quotes for market orders, computed over an off-chain book snapshot with the same
+//! integer arithmetic as the program's `fill_market_order`, so a quote matches what the order
+//! fills against the same book to the atom.
+//!
+//! All prices here are in quote atoms per base atom. See [`MarketContext::to_decimal_price`] for
+//! converting encoded prices to whole token prices.
+//!
+//! [`MarketContext::to_decimal_price`]: crate::context::market::MarketContext::to_decimal_price
+
+use core::num::NonZeroU64;
+
+use dropset_interface::{
+    error::DropsetError,
+    state::{
+        market::MarketRef,
+        order::Order,
+        sector::Sector,
+    },
+    utils::mul_div_checked,
+};
+use price::client_helpers::try_encoded_u32_to_decoded_decimal;
+use rust_decimal::Decimal;
+use transaction_parser::views::{
+    MarketViewAll,
+    OrderView,
+};
+
+/// A resting order's price and remaining amounts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BookLevel {
+    pub encoded_price: u32,
+    pub base_remaining: u64,
+    pub quote_remaining: u64,
+}
+
+impl From<&OrderView> for BookLevel {
+    fn from(order: &OrderView) -> Self {
+        Self {
+            encoded_price: order.encoded_price,
+            base_remaining: order.base_remaining,
+            quote_remaining: order.quote_remaining,
+        }
+    }
+}
+
+impl From<&Order> for BookLevel {
+    fn from(order: &Order) -> Self {
+        Self {
+            encoded_price: order.encoded_price(),
+            base_remaining: order.base_remaining(),
+            quote_remaining: order.quote_remaining(),
+        }
+    }
+}
+
+/// Both sides of a book, best price first: bids descending and asks ascending.
+#[derive(Clone, Debug, Default)]
+pub struct BookSnapshot {
+    pub bids: Vec<BookLevel>,
+    pub asks: Vec<BookLevel>,
+}
+
+impl From<&MarketViewAll> for BookSnapshot {
+    fn from(market: &MarketViewAll) -> Self {
+        Self {
+            bids: market.bids.iter().map(BookLevel::from).collect(),
+            asks: market.asks.iter().map(BookLevel::from).collect(),
+        }
+    }
+}
+
+impl From<MarketRef<'_>> for BookSnapshot {
+    fn from(market: MarketRef<'_>) -> Self {
+        let level = |(_, sector): (_, &Sector)| BookLevel::from(sector.load_payload::<Order>());
+        Self {
+            bids: market.iter_bids().map(level).collect(),
+            asks: market.iter_asks().map(level).collect(),
+        }
+    }
+}
+
+/// The expected result of a market order against a [`BookSnapshot`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Quote {
+    pub is_buy: bool,
+    pub is_base: bool,
+    pub order_size: u64,
+    pub base_filled: u64,
+    pub quote_filled: u64,
+    /// The number of levels filled against, including a final partially filled one.
+    pub levels_consumed: usize,
+    /// The top of book price before the order, or `None` if that side of the book is empty.
+    pub best_price: Option<Decimal>,
+    /// The price of the last level filled against, or `None` if nothing filled.
+    pub worst_price: Option<Decimal>,
+}
+
+impl Quote {
+    /// Whether the book had enough liquidity to fill the entire order size.
+    pub fn is_fully_filled(&self) -> bool {
+        let constrained_filled = match self.is_base {
+            true => self.base_filled,
+            false => self.quote_filled,
+        };
+        constrained_filled == self.order_size
+    }
+
+    /// The amount the taker pays: quote atoms for a buy, base atoms for a sell.
+    pub fn amount_in(&self) -> u64 {
+        match self.is_buy {
+            true => self.quote_filled,
+            false => self.base_filled,
+        }
+    }
+
+    /// The amount the taker receives: base atoms for a buy, quote atoms for a sell.
+    pub fn amount_out(&self) -> u64 {
+        match self.is_buy {
+            true => self.base_filled,
+            false => self.quote_filled,
+        }
+    }
+
+    /// The average fill price, or `None` if no base filled.
+    pub fn average_price(&self) -> Option<Decimal> {
+        match self.base_filled {
+            0 => None,
+            base_filled => Some(Decimal::from(self.quote_filled) / Decimal::from(base_filled)),
+        }
+    }
+
+    /// How far the average price is from the best price, as a fraction of the best price. Always
+    /// non-negative, since a buy's average price can only be above the best ask and a sell's
+    /// below the best bid.
+    pub fn price_impact(&self) -> Option<Decimal> {
+        let best = self.best_price.filter(|best| !best.is_zero())?;
+        let average = self.average_price()?;
+        Some((average - best).abs() / best)
+    }
+}
+
+impl BookSnapshot {
+    /// Quotes a market order the way the program fills it. `is_buy` fills against the asks and
+    /// `is_base` denominates `order_size` in base atoms rather than quote atoms.
+    pub fn quote(
+        &self,
+        is_buy: bool,
+        is_base: bool,
+        order_size: u64,
+    ) -> Result<Quote, DropsetError> {
+        let levels = match is_buy {
+            true => &self.asks,
+            false => &self.bids,
+        };
+        let (filled, levels_consumed) = match is_base {
+            true => fill::<true>(levels, order_size)?,
+            false => fill::<false>(levels, order_size)?,
+        };
+
+        let price = |level: Option<&BookLevel>| {
+            level
+                .map(|level| try_encoded_u32_to_decoded_decimal(level.encoded_price))
+                .transpose()
+        };
+        let (base_filled, quote_filled) = match is_base {
+            true => (
+                order_size - filled.constraint_remaining,
+                filled.counter_filled,
+            ),
+            false => (
+                filled.counter_filled,
+                order_size - filled.constraint_remaining,
+            ),
+        };
+
+        Ok(Quote {
+            is_buy,
+            is_base,
+            order_size,
+            base_filled,
+            quote_filled,
+            levels_consumed,
+            best_price: price(levels.first())?,
+            worst_price: price(levels_consumed.checked_sub(1).and_then(|i| levels.get(i)))?,
+        })
+    }
+
+    /// The smallest order size, denominated per `is_base`, for the taker to receive at least
+    /// `amount_out`: base atoms for a buy, quote atoms for a sell. Returns `None` if the book
+    /// doesn't have enough liquidity.
+    pub fn order_size_for(
+        &self,
+        is_buy: bool,
+        is_base: bool,
+        amount_out: u64,
+    ) -> Result<Option<u64>, DropsetError> {
+        let levels = match is_buy {
+            true => &self.asks,
+            false => &self.bids,
+        };
+        let constrained_liquidity = levels.iter().try_fold(0u64, |sum, level| {
+            sum.checked_add(constrained_remaining(level, is_base))
+                .ok_or(DropsetError::ArithmeticOverflow)
+        })?;
+
+        // The amount received only grows with the order size, so the smallest size that receives
+        // enough can be binary searched for, starting from one that fills the whole side.
+        let max = self.quote(is_buy, is_base, constrained_liquidity)?;
+        if max.amount_out() < amount_out {
+            return Ok(None);
+        }
+
+        let (mut low, mut high) = (0, constrained_liquidity);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.quote(is_buy, is_base, mid)?.amount_out() >= amount_out {
+                true => high = mid,
+                false => low = mid + 1,
+            }
+        }
+
+        Ok(Some(low))
+    }
+}
+
+struct Filled {
+    constraint_remaining: u64,
+    counter_filled: u64,
+}
+
+fn constrained_remaining(level: &BookLevel, is_base: bool) -> u64 {
+    match is_base {
+        true => level.base_remaining,
+        false => level.quote_remaining,
+    }
+}
+
+/// Mirrors `fill_market_order`: levels are filled whole while the remaining size covers them,
+/// and only the final partial fill is prorated with [`mul_div_checked`].
+fn fill<const BASE_DENOM: bool>(
+    levels: &[BookLevel],
+    order_size: u64,
+) -> Result<(Filled, usize), DropsetError> {
+    let mut filled = Filled {
+        constraint_remaining: order_size,
+        counter_filled: 0,
+    };
+    let mut levels_consumed = 0;
+
+    for level in levels {
+        if filled.constraint_remaining == 0 {
+            break;
+        }
+        levels_consumed += 1;
+
+        let (constrained, counter) = match BASE_DENOM {
+            true => (level.base_remaining, level.quote_remaining),
+            false => (level.quote_remaining, level.base_remaining),
+        };
+        if constrained <= filled.constraint_remaining {
+            filled.constraint_remaining -= constrained;
+            filled.counter_filled = filled
+                .counter_filled
+                .checked_add(counter)
+                .ok_or(DropsetError::ArithmeticOverflow)?;
+        } else {
+            let constrained =
+                NonZeroU64::new(constrained).ok_or(DropsetError::AmountCannotBeZero)?;
+            let partial = mul_div_checked(filled.constraint_remaining, counter, constrained)?;
+            filled.counter_filled = filled
+                .counter_filled
+                .checked_add(partial)
+                .ok_or(DropsetError::ArithmeticOverflow)?;
+            filled.constraint_remaining = 0;
+            break;
+        }
+    }
+
+    Ok((filled, levels_consumed))
+}
+
+#[cfg(test)]
+mod tests {
+    use price::{
+        EncodedPrice,
+        ValidatedPriceMantissa,
+        BIAS,
+    };
+    use rust_decimal::dec;
+
+    use super::*;
+
+    /// A level priced at `quote_remaining / base_remaining`, which must be a whole number.
+    fn level(base_remaining: u64, quote_remaining: u64) -> BookLevel {
+        let mantissa = (quote_remaining / base_remaining) as u32 * 10_000_000;
+        let mantissa = ValidatedPriceMantissa::try_from(mantissa).unwrap();
+        BookLevel {
+            encoded_price: EncodedPrice::new(mantissa, BIAS - 7).as_u32(),
+            base_remaining,
+            quote_remaining,
+        }
+    }
+
+    fn book() -> BookSnapshot {
+        BookSnapshot {
+            bids: vec![level(100, 300), level(100, 200)],
+            asks: vec![level(100, 400), level(100, 500)],
+        }
+    }
+
+    #[test]
+    fn quotes_all_four_order_kinds() {
+        let book = book();
+
+        // Buy 150 base: the first ask in full, then half of the second.
+        let buy_base = book.quote(true, true, 150).unwrap();
+        assert_eq!((buy_base.base_filled, buy_base.quote_filled), (150, 650));
+        assert_eq!(buy_base.levels_consumed, 2);
+        assert_eq!(buy_base.best_price, Some(dec!(4)));
+        assert_eq!(buy_base.worst_price, Some(dec!(5)));
+        assert_eq!(buy_base.price_impact().unwrap().round_dp(4), dec!(0.0833));
+
+        // Spend 401 quote: the first ask in full, then 1 quote of the second rounds down to 0 base.
+        let buy_quote = book.quote(true, false, 401).unwrap();
+        assert_eq!((buy_quote.base_filled, buy_quote.quote_filled), (100, 401));
+
+        // Sell 50 base into the first bid.
+        let sell_base = book.quote(false, true, 50).unwrap();
+        assert_eq!((sell_base.base_filled, sell_base.quote_filled), (50, 150));
+        assert_eq!(sell_base.levels_consumed, 1);
+
+        // Receive 600 quote: more than the book holds, so it fills both bids and stops.
+        let sell_quote = book.quote(false, false, 600).unwrap();
+        assert_eq!(
+            (sell_quote.base_filled, sell_quote.quote_filled),
+            (200, 500)
+        );
+        assert!(!sell_quote.is_fully_filled());
+    }
+
+    #[test]
+    fn finds_order_size_for_amount_out() {
+        let book = book();
+
+        // 150 base costs 400 + 250 quote.
+        let size = book.order_size_for(true, false, 150).unwrap().unwrap();
+        assert_eq!(size, 650);
+        assert_eq!(book.quote(true, false, size).unwrap().base_filled, 150);
+        assert!(book.quote(true, false, size - 1).unwrap().base_filled < 150);
+
+        // 450 quote takes 100 + 75 base.
+        assert_eq!(book.order_size_for(false, true, 450).unwrap(), Some(175));
+        assert_eq!(book.order_size_for(false, true, 501).unwrap(), None);
+    }
+}
@@ -0,0 +1,296 @@
+//! See [`MarketRegistry`].
+
+use std::collections::HashMap;
+
+use dropset_interface::state::{
+    market_header::{
+        MarketHeader,
+        MARKET_ACCOUNT_DISCRIMINANT,
+    },
+    transmutable::Transmutable,
+};
+use rust_decimal::Decimal;
+use solana_address::Address;
+use solana_client::{
+    rpc_config::{
+        RpcAccountInfoConfig,
+        RpcProgramAccountsConfig,
+        UiAccountEncoding,
+        UiDataSliceConfig,
+    },
+    rpc_filter::{
+        Memcmp,
+        RpcFilterType,
+    },
+};
+use spl_token_2022_interface::{
+    check_spl_token_program_account,
+    extension::StateWithExtensions,
+    state::Mint,
+};
+
+use crate::{
+    context::market::{
+        decode_token_price,
+        MarketContext,
+    },
+    pda::find_market_address,
+    transactions::CustomRpcClient,
+};
+
+/// `getMultipleAccounts` accepts at most this many addresses per request.
+pub(crate) const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MintInfo {
+    pub address: Address,
+    pub token_program: Address,
+    pub decimals: u8,
+}
+
+/// A market found on-chain along with its token pair's mint info.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarketEntry {
+    pub market: Address,
+    pub base: MintInfo,
+    pub quote: MintInfo,
+    pub num_seats: u32,
+    pub num_bids: u32,
+    pub num_asks: u32,
+    pub layout_version: u8,
+}
+
+impl MarketEntry {
+    /// Converts an encoded price in quote atoms per base atom to quote tokens per base token.
+    pub fn to_decimal_price(&self, encoded_price: u32) -> anyhow::Result<Decimal> {
+        decode_token_price(encoded_price, self.base.decimals, self.quote.decimals)
+    }
+
+    /// A [`MarketContext`] for sending instructions to this market.
+    pub async fn market_context(&self, rpc: &CustomRpcClient) -> anyhow::Result<MarketContext> {
+        MarketContext::new_from_token_pair(rpc, self.base.address, self.quote.address, None, None)
+            .await
+    }
+}
+
+/// Every market owned by the `dropset` program, discovered with `getProgramAccounts` filtered on
+/// [`MARKET_ACCOUNT_DISCRIMINANT`] and sliced to the [`MarketHeader`], so no sector data is
+/// transferred.
+///
+/// Mint info is cached across refreshes, since a mint's decimals and token program never change.
+#[derive(Default)]
+pub struct MarketRegistry {
+    markets: Vec<MarketEntry>,
+    mints: HashMap<Address, MintInfo>,
+}
+
+impl MarketRegistry {
+    pub async fn load(rpc: &CustomRpcClient) -> anyhow::Result<Self> {
+        let mut registry = Self::default();
+        registry.refresh(rpc).await?;
+
+        Ok(registry)
+    }
+
+    /// Re-discovers all markets, picking up new markets and updated header counts. Only mints
+    /// that haven't been seen before are fetched.
+    pub async fn refresh(&mut self, rpc: &CustomRpcClient) -> anyhow::Result<()> {
+        let headers = fetch_market_headers(rpc).await?;
+
+        let unknown_mints = headers
+            .iter()
+            .flat_map(|(_, header)| [header.base_mint, header.quote_mint])
+            .filter(|mint| !self.mints.contains_key(mint))
+            .collect::<Vec<_>>();
+        for mint in fetch_mints(rpc, unknown_mints).await? {
+            self.mints.insert(mint.address, mint);
+        }
+
+        self.markets = headers
+            .into_iter()
+            .filter_map(|(market, header)| {
+                Some(MarketEntry {
+                    market,
+                    base: *self.mints.get(&header.base_mint)?,
+                    quote: *self.mints.get(&header.quote_mint)?,
+                    num_seats: header.num_seats(),
+                    num_bids: header.num_bids(),
+                    num_asks: header.num_asks(),
+                    layout_version: header.layout_version(),
+                })
+            })
+            .collect();
+        self.markets
+            .sort_by_key(|entry| (entry.base.address, entry.quote.address));
+
+        Ok(())
+    }
+
+    /// All known markets, sorted by base mint and then quote mint.
+    pub fn markets(&self) -> &[MarketEntry] {
+        &self.markets
+    }
+
+    pub fn get(&self, market: &Address) -> Option<&MarketEntry> {
+        self.markets.iter().find(|entry| &entry.market == market)
+    }
+
+    /// The market for a base/quote pair, if one exists.
+    pub fn find_pair(&self, base_mint: &Address, quote_mint: &Address) -> Option<&MarketEntry> {
+        self.get(&find_market_address(base_mint, quote_mint).0)
+    }
+
+    /// Markets where `mint` is either the base or the quote token.
+    pub fn with_mint<'a>(&'a self, mint: &'a Address) -> impl Iterator<Item = &'a MarketEntry> {
+        self.markets
+            .iter()
+            .filter(move |entry| &entry.base.address == mint || &entry.quote.address == mint)
+    }
+
+    /// Markets whose market, base mint, or quote mint address starts with `query`.
+    pub fn search<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a MarketEntry> {
+        self.markets.iter().filter(move |entry| {
+            [entry.market, entry.base.address, entry.quote.address]
+                .iter()
+                .any(|address| address.to_string().starts_with(query))
+        })
+    }
+}
+
+/// Fetches every market's header. Accounts whose address isn't the market PDA for their header's
+/// mints are skipped.
+async fn fetch_market_headers(
+    rpc: &CustomRpcClient,
+) -> anyhow::Result<Vec<(Address, MarketHeader)>> {
+    let accounts = rpc
+        .client
+        .get_program_accounts_with_config(
+            &dropset::ID,
+            RpcProgramAccountsConfig {
+                filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                    0,
+                    MARKET_ACCOUNT_DISCRIMINANT.to_le_bytes().to_vec(),
+                ))]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    data_slice: Some(UiDataSliceConfig {
+                        offset: 0,
+                        length: MarketHeader::LEN,
+                    }),
+                    commitment: Some(rpc.client.commitment()),
+                    min_context_slot: None,
+                },
+                with_context: None,
+                sort_results: None,
+            },
+        )
+        .await?;
+
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(market, account)| {
+            let header = MarketHeader::load(&account.data).ok()?.clone();
+            let (pda, _) = find_market_address(&header.base_mint, &header.quote_mint);
+            (pda == market).then_some((market, header))
+        })
+        .collect())
+}
+
+/// Fetches and unpacks mints, skipping any that don't exist or aren't owned by a token program.
+async fn fetch_mints(rpc: &CustomRpcClient, mints: Vec<Address>) -> anyhow::Result<Vec<MintInfo>> {
+    let mut mints = mints;
+    mints.sort_unstable();
+    mints.dedup();
+
+    let mut infos = Vec::with_capacity(mints.len());
+    for chunk in mints.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = rpc.client.get_multiple_accounts(chunk).await?;
+        for (address, account) in chunk.iter().zip(accounts) {
+            let Some(account) = account else {
+                continue;
+            };
+            if check_spl_token_program_account(&account.owner).is_err() {
+                continue;
+            }
+            let Ok(mint) = StateWithExtensions::<Mint>::unpack(&account.data) else {
+                continue;
+            };
+            infos.push(MintInfo {
+                address: *address,
+                token_program: account.owner,
+                decimals: mint.base.decimals,
+            });
+        }
+    }
+
+    Ok(infos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mint(decimals: u8) -> MintInfo {
+        MintInfo {
+            address: Address::new_unique(),
+            token_program: spl_token_interface::ID,
+            decimals,
+        }
+    }
+
+    fn entry(base: MintInfo, quote: MintInfo) -> MarketEntry {
+        MarketEntry {
+            market: find_market_address(&base.address, &quote.address).0,
+            base,
+            quote,
+            num_seats: 0,
+            num_bids: 0,
+            num_asks: 0,
+            layout_version: 1,
+        }
+    }
+
+    #[test]
+    fn finds_markets_by_pair_and_mint() {
+        let (sol, usdc, bonk) = (mint(9), mint(6), mint(5));
+        let registry = MarketRegistry {
+            markets: vec![entry(sol, usdc), entry(bonk, usdc)],
+            mints: HashMap::new(),
+        };
+
+        let sol_usdc = registry.find_pair(&sol.address, &usdc.address).unwrap();
+        assert_eq!(sol_usdc.base, sol);
+        assert!(registry.find_pair(&usdc.address, &sol.address).is_none());
+        assert_eq!(registry.with_mint(&usdc.address).count(), 2);
+        assert_eq!(registry.with_mint(&bonk.address).count(), 1);
+    }
+
+    #[test]
+    fn searches_market_and_mint_addresses() {
+        let (sol, usdc, bonk) = (mint(9), mint(6), mint(5));
+        let (sol_usdc, bonk_usdc) = (entry(sol, usdc), entry(bonk, usdc));
+        let registry = MarketRegistry {
+            markets: vec![sol_usdc.clone(), bonk_usdc.clone()],
+            mints: HashMap::new(),
+        };
+        let search = |query: &str| {
+            registry
+                .search(query)
+                .map(|entry| entry.market)
+                .collect::<Vec<_>>()
+        };
+
+        // Full market address.
+        assert_eq!(search(&bonk_usdc.market.to_string()), [bonk_usdc.market]);
+        // Full base and quote mint addresses.
+        assert_eq!(search(&sol.address.to_string()), [sol_usdc.market]);
+        assert_eq!(
+            search(&usdc.address.to_string()),
+            [sol_usdc.market, bonk_usdc.market]
+        );
+        // Address prefix.
+        assert!(search(&bonk.address.to_string()[..8]).contains(&bonk_usdc.market));
+        // `0` isn't a base58 character, so it can't prefix any address.
+        assert!(search("0").is_empty());
+    }
+}
@@ -0,0 +1,617 @@
+//! See [`TransactionSender`] and its default RPC implementation, [`RpcTransactionSender`].
+
+use std::{
+    fmt::Display,
+    future::Future,
+    sync::{
+        Arc,
+        Mutex,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use itertools::Itertools;
+use solana_address::Address;
+use solana_client::{
+    client_error::{
+        ClientError,
+        ClientErrorKind,
+    },
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{
+        RpcSendTransactionConfig,
+        RpcSimulateTransactionConfig,
+    },
+    rpc_request::{
+        RpcError,
+        RpcResponseErrorData,
+    },
+    rpc_response::RpcSimulateTransactionResult,
+};
+use solana_commitment_config::CommitmentConfig;
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_sdk::{
+    hash::Hash,
+    message::{
+        v0,
+        AddressLookupTableAccount,
+        CompileError,
+        Instruction,
+        VersionedMessage,
+    },
+    signature::{
+        Keypair,
+        Signature,
+    },
+    signer::{
+        Signer,
+        SignerError,
+    },
+    transaction::{
+        TransactionError,
+        VersionedTransaction,
+    },
+};
+use transaction_parser::client_rpc::ParsedDropsetError;
+
+/// The most compute units a single transaction can request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Signs, sends, and confirms transactions.
+pub trait TransactionSender {
+    /// Sends a transaction with `instructions` paid for by `payer` and signed by `payer` and
+    /// `signers`, and waits for it to be confirmed.
+    fn send(
+        &self,
+        payer: &Keypair,
+        signers: &[&Keypair],
+        instructions: &[Instruction],
+    ) -> impl Future<Output = Result<Signature, SendError>>;
+}
+
+#[derive(Debug)]
+pub enum SendError {
+    /// An RPC request failed, including preflight failures reported by the node.
+    Rpc(ClientError),
+    Signing(SignerError),
+    /// The instructions couldn't be compiled into a v0 message, e.g. because they reference too
+    /// many accounts.
+    Compile(CompileError),
+    /// The transaction failed in simulation, so it was never sent.
+    Simulation {
+        err: TransactionError,
+        dropset_error: Option<ParsedDropsetError>,
+        logs: Vec<String>,
+    },
+    /// The transaction landed but failed.
+    Failed {
+        signature: Signature,
+        err: TransactionError,
+        dropset_error: Option<ParsedDropsetError>,
+    },
+    /// Every attempt's blockhash expired before the transaction landed.
+    Expired {
+        signature: Signature,
+        attempts: u32,
+    },
+}
+
+impl Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rpc(e) => write!(f, "RPC request failed: {e}"),
+            Self::Signing(e) => write!(f, "Failed to sign transaction: {e}"),
+            Self::Compile(e) => write!(f, "Failed to compile transaction message: {e}"),
+            Self::Simulation {
+                dropset_error: Some(dropset_error),
+                ..
+            } => write!(f, "Simulation failed: {dropset_error}"),
+            Self::Simulation { err, .. } => write!(f, "Simulation failed: {err}"),
+            Self::Failed {
+                signature,
+                dropset_error: Some(dropset_error),
+                ..
+            } => write!(f, "Transaction {signature} failed: {dropset_error}"),
+            Self::Failed { signature, err, .. } => {
+                write!(f, "Transaction {signature} failed: {err}")
+            }
+            Self::Expired {
+                signature,
+                attempts,
+            } => write!(
+                f,
+                "Transaction {signature} expired after {attempts} attempt(s)"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SendError {}
+
+impl SendError {
+    /// The decoded `dropset` error the transaction failed or would fail with, if any.
+    pub fn dropset_error(&self) -> Option<&ParsedDropsetError> {
+        match self {
+            Self::Simulation { dropset_error, .. } | Self::Failed { dropset_error, .. } => {
+                dropset_error.as_ref()
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<ClientError> for SendError {
+    fn from(e: ClientError) -> Self {
+        Self::Rpc(e)
+    }
+}
+
+impl From<SignerError> for SendError {
+    fn from(e: SignerError) -> Self {
+        Self::Signing(e)
+    }
+}
+
+impl From<CompileError> for SendError {
+    fn from(e: CompileError) -> Self {
+        Self::Compile(e)
+    }
+}
+
+/// How the compute unit price, i.e. the priority fee, is set.
+#[derive(Clone, Copy, Debug)]
+pub enum PriorityFee {
+    /// Don't set a compute unit price.
+    None,
+    /// A fixed price in micro-lamports per compute unit.
+    Fixed(u64),
+    /// A percentile of the fees recently paid to write lock the transaction's writable accounts,
+    /// from `getRecentPrioritizationFees`, capped at `max`.
+    Recent { percentile: u8, max: u64 },
+}
+
+/// How the compute unit limit is set.
+#[derive(Clone, Copy, Debug)]
+pub enum ComputeUnitLimit {
+    /// Don't set a limit and use the runtime's default.
+    Default,
+    Fixed(u32),
+    /// Simulate the transaction first and request the units it consumed plus a margin. This also
+    /// catches failing transactions before they're sent.
+    Simulated {
+        margin_percent: u32,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct SenderConfig {
+    pub priority_fee: PriorityFee,
+    pub compute_unit_limit: ComputeUnitLimit,
+    /// How long a fetched blockhash is reused for before fetching a new one.
+    pub blockhash_ttl: Duration,
+    /// How often an unconfirmed transaction is resent and its status polled.
+    pub resend_interval: Duration,
+    /// How many times the transaction is re-signed with a fresh blockhash after expiring before
+    /// giving up.
+    pub max_attempts: u32,
+    pub commitment: CommitmentConfig,
+    /// The lookup tables v0 messages may load accounts from. Accounts found in a table are
+    /// referenced by index, which shrinks transactions with many accounts.
+    pub lookup_tables: Vec<AddressLookupTableAccount>,
+}
+
+impl Default for SenderConfig {
+    fn default() -> Self {
+        Self {
+            priority_fee: PriorityFee::Recent {
+                percentile: 75,
+                max: 1_000_000,
+            },
+            compute_unit_limit: ComputeUnitLimit::Default,
+            blockhash_ttl: Duration::from_secs(30),
+            resend_interval: Duration::from_secs(2),
+            max_attempts: 3,
+            commitment: CommitmentConfig::confirmed(),
+            lookup_tables: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct CachedBlockhash {
+    blockhash: Hash,
+    last_valid_block_height: u64,
+    fetched_at: Instant,
+}
+
+/// The blockhash a sender signs with, reused until [`SenderConfig::blockhash_ttl`] passes. Clones
+/// share the same blockhash, so senders built for each transaction can still reuse it.
+#[derive(Clone, Default)]
+pub struct BlockhashCache(Arc<Mutex<Option<CachedBlockhash>>>);
+
+impl BlockhashCache {
+    fn get(&self) -> Option<CachedBlockhash> {
+        *self.0.lock().expect("Lock shouldn't be poisoned")
+    }
+
+    fn set(&self, blockhash: Option<CachedBlockhash>) {
+        *self.0.lock().expect("Lock shouldn't be poisoned") = blockhash;
+    }
+}
+
+/// Sends transactions through an RPC node, resending each one until it's confirmed or its
+/// blockhash expires, then retrying with a fresh blockhash up to [`SenderConfig::max_attempts`]
+/// times.
+pub struct RpcTransactionSender<'a> {
+    pub rpc: &'a RpcClient,
+    pub config: SenderConfig,
+    blockhash: BlockhashCache,
+}
+
+impl<'a> RpcTransactionSender<'a> {
+    pub fn new(rpc: &'a RpcClient, config: SenderConfig) -> Self {
+        Self {
+            rpc,
+            config,
+            blockhash: BlockhashCache::default(),
+        }
+    }
+
+    /// Reuses blockhashes fetched by other senders sharing `cache`.
+    pub fn with_blockhash_cache(mut self, cache: BlockhashCache) -> Self {
+        self.blockhash = cache;
+        self
+    }
+
+    /// Returns the cached blockhash, fetching a new one if it's missing or older than the TTL.
+    async fn blockhash(&self) -> Result<CachedBlockhash, ClientError> {
+        if let Some(cached) = self
+            .blockhash
+            .get()
+            .filter(|c| c.fetched_at.elapsed() < self.config.blockhash_ttl)
+        {
+            return Ok(cached);
+        }
+
+        let (blockhash, last_valid_block_height) = self
+            .rpc
+            .get_latest_blockhash_with_commitment(self.config.commitment)
+            .await?;
+        let fetched = CachedBlockhash {
+            blockhash,
+            last_valid_block_height,
+            fetched_at: Instant::now(),
+        };
+        self.blockhash.set(Some(fetched));
+
+        Ok(fetched)
+    }
+
+    fn invalidate_blockhash(&self) {
+        self.blockhash.set(None);
+    }
+
+    async fn compute_unit_price(&self, instructions: &[Instruction]) -> Result<u64, ClientError> {
+        match self.config.priority_fee {
+            PriorityFee::None => Ok(0),
+            PriorityFee::Fixed(price) => Ok(price),
+            PriorityFee::Recent { percentile, max } => {
+                let writable = instructions
+                    .iter()
+                    .flat_map(|instruction| instruction.accounts.iter())
+                    .filter(|meta| meta.is_writable)
+                    .map(|meta| meta.pubkey)
+                    .collect::<Vec<Address>>();
+                let fees = self
+                    .rpc
+                    .get_recent_prioritization_fees(&writable)
+                    .await?
+                    .into_iter()
+                    .map(|fee| fee.prioritization_fee)
+                    .collect();
+
+                Ok(fee_percentile(fees, percentile, max))
+            }
+        }
+    }
+
+    async fn compute_unit_limit(
+        &self,
+        payer: &Address,
+        instructions: &[Instruction],
+    ) -> Result<Option<u32>, SendError> {
+        let margin_percent = match self.config.compute_unit_limit {
+            ComputeUnitLimit::Default => return Ok(None),
+            ComputeUnitLimit::Fixed(limit) => return Ok(Some(limit)),
+            ComputeUnitLimit::Simulated { margin_percent } => margin_percent,
+        };
+
+        // Simulate with the maximum limit so the simulation itself can't run out of units.
+        let simulated = [
+            vec![ComputeBudgetInstruction::set_compute_unit_limit(
+                MAX_COMPUTE_UNIT_LIMIT,
+            )],
+            instructions.to_vec(),
+        ]
+        .concat();
+        let message = compile_message(
+            payer,
+            &simulated,
+            &self.config.lookup_tables,
+            Hash::default(),
+        )?;
+        let transaction = VersionedTransaction {
+            signatures: vec![
+                Signature::default();
+                message.header().num_required_signatures as usize
+            ],
+            message,
+        };
+        let result = self
+            .rpc
+            .simulate_transaction_with_config(
+                &transaction,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    commitment: Some(self.config.commitment),
+                    ..Default::default()
+                },
+            )
+            .await?
+            .value;
+
+        if let Some(err) = result.err {
+            let err: TransactionError = err.into();
+            return Err(SendError::Simulation {
+                dropset_error: decode_dropset_error(&err, &transaction.message),
+                err,
+                logs: result.logs.unwrap_or_default(),
+            });
+        }
+
+        let units = result
+            .units_consumed
+            .unwrap_or(MAX_COMPUTE_UNIT_LIMIT as u64);
+        Ok(Some(limit_with_margin(units, margin_percent)))
+    }
+}
+
+/// The fee at `percentile` of `fees`, rounded down to the nearest fee, capped at `max`. No recent
+/// fees means no priority fee.
+fn fee_percentile(mut fees: Vec<u64>, percentile: u8, max: u64) -> u64 {
+    fees.sort_unstable();
+    let index = fees.len().saturating_sub(1) * percentile.min(100) as usize / 100;
+    fees.get(index).copied().unwrap_or(0).min(max)
+}
+
+/// `units` plus `margin_percent` percent, capped at [`MAX_COMPUTE_UNIT_LIMIT`].
+fn limit_with_margin(units: u64, margin_percent: u32) -> u32 {
+    let limit = units.saturating_mul(100 + margin_percent as u64) / 100;
+    limit.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+}
+
+impl TransactionSender for RpcTransactionSender<'_> {
+    async fn send(
+        &self,
+        payer: &Keypair,
+        signers: &[&Keypair],
+        instructions: &[Instruction],
+    ) -> Result<Signature, SendError> {
+        let limit = self
+            .compute_unit_limit(&payer.pubkey(), instructions)
+            .await?;
+        let price = self.compute_unit_price(instructions).await?;
+        let instructions = limit
+            .map(ComputeBudgetInstruction::set_compute_unit_limit)
+            .into_iter()
+            .chain((price > 0).then(|| ComputeBudgetInstruction::set_compute_unit_price(price)))
+            .chain(instructions.iter().cloned())
+            .collect::<Vec<_>>();
+
+        // v0 signing requires exactly one keypair per required signature, so drop duplicates such
+        // as a payer that's also passed as a signer.
+        let all_signers = std::iter::once(payer)
+            .chain(signers.iter().copied())
+            .unique_by(|signer| signer.pubkey())
+            .collect::<Vec<_>>();
+        let mut signature = Signature::default();
+
+        for attempt in 1..=self.config.max_attempts.max(1) {
+            let blockhash = self.blockhash().await?;
+            let message = compile_message(
+                &payer.pubkey(),
+                &instructions,
+                &self.config.lookup_tables,
+                blockhash.blockhash,
+            )?;
+            let transaction = VersionedTransaction::try_new(message, &all_signers)?;
+            signature = transaction.signatures[0];
+
+            let last_valid_block_height = blockhash.last_valid_block_height;
+            let confirmation =
+                send_until_confirmed(self.rpc, &transaction, &self.config, move || async move {
+                    let block_height = self
+                        .rpc
+                        .get_block_height_with_commitment(self.config.commitment)
+                        .await?;
+                    Ok(block_height > last_valid_block_height)
+                })
+                .await?;
+            match confirmation {
+                Confirmation::Confirmed => return Ok(signature),
+                Confirmation::Expired => {
+                    self.invalidate_blockhash();
+                    if attempt == self.config.max_attempts.max(1) {
+                        return Err(SendError::Expired {
+                            signature,
+                            attempts: attempt,
+                        });
+                    }
+                }
+            }
+        }
+
+        Err(SendError::Expired {
+            signature,
+            attempts: self.config.max_attempts,
+        })
+    }
+}
+
+/// How [`send_until_confirmed`] stopped resending a transaction that didn't fail.
+pub(crate) enum Confirmation {
+    /// The transaction reached the configured commitment.
+    Confirmed,
+    /// The transaction's lifetime ended before it was processed.
+    Expired,
+}
+
+/// Sends `transaction` and resends it every `resend_interval` until it reaches the configured
+/// commitment, fails, or `is_expired` reports its lifetime has ended without it being processed.
+pub(crate) async fn send_until_confirmed<F, Fut>(
+    rpc: &RpcClient,
+    transaction: &VersionedTransaction,
+    config: &SenderConfig,
+    mut is_expired: F,
+) -> Result<Confirmation, SendError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<bool, SendError>>,
+{
+    let signature = transaction.signatures[0];
+
+    // Only the first send runs preflight checks, so deterministic failures surface right away with
+    // the node's error. Resends skip them since the transaction may already be processed.
+    let mut skip_preflight = false;
+    loop {
+        rpc.send_transaction_with_config(
+            transaction,
+            RpcSendTransactionConfig {
+                skip_preflight,
+                preflight_commitment: Some(config.commitment.commitment),
+                max_retries: Some(0),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|e| preflight_error(e, &transaction.message))?;
+        skip_preflight = true;
+
+        tokio::time::sleep(config.resend_interval).await;
+
+        // Check expiry before the status, so a transaction that landed just before expiring is
+        // still seen as landed.
+        let expired = is_expired().await?;
+
+        let status = rpc
+            .get_signature_statuses(&[signature])
+            .await?
+            .value
+            .into_iter()
+            .next()
+            .flatten();
+        if let Some(status) = status {
+            if let Some(err) = status.err {
+                return Err(SendError::Failed {
+                    signature,
+                    dropset_error: decode_dropset_error(&err, &transaction.message),
+                    err,
+                });
+            }
+            if status.satisfies_commitment(config.commitment) {
+                return Ok(Confirmation::Confirmed);
+            }
+            // Processed but not yet confirmed, so it can't expire anymore.
+            continue;
+        }
+
+        if expired {
+            return Ok(Confirmation::Expired);
+        }
+    }
+}
+
+/// Compiles a v0 message, loading any accounts found in `lookup_tables` by index rather than
+/// including their addresses in the message.
+pub(crate) fn compile_message(
+    payer: &Address,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    blockhash: Hash,
+) -> Result<VersionedMessage, CompileError> {
+    v0::Message::try_compile(payer, instructions, lookup_tables, blockhash)
+        .map(VersionedMessage::V0)
+}
+
+/// Converts a preflight failure into [`SendError::Simulation`], since the node simulated it.
+pub(crate) fn preflight_error(error: ClientError, message: &VersionedMessage) -> SendError {
+    if let ClientErrorKind::RpcError(RpcError::RpcResponseError {
+        data:
+            RpcResponseErrorData::SendTransactionPreflightFailure(RpcSimulateTransactionResult {
+                err: Some(err),
+                logs,
+                ..
+            }),
+        ..
+    }) = error.kind()
+    {
+        let err: TransactionError = err.clone().into();
+        return SendError::Simulation {
+            dropset_error: decode_dropset_error(&err, message),
+            err,
+            logs: logs.clone().unwrap_or_default(),
+        };
+    }
+
+    SendError::Rpc(error)
+}
+
+/// Decodes the `dropset` error `err` maps to, if the failing instruction is a `dropset` one.
+/// Program IDs are always static keys in a compiled message, so lookup tables aren't needed.
+pub(crate) fn decode_dropset_error(
+    err: &TransactionError,
+    message: &VersionedMessage,
+) -> Option<ParsedDropsetError> {
+    let TransactionError::InstructionError(index, error) = err else {
+        return None;
+    };
+    let instruction = message.instructions().get(*index as usize)?;
+    let program_id = message
+        .static_account_keys()
+        .get(instruction.program_id_index as usize)?;
+
+    ParsedDropsetError::from_instruction_error(*index, program_id, &instruction.data, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_fee_percentile_under_max() {
+        let fees = vec![50, 10, 40, 20, 30];
+
+        assert_eq!(fee_percentile(fees.clone(), 0, u64::MAX), 10);
+        assert_eq!(fee_percentile(fees.clone(), 50, u64::MAX), 30);
+        // 4 * 75 / 100 rounds down to the fourth fee.
+        assert_eq!(fee_percentile(fees.clone(), 75, u64::MAX), 40);
+        assert_eq!(fee_percentile(fees.clone(), 100, u64::MAX), 50);
+        assert_eq!(fee_percentile(fees.clone(), 255, u64::MAX), 50);
+        assert_eq!(fee_percentile(fees, 100, 35), 35);
+        assert_eq!(fee_percentile(vec![], 75, 1_000), 0);
+    }
+
+    #[test]
+    fn adds_margin_to_compute_unit_limit() {
+        assert_eq!(limit_with_margin(100_000, 0), 100_000);
+        assert_eq!(limit_with_margin(100_000, 20), 120_000);
+        // Rounds down.
+        assert_eq!(limit_with_margin(333, 10), 366);
+        assert_eq!(limit_with_margin(1_200_000, 20), MAX_COMPUTE_UNIT_LIMIT);
+        assert_eq!(limit_with_margin(u64::MAX, 20), MAX_COMPUTE_UNIT_LIMIT);
+    }
+}
@@ -0,0 +1,135 @@
+//! Predicts a transaction's outcome before sending it: the `dropset` events it would emit, the
+//! resulting account state, and the compute units it would consume.
+//!
+//! See [`Simulate`], which is implemented for both an RPC node and an offline [`MolluskContext`].
+//!
+//! [`MolluskContext`]: mollusk_svm::MolluskContext
+
+mod mollusk;
+mod rpc;
+
+use std::{
+    collections::HashMap,
+    future::Future,
+};
+
+use solana_account::Account;
+use solana_address::Address;
+use solana_instruction::Instruction;
+use solana_sdk::transaction::TransactionError;
+use transaction_parser::{
+    client_rpc::ParsedDropsetError,
+    events::{
+        display_types::DisplayMarketOrderData,
+        dropset_event::DropsetEvent,
+    },
+    ParseDropsetEvents,
+};
+
+/// Something that can execute instructions without committing their effects.
+pub trait Simulate {
+    /// Simulates a transaction with `instructions` paid for by `payer`, returning the
+    /// post-simulation state of each account in `accounts`.
+    ///
+    /// Signatures aren't verified, so the payer and any other signers don't need to sign.
+    fn simulate(
+        &self,
+        payer: &Address,
+        instructions: &[Instruction],
+        accounts: &[Address],
+    ) -> impl Future<Output = anyhow::Result<Simulation>>;
+}
+
+/// The predicted outcome of a transaction.
+#[derive(Debug)]
+pub struct Simulation {
+    /// The error the transaction would fail with, if any.
+    pub err: Option<TransactionError>,
+    /// The decoded error if the transaction would fail in a `dropset` instruction.
+    pub dropset_error: Option<ParsedDropsetError>,
+    /// The `dropset` events the transaction would emit, up until the failing instruction if it
+    /// fails.
+    pub events: Vec<DropsetEvent>,
+    pub units_consumed: Option<u64>,
+    /// The program logs. Always empty for offline simulations.
+    pub logs: Vec<String>,
+    /// The post-simulation state of each requested account, or `None` if it wouldn't exist.
+    pub accounts: HashMap<Address, Option<Account>>,
+}
+
+/// An inner instruction from a simulation, used to find the `FlushEvents` self-CPIs.
+struct SimulatedInnerInstruction {
+    program_id: Address,
+    data: Vec<u8>,
+}
+
+impl ParseDropsetEvents for SimulatedInnerInstruction {
+    fn program_id(&self) -> &[u8; 32] {
+        self.program_id.as_array()
+    }
+
+    fn instruction_data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// The offset of the `amount` field in both SPL token and token-2022 token accounts, after the
+/// mint and owner addresses.
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+
+impl Simulation {
+    fn new(
+        instructions: &[Instruction],
+        err: Option<TransactionError>,
+        events: Vec<DropsetEvent>,
+        units_consumed: Option<u64>,
+        logs: Vec<String>,
+        accounts: HashMap<Address, Option<Account>>,
+    ) -> Self {
+        let dropset_error = match &err {
+            Some(TransactionError::InstructionError(index, error)) => {
+                instructions.get(*index as usize).and_then(|instruction| {
+                    ParsedDropsetError::from_instruction_error(
+                        *index,
+                        &instruction.program_id,
+                        &instruction.data,
+                        error,
+                    )
+                })
+            }
+            _ => None,
+        };
+
+        Self {
+            err,
+            dropset_error,
+            events,
+            units_consumed,
+            logs,
+            accounts,
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.err.is_none()
+    }
+
+    /// The market order fills the transaction would make.
+    pub fn fills(&self) -> impl Iterator<Item = &DisplayMarketOrderData> {
+        self.events.iter().filter_map(|event| match event {
+            DropsetEvent::MarketOrder(market_order) => Some(market_order),
+            _ => None,
+        })
+    }
+
+    /// The post-simulation token balance of a requested token account, or `None` if it wasn't
+    /// requested, wouldn't exist, or isn't a token account.
+    pub fn token_balance(&self, token_account: &Address) -> Option<u64> {
+        let account = self.accounts.get(token_account)?.as_ref()?;
+        let amount = account
+            .data
+            .get(TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8)?;
+
+        Some(u64::from_le_bytes(amount.try_into().ok()?))
+    }
+}
@@ -0,0 +1,123 @@
+//! See [`MolluskContext`]'s [`Simulate`] implementation.
+
+use std::future::{
+    ready,
+    Future,
+};
+
+use anyhow::Context;
+use itertools::Itertools;
+use mollusk_svm::{
+    account_store::AccountStore,
+    MolluskContext,
+};
+use solana_account::Account;
+use solana_address::Address;
+use solana_instruction::Instruction;
+use solana_sdk::transaction::TransactionError;
+use transaction_parser::ParseDropsetEvents;
+
+use crate::simulate::{
+    Simulate,
+    SimulatedInnerInstruction,
+    Simulation,
+};
+
+impl<AS: AccountStore> Simulate for MolluskContext<AS> {
+    /// Processes the instructions in order against a copy of the context's accounts, so the
+    /// account store is left untouched. There are no fees or logs offline, so the payer is unused.
+    fn simulate(
+        &self,
+        _payer: &Address,
+        instructions: &[Instruction],
+        accounts: &[Address],
+    ) -> impl Future<Output = anyhow::Result<Simulation>> {
+        ready(simulate(self, instructions, accounts))
+    }
+}
+
+fn simulate<AS: AccountStore>(
+    context: &MolluskContext<AS>,
+    instructions: &[Instruction],
+    requested: &[Address],
+) -> anyhow::Result<Simulation> {
+    let mut accounts: Vec<(Address, Account)> = {
+        let store = context.account_store.borrow();
+        instructions
+            .iter()
+            .flat_map(|instruction| instruction.accounts.iter().map(|meta| meta.pubkey))
+            .chain(requested.iter().copied())
+            .unique()
+            .map(|address| {
+                let account = store
+                    .get_account(&address)
+                    .unwrap_or_else(|| store.default_account(&address));
+                (address, account)
+            })
+            .collect()
+    };
+
+    let mut err = None;
+    let mut events = vec![];
+    let mut units_consumed = 0;
+    for (index, instruction) in instructions.iter().enumerate() {
+        let result = context.mollusk.process_instruction(instruction, &accounts);
+        units_consumed += result.compute_units_consumed;
+
+        // Inner instructions refer to their program by its index in the compiled message.
+        if !result.inner_instructions.is_empty() {
+            let message = result
+                .message
+                .as_ref()
+                .context("Simulation returned inner instructions without a message")?;
+            let account_keys = message.account_keys();
+            for inner in result.inner_instructions.iter() {
+                let program_id = account_keys
+                    .get(inner.instruction.program_id_index as usize)
+                    .context("Inner instruction's program ID index is out of bounds")?;
+                let inner = SimulatedInnerInstruction {
+                    program_id: *program_id,
+                    data: inner.instruction.data.clone(),
+                };
+                events.extend(
+                    inner
+                        .parse_events()
+                        .map_err(|e| anyhow::anyhow!("Failed to parse simulated events: {e:?}"))?,
+                );
+            }
+        }
+
+        if let Err(error) = result.raw_result {
+            err = Some(TransactionError::InstructionError(index as u8, error));
+            break;
+        }
+
+        // Carry each instruction's resulting accounts over to the next instruction.
+        for (address, resulting) in result.resulting_accounts {
+            if let Some((_, account)) = accounts.iter_mut().find(|(a, _)| *a == address) {
+                *account = resulting;
+            }
+        }
+    }
+
+    let requested = requested
+        .iter()
+        .map(|address| {
+            let account = accounts
+                .iter()
+                .find(|(a, _)| a == address)
+                .map(|(_, account)| account.clone())
+                .filter(|account| *account != Account::default());
+            (*address, account)
+        })
+        .collect();
+
+    Ok(Simulation::new(
+        instructions,
+        err,
+        events,
+        Some(units_consumed),
+        vec![],
+        requested,
+    ))
+}
@@ -0,0 +1,137 @@
+//! See [`CustomRpcClient`]'s [`Simulate`] implementation.
+
+use anyhow::Context;
+use itertools::Itertools;
+use solana_account::Account;
+use solana_address::Address;
+use solana_client::rpc_config::{
+    RpcSimulateTransactionAccountsConfig,
+    RpcSimulateTransactionConfig,
+};
+use solana_commitment_config::CommitmentConfig;
+use solana_instruction::Instruction;
+use solana_sdk::{
+    bs58,
+    hash::Hash,
+    message::VersionedMessage,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::UiInstruction;
+use transaction_parser::ParseDropsetEvents;
+
+use crate::{
+    lookup_table::resolve_account_keys,
+    sender::compile_message,
+    simulate::{
+        Simulate,
+        SimulatedInnerInstruction,
+        Simulation,
+    },
+    transactions::{
+        with_compute_budget,
+        CustomRpcClient,
+    },
+};
+
+impl Simulate for CustomRpcClient {
+    /// Runs `simulateTransaction` with inner instructions enabled, prepending the compute budget
+    /// instructions and loading accounts from the lookup tables in the client's config the same
+    /// way sending does.
+    async fn simulate(
+        &self,
+        payer: &Address,
+        instructions: &[Instruction],
+        accounts: &[Address],
+    ) -> anyhow::Result<Simulation> {
+        let instructions = with_compute_budget(&self.config, instructions);
+        let lookup_tables = &self.config.lookup_tables;
+        let message = compile_message(payer, &instructions, lookup_tables, Hash::default())?;
+        let VersionedMessage::V0(compiled) = &message else {
+            unreachable!("Messages are always compiled as v0");
+        };
+        let account_keys = resolve_account_keys(compiled, lookup_tables)
+            .context("Compiled message refers to an unknown lookup table")?;
+        let transaction = VersionedTransaction {
+            signatures: vec![
+                Signature::default();
+                message.header().num_required_signatures as usize
+            ],
+            message,
+        };
+
+        let result = self
+            .client
+            .simulate_transaction_with_config(
+                &transaction,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    accounts: (!accounts.is_empty()).then(|| {
+                        RpcSimulateTransactionAccountsConfig {
+                            // Defaults to base64, the only encoding that round trips raw accounts.
+                            encoding: None,
+                            addresses: accounts.iter().map(ToString::to_string).collect(),
+                        }
+                    }),
+                    inner_instructions: true,
+                    ..Default::default()
+                },
+            )
+            .await
+            .context("Failed to simulate transaction")?
+            .value;
+
+        let inner_instructions = result
+            .inner_instructions
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|inner| inner.instructions)
+            .filter_map(|instruction| match instruction {
+                UiInstruction::Compiled(compiled) => Some(compiled),
+                UiInstruction::Parsed(_) => None,
+            })
+            .map(|compiled| {
+                let program_id = account_keys
+                    .get(compiled.program_id_index as usize)
+                    .context("Inner instruction's program ID index is out of bounds")?;
+                Ok(SimulatedInnerInstruction {
+                    program_id: *program_id,
+                    data: bs58::decode(&compiled.data).into_vec()?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let events = inner_instructions
+            .iter()
+            .map(|inner| {
+                inner
+                    .parse_events()
+                    .map_err(|e| anyhow::anyhow!("Failed to parse simulated events: {e:?}"))
+            })
+            .flatten_ok()
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let returned_accounts = result.accounts.unwrap_or_default();
+        let accounts = accounts
+            .iter()
+            .zip(returned_accounts.into_iter().chain(std::iter::repeat(None)))
+            .map(|(address, account)| {
+                (
+                    *address,
+                    account.and_then(|account| account.decode::<Account>()),
+                )
+            })
+            .collect();
+
+        Ok(Simulation::new(
+            &instructions,
+            result.err.map(Into::into),
+            events,
+            result.units_consumed,
+            result.logs.unwrap_or_default(),
+            accounts,
+        ))
+    }
+}
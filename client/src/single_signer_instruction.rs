@@ -1,5 +1,8 @@
 use solana_instruction::Instruction;
-use solana_sdk::signature::Keypair;
+use solana_sdk::{
+    message::AddressLookupTableAccount,
+    signature::Keypair,
+};
 
 use crate::transactions::{
     CustomRpcClient,
@@ -8,7 +11,13 @@ use crate::transactions::{
 
 /// A utility wrapper newtype for instructions that only need a single signer. This facilitates
 /// simple construction and submission of single signer transactions with one instruction.
-pub struct SingleSignerInstruction(Instruction);
+///
+/// Instructions built by a [`crate::context::market::MarketContext`] carry the market's lookup
+/// table, which sending loads accounts from.
+pub struct SingleSignerInstruction {
+    instruction: Instruction,
+    lookup_table: Option<AddressLookupTableAccount>,
+}
 
 impl TryFrom<Instruction> for SingleSignerInstruction {
     type Error = anyhow::Error;
@@ -25,22 +34,37 @@ impl TryFrom<Instruction> for SingleSignerInstruction {
             ));
         };
 
-        Ok(Self(instruction))
+        Ok(Self {
+            instruction,
+            lookup_table: None,
+        })
     }
 }
 
 impl From<SingleSignerInstruction> for Instruction {
     fn from(instruction: SingleSignerInstruction) -> Self {
-        instruction.0
+        instruction.instruction
     }
 }
 
 impl SingleSignerInstruction {
+    /// Loads accounts from `lookup_table` when sending the instruction.
+    pub fn with_lookup_table(mut self, lookup_table: Option<AddressLookupTableAccount>) -> Self {
+        self.lookup_table = lookup_table;
+        self
+    }
+
     pub async fn send_single_signer(
         self,
         rpc: &CustomRpcClient,
         signer: &Keypair,
     ) -> anyhow::Result<ParsedTransactionWithEvents> {
-        rpc.send_single_signer(signer, [self.0]).await
+        rpc.send_with_lookup_tables(
+            signer,
+            &[signer],
+            &[self.instruction],
+            self.lookup_table.as_slice(),
+        )
+        .await
     }
 }
@@ -1,7 +1,10 @@
 //! Lightweight, nonblocking RPC client utilities for funding accounts, sending transactions,
 //! and pretty-printing `dropset`-related transaction logs.
 
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    time::Duration,
+};
 
 use anyhow::{
     bail,
@@ -14,15 +17,14 @@ use solana_commitment_config::CommitmentConfig;
 use solana_compute_budget_interface::ComputeBudgetInstruction;
 use solana_sdk::{
     message::{
+        AddressLookupTableAccount,
         Instruction,
-        Message,
     },
     signature::{
         Keypair,
         Signature,
         Signer,
     },
-    transaction::Transaction,
 };
 use solana_transaction_status::{
     EncodedConfirmedTransactionWithStatusMeta,
@@ -38,17 +40,32 @@ use transaction_parser::{
 };
 
 use crate::{
+    fmt_kv,
+    offline::{
+        self,
+        Lifetime,
+        OfflineTransaction,
+    },
     pretty::{
         instruction_error::PrettyInstructionError,
         transaction::PrettyTransaction,
     },
-    print_kv,
+    sender::{
+        BlockhashCache,
+        ComputeUnitLimit,
+        PriorityFee,
+        RpcTransactionSender,
+        SendError,
+        SenderConfig,
+        TransactionSender,
+    },
     LogColor,
 };
 
 pub struct CustomRpcClient {
     pub client: RpcClient,
     pub config: SendTransactionConfig,
+    blockhash_cache: BlockhashCache,
 }
 
 impl Default for CustomRpcClient {
@@ -59,6 +76,7 @@ impl Default for CustomRpcClient {
                 CommitmentConfig::confirmed(),
             ),
             config: Default::default(),
+            blockhash_cache: Default::default(),
         }
     }
 }
@@ -66,15 +84,21 @@ impl Default for CustomRpcClient {
 impl CustomRpcClient {
     pub fn new(client: Option<RpcClient>, config: Option<SendTransactionConfig>) -> Self {
         match (client, config) {
-            (Some(client), Some(config)) => Self { client, config },
+            (Some(client), Some(config)) => Self {
+                client,
+                config,
+                blockhash_cache: Default::default(),
+            },
             (client, config) => {
                 let CustomRpcClient {
                     client: default_client,
                     config: default_config,
+                    blockhash_cache,
                 } = Default::default();
                 Self {
                     client: client.unwrap_or(default_client),
                     config: config.unwrap_or(default_config),
+                    blockhash_cache,
                 }
             }
         }
@@ -84,9 +108,16 @@ impl CustomRpcClient {
         CustomRpcClient {
             client: RpcClient::new_with_commitment(url.into(), CommitmentConfig::confirmed()),
             config,
+            blockhash_cache: Default::default(),
         }
     }
 
+    /// A sender for `config` that shares its blockhash with every other sender from this client.
+    pub fn sender(&self, config: SenderConfig) -> RpcTransactionSender<'_> {
+        RpcTransactionSender::new(&self.client, config)
+            .with_blockhash_cache(self.blockhash_cache.clone())
+    }
+
     pub async fn fund_account(&self, address: &Address) -> anyhow::Result<()> {
         fund(&self.client, address).await
     }
@@ -117,7 +148,65 @@ impl CustomRpcClient {
         signers: &[&Keypair],
         instructions: &[Instruction],
     ) -> anyhow::Result<ParsedTransactionWithEvents> {
-        send_transaction_with_config(&self.client, payer, signers, instructions, &self.config).await
+        send_transaction_with_config(self, payer, signers, instructions, &self.config).await
+    }
+
+    /// Sends and confirms a transaction the same way as [`Self::send_and_confirm_txn`],
+    /// additionally loading accounts from `lookup_tables`.
+    pub async fn send_with_lookup_tables(
+        &self,
+        payer: &Keypair,
+        signers: &[&Keypair],
+        instructions: &[Instruction],
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> anyhow::Result<ParsedTransactionWithEvents> {
+        if lookup_tables.is_empty() {
+            return self
+                .send_and_confirm_txn(payer, signers, instructions)
+                .await;
+        }
+        let mut config = self.config.clone();
+        config.lookup_tables.extend_from_slice(lookup_tables);
+        send_transaction_with_config(self, payer, signers, instructions, &config).await
+    }
+
+    /// Builds an unsigned transaction for signers that aren't in-memory keypairs, e.g. a cold
+    /// wallet. See [`crate::offline`].
+    pub async fn build_offline(
+        &self,
+        payer: &Address,
+        instructions: &[Instruction],
+        lifetime: Lifetime,
+    ) -> anyhow::Result<OfflineTransaction> {
+        OfflineTransaction::build(
+            &self.client,
+            payer,
+            &with_compute_budget(&self.config, instructions),
+            &self.config.lookup_tables,
+            lifetime,
+        )
+        .await
+    }
+
+    /// Submits a transaction once it's been fully signed and waits for it to be confirmed.
+    pub async fn send_offline(
+        &self,
+        transaction: OfflineTransaction,
+    ) -> anyhow::Result<ParsedTransactionWithEvents> {
+        let payer = *transaction
+            .signers()
+            .first()
+            .context("Transaction has no fee payer signature")?;
+        let transaction = transaction.into_signed()?;
+        match offline::submit(&self.client, &transaction, &self.config.sender_config()).await {
+            Ok(signature) => {
+                fetch_sent_transaction(&self.client, payer, signature, &self.config).await
+            }
+            Err(error) => {
+                log_send_error(&error, payer, &self.config);
+                Err(error).context("Failed transaction submission")
+            }
+        }
     }
 }
 
@@ -155,6 +244,28 @@ pub struct SendTransactionConfig {
     pub compute_budget: Option<u32>,
     pub debug_logs: Option<bool>,
     pub program_id_filter: HashSet<Address>,
+    /// The lookup tables transactions load accounts from. See [`crate::lookup_table`].
+    pub lookup_tables: Vec<AddressLookupTableAccount>,
+}
+
+impl SendTransactionConfig {
+    /// The [`SenderConfig`] used to send transactions with this config. A fixed compute budget is
+    /// used as the compute unit limit alongside the nominal 1 micro-lamport priority fee; without
+    /// one, no compute budget instructions are added. Use [`SenderConfig`] directly to opt into
+    /// recent priority fees.
+    pub fn sender_config(&self) -> SenderConfig {
+        let (priority_fee, compute_unit_limit) = match self.compute_budget {
+            Some(budget) => (PriorityFee::Fixed(1), ComputeUnitLimit::Fixed(budget)),
+            None => (PriorityFee::None, ComputeUnitLimit::Default),
+        };
+        SenderConfig {
+            priority_fee,
+            compute_unit_limit,
+            resend_interval: Duration::from_millis(500),
+            lookup_tables: self.lookup_tables.clone(),
+            ..Default::default()
+        }
+    }
 }
 
 impl Default for SendTransactionConfig {
@@ -163,6 +274,7 @@ impl Default for SendTransactionConfig {
             compute_budget: Default::default(),
             debug_logs: Some(true),
             program_id_filter: HashSet::new(),
+            lookup_tables: vec![],
         }
     }
 }
@@ -179,92 +291,104 @@ pub struct ParsedTransactionWithEvents {
     pub events: Vec<DropsetEvent>,
 }
 
-async fn send_transaction_with_config(
-    rpc: &RpcClient,
-    payer: &Keypair,
-    signers: &[&Keypair],
-    instructions: &[Instruction],
+/// Prepends the compute unit limit from `config` and the nominal 1 micro-lamport priority fee to
+/// `instructions`, if it sets a budget.
+pub(crate) fn with_compute_budget(
     config: &SendTransactionConfig,
-) -> anyhow::Result<ParsedTransactionWithEvents> {
-    let bh = rpc
-        .get_latest_blockhash()
-        .await
-        .or(Err(()))
-        .expect("Should be able to get blockhash.");
-
-    let final_instructions: &[Instruction] = &[
-        config.compute_budget.map_or(vec![], |budget| {
-            vec![
+    instructions: &[Instruction],
+) -> Vec<Instruction> {
+    config
+        .compute_budget
+        .into_iter()
+        .flat_map(|budget| {
+            [
                 ComputeBudgetInstruction::set_compute_unit_limit(budget),
                 ComputeBudgetInstruction::set_compute_unit_price(1),
             ]
-        }),
-        instructions.to_vec(),
-    ]
-    .concat();
-
-    let msg = Message::new(final_instructions, Some(&payer.pubkey()));
-
-    let mut tx = Transaction::new_unsigned(msg);
-    tx.try_sign(
-        &[std::iter::once(payer)
-            .chain(signers.iter().cloned())
-            .collect::<Vec<_>>()]
-        .concat(),
-        bh,
-    )
-    .expect("Should sign");
+        })
+        .chain(instructions.iter().cloned())
+        .collect()
+}
 
-    let res = rpc.send_and_confirm_transaction(&tx).await;
+pub(crate) async fn send_transaction_with_config(
+    rpc: &CustomRpcClient,
+    payer: &Keypair,
+    signers: &[&Keypair],
+    instructions: &[Instruction],
+    config: &SendTransactionConfig,
+) -> anyhow::Result<ParsedTransactionWithEvents> {
+    let sender = rpc.sender(config.sender_config());
+    let res = sender.send(payer, signers, instructions).await;
     match res {
         Ok(signature) => {
-            let encoded = fetch_transaction_json(rpc, signature).await?;
-            let parsed_transaction = parse_transaction(encoded).expect("Should parse transaction");
-            let dropset_events = parsed_transaction
-                .instructions
-                .iter()
-                .flat_map(|outer| {
-                    outer.inner_instructions.iter().flat_map(|inner_ixn| {
-                        inner_ixn
-                            .parse_events()
-                            .expect("Should be able to parse events")
-                    })
-                })
-                .collect_vec();
-
-            if matches!(config.debug_logs, Some(true)) {
-                print!(
-                    "{}",
-                    PrettyTransaction {
-                        sender: payer.pubkey(),
-                        signature,
-                        indent_size: 2,
-                        transaction: &parsed_transaction,
-                        instruction_filter: &config.program_id_filter,
-                    }
-                );
-
-                for event in dropset_events.iter() {
-                    println!("{event:?}");
-                }
-            }
-
-            Ok(ParsedTransactionWithEvents {
-                parsed_transaction,
-                events: dropset_events,
-            })
+            fetch_sent_transaction(&rpc.client, payer.pubkey(), signature, config).await
         }
         Err(error) => {
-            PrettyInstructionError::new(&error, final_instructions).inspect(|err| {
-                print!("{err}");
-                print_kv!("Payer", payer.pubkey(), LogColor::Error);
-            });
+            log_send_error(&error, payer.pubkey(), config);
             Err(error).context("Failed transaction submission")
         }
     }
 }
 
-async fn fetch_transaction_json(
+/// Pretty-prints a failed send to stderr if [`SendTransactionConfig::debug_logs`] is set. The
+/// error itself is always returned to the caller.
+fn log_send_error(error: &SendError, payer: Address, config: &SendTransactionConfig) {
+    if !matches!(config.debug_logs, Some(true)) {
+        return;
+    }
+    match PrettyInstructionError::new(error) {
+        Some(pretty) => eprint!("{pretty}"),
+        None => eprintln!("{}", fmt_kv!("SendError", error, LogColor::Error)),
+    }
+    eprintln!("{}", fmt_kv!("Payer", payer, LogColor::Error));
+}
+
+/// Fetches and parses a confirmed transaction and its events, pretty-printing them if
+/// [`SendTransactionConfig::debug_logs`] is set.
+pub(crate) async fn fetch_sent_transaction(
+    rpc: &RpcClient,
+    payer: Address,
+    signature: Signature,
+    config: &SendTransactionConfig,
+) -> anyhow::Result<ParsedTransactionWithEvents> {
+    let encoded = fetch_transaction_json(rpc, signature).await?;
+    let parsed_transaction = parse_transaction(encoded).expect("Should parse transaction");
+    let dropset_events = parsed_transaction
+        .instructions
+        .iter()
+        .flat_map(|outer| {
+            outer.inner_instructions.iter().flat_map(|inner_ixn| {
+                inner_ixn
+                    .parse_events()
+                    .expect("Should be able to parse events")
+            })
+        })
+        .collect_vec();
+
+    if matches!(config.debug_logs, Some(true)) {
+        print!(
+            "{}",
+            PrettyTransaction {
+                sender: payer,
+                signature,
+                indent_size: 2,
+                transaction: &parsed_transaction,
+                instruction_filter: &config.program_id_filter,
+            }
+        );
+
+        for event in dropset_events.iter() {
+            println!("{event:?}");
+        }
+    }
+
+    Ok(ParsedTransactionWithEvents {
+        parsed_transaction,
+        events: dropset_events,
+    })
+}
+
+pub(crate) async fn fetch_transaction_json(
     rpc: &RpcClient,
     sig: Signature,
 ) -> anyhow::Result<EncodedConfirmedTransactionWithStatusMeta> {
@@ -0,0 +1,190 @@
+use client::backfill::{
+    backfill,
+    BackfillCheckpoint,
+    DirectoryTransactionSource,
+};
+use dropset_interface::{
+    events::{
+        DepositEventInstructionData,
+        HeaderEventInstructionData,
+        WithdrawEventInstructionData,
+    },
+    instructions::DropsetInstruction,
+};
+use futures::{
+    executor::block_on,
+    TryStreamExt,
+};
+use instruction_macros_traits::Tagged;
+use solana_address::Address;
+use solana_instruction::{
+    AccountMeta,
+    Instruction,
+};
+use solana_sdk::{
+    message::{
+        compiled_instruction::CompiledInstruction,
+        Message,
+    },
+    signature::Signature,
+    transaction::Transaction,
+};
+use solana_transaction_status::{
+    Encodable,
+    EncodedConfirmedTransactionWithStatusMeta,
+    EncodedTransactionWithStatusMeta,
+    InnerInstruction,
+    InnerInstructions,
+    TransactionStatusMeta,
+    UiTransactionEncoding,
+};
+use transaction_parser::events::dropset_event::DropsetEvent;
+
+const MARKET: Address = Address::new_from_array([1; 32]);
+const OTHER_MARKET: Address = Address::new_from_array([2; 32]);
+
+/// Builds a transaction whose single outer `dropset` instruction flushes `events` for `market`,
+/// where `num_events` is the market's total number of events after the flush.
+fn flush_transaction(
+    slot: u64,
+    signature: u8,
+    market: Address,
+    num_events: u64,
+    events: &[Vec<u8>],
+) -> EncodedConfirmedTransactionWithStatusMeta {
+    let payer = Address::new_from_array([9; 32]);
+    let outer = Instruction::new_with_bytes(
+        dropset::ID,
+        &[DropsetInstruction::Deposit as u8],
+        vec![AccountMeta::new(payer, true)],
+    );
+    let mut transaction = Transaction::new_unsigned(Message::new(&[outer], Some(&payer)));
+    transaction.signatures = vec![Signature::from([signature; 64])];
+
+    let program_id_index = transaction
+        .message
+        .account_keys
+        .iter()
+        .position(|key| *key == dropset::ID)
+        .unwrap() as u8;
+    let header = HeaderEventInstructionData::new(
+        DropsetInstruction::Deposit as u8,
+        events.len() as u16,
+        num_events,
+        market,
+    );
+    let flush_data = [
+        &[DropsetInstruction::FlushEvents as u8][..],
+        header.pack_tagged().as_slice(),
+        &events.concat(),
+    ]
+    .concat();
+
+    let meta = TransactionStatusMeta {
+        inner_instructions: Some(vec![InnerInstructions {
+            index: 0,
+            instructions: vec![InnerInstruction {
+                instruction: CompiledInstruction::new_from_raw_parts(
+                    program_id_index,
+                    flush_data,
+                    vec![],
+                ),
+                stack_height: Some(2),
+            }],
+        }]),
+        log_messages: Some(vec![]),
+        ..Default::default()
+    };
+
+    EncodedConfirmedTransactionWithStatusMeta {
+        slot,
+        transaction: EncodedTransactionWithStatusMeta {
+            transaction: transaction.encode(UiTransactionEncoding::Base64),
+            meta: Some(meta.into()),
+            version: None,
+        },
+        block_time: Some(1_700_000_000 + slot as i64),
+    }
+}
+
+fn deposit(amount: u64) -> Vec<u8> {
+    DepositEventInstructionData::new(amount, true, 0)
+        .pack_tagged()
+        .to_vec()
+}
+
+fn withdraw(amount: u64) -> Vec<u8> {
+    WithdrawEventInstructionData::new(amount, true)
+        .pack_tagged()
+        .to_vec()
+}
+
+fn amount(event: &DropsetEvent) -> u64 {
+    match event {
+        DropsetEvent::Deposit(e) => e.amount,
+        DropsetEvent::Withdraw(e) => e.amount,
+        other => panic!("Unexpected event: {other:?}"),
+    }
+}
+
+#[test]
+fn backfill_from_directory() {
+    let dir = std::env::temp_dir().join(format!("dropset-backfill-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    // Saved out of order to check that the source orders transactions by slot.
+    let transactions = [
+        flush_transaction(12, 3, MARKET, 4, &[withdraw(40)]),
+        flush_transaction(10, 1, MARKET, 2, &[deposit(10), deposit(20)]),
+        flush_transaction(11, 2, OTHER_MARKET, 1, &[deposit(99)]),
+        flush_transaction(11, 4, MARKET, 3, &[deposit(30)]),
+    ];
+    for transaction in transactions.iter() {
+        DirectoryTransactionSource::save(&dir, transaction).unwrap();
+    }
+    let source = DirectoryTransactionSource::load(&dir).unwrap();
+
+    let events = block_on(backfill(&source, MARKET, None).try_collect::<Vec<_>>()).unwrap();
+    assert_eq!(
+        events.iter().map(|e| e.event.seq).collect::<Vec<_>>(),
+        [1, 2, 3, 4]
+    );
+    assert_eq!(
+        events
+            .iter()
+            .map(|e| amount(&e.event.event))
+            .collect::<Vec<_>>(),
+        [10, 20, 30, 40]
+    );
+    assert_eq!(
+        events.iter().map(|e| e.slot).collect::<Vec<_>>(),
+        [10, 10, 11, 12]
+    );
+    assert!(events.iter().all(|e| e.event.market == MARKET));
+    assert_eq!(events[2].signature, Signature::from([4; 64]));
+    assert_eq!(events[2].block_time, Some(1_700_000_011));
+
+    // Resuming mid-transaction picks up the rest of that transaction's events.
+    let checkpoint = events[0].checkpoint();
+    assert_eq!(
+        checkpoint,
+        BackfillCheckpoint {
+            signature: Signature::from([1; 64]),
+            seq: 1,
+        }
+    );
+    let resumed =
+        block_on(backfill(&source, MARKET, Some(checkpoint)).try_collect::<Vec<_>>()).unwrap();
+    assert_eq!(
+        resumed.iter().map(|e| e.event.seq).collect::<Vec<_>>(),
+        [2, 3, 4]
+    );
+
+    // Resuming from the last event yields nothing new.
+    let resumed =
+        block_on(backfill(&source, MARKET, Some(events[3].checkpoint())).try_collect::<Vec<_>>())
+            .unwrap();
+    assert!(resumed.is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,499 @@
+//! Differential tests for the program's matching engine.
+//!
+//! Random streams of post, cancel and market orders are run through both the `dropset` program
+//! under Mollusk and the plain-Rust [`ReferenceBook`]. After every instruction the two must agree
+//! on whether it succeeded, which events it emitted, the full book and seat state, and every token
+//! account balance.
+
+mod reference_book;
+
+use std::collections::HashMap;
+
+use client::{
+    e2e_helpers::mollusk::{
+        market_account,
+        mint_account,
+        new_dropset_mollusk_context,
+        token_account,
+    },
+    pda::find_market_address,
+};
+use dropset_interface::{
+    instructions::{
+        generated_client::*,
+        CancelOrderInstructionData,
+        DepositInstructionData,
+        DropsetInstruction,
+        MarketOrderInstructionData,
+        PostOrderInstructionData,
+    },
+    seeds::event_authority,
+    state::{
+        market_header::MarketHeader,
+        sector::{
+            SectorIndex,
+            SECTOR_SIZE,
+        },
+        transmutable::Transmutable,
+        user_order_sectors::OrderSectors,
+        SYSTEM_PROGRAM_ID,
+    },
+};
+use mollusk_svm::{
+    result::InstructionResult,
+    MolluskContext,
+};
+use price::OrderInfoArgs;
+use proptest::prelude::*;
+use reference_book::{
+    BookSnapshot,
+    RefEvent,
+    RefOrder,
+    RefResult,
+    ReferenceBook,
+    SeatSnapshot,
+};
+use solana_account::Account;
+use solana_address::Address;
+use solana_instruction::Instruction;
+use solana_sdk::program_pack::Pack;
+use spl_associated_token_account_interface::address::get_associated_token_address;
+use spl_token_interface::state::Account as TokenAccount;
+use transaction_parser::{
+    events::dropset_event::{
+        unpack_instruction_events,
+        DropsetEvent,
+    },
+    views::{
+        try_market_view_all_from_owner_and_data,
+        OrderView,
+    },
+};
+
+const NUM_TRADERS: usize = 3;
+/// Small enough that posting orders regularly runs out of free sectors.
+const NUM_SECTORS: u32 = 16;
+const LAMPORTS: u64 = 10_000_000_000;
+
+/// Each trader starts with these token balances and deposits half of each into their seat.
+const INITIAL_BASE: u64 = 2_000;
+const INITIAL_QUOTE: u64 = 200_000_000_000;
+
+/// Prices are drawn from a narrow band so that orders regularly share a price level.
+const MIN_PRICE_MANTISSA: u32 = 10_000_000;
+const PRICE_TICK: u32 = 1_000;
+const NUM_PRICE_LEVELS: u32 = 24;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Post {
+        trader: usize,
+        is_bid: bool,
+        price_level: u32,
+        base_scalar: u64,
+    },
+    /// Cancels the trader's order at `selector % num_orders`, or a nonexistent order if they have
+    /// none on that side.
+    Cancel {
+        trader: usize,
+        is_bid: bool,
+        selector: usize,
+    },
+    MarketOrder {
+        trader: usize,
+        is_buy: bool,
+        is_base: bool,
+        order_size: u64,
+    },
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let trader = 0..NUM_TRADERS;
+    prop_oneof![
+        5 => (trader.clone(), any::<bool>(), 0..NUM_PRICE_LEVELS, 1..=20u64).prop_map(
+            |(trader, is_bid, price_level, base_scalar)| Op::Post {
+                trader,
+                is_bid,
+                price_level,
+                base_scalar,
+            }
+        ),
+        2 => (trader.clone(), any::<bool>(), any::<usize>()).prop_map(
+            |(trader, is_bid, selector)| Op::Cancel {
+                trader,
+                is_bid,
+                selector,
+            }
+        ),
+        3 => (trader, any::<bool>(), any::<bool>(), 0..=400_000_000u64).prop_map(
+            |(trader, is_buy, is_base, size)| Op::MarketOrder {
+                trader,
+                is_buy,
+                is_base,
+                // Base atoms are worth roughly `MIN_PRICE_MANTISSA` quote atoms each.
+                order_size: if is_base { size % 48 } else { size },
+            }
+        ),
+    ]
+}
+
+fn to_ref_event(event: DropsetEvent) -> RefEvent {
+    match event {
+        DropsetEvent::Deposit(e) => RefEvent::Deposit {
+            amount: e.amount,
+            is_base: e.is_base,
+            seat_sector_index: e.seat_sector_index,
+        },
+        DropsetEvent::Withdraw(e) => RefEvent::Withdraw {
+            amount: e.amount,
+            is_base: e.is_base,
+        },
+        DropsetEvent::PostOrder(e) => RefEvent::PostOrder {
+            user: e.user,
+            is_bid: e.is_bid,
+            encoded_price: e.encoded_price,
+            user_seat_sector_index: e.user_seat_sector_index,
+            order_sector_index: e.order_sector_index,
+            base_atoms: e.base_atoms,
+            quote_atoms: e.quote_atoms,
+        },
+        DropsetEvent::CancelOrder(e) => RefEvent::CancelOrder {
+            user: e.user,
+            is_bid: e.is_bid,
+            encoded_price: e.encoded_price,
+            user_seat_sector_index: e.user_seat_sector_index,
+            order_sector_index: e.order_sector_index,
+            base_remaining: e.base_remaining,
+            quote_remaining: e.quote_remaining,
+        },
+        DropsetEvent::MarketOrder(e) => RefEvent::MarketOrder {
+            order_size: e.order_size,
+            is_buy: e.is_buy,
+            is_base: e.is_base,
+            base_filled: e.base_filled,
+            quote_filled: e.quote_filled,
+        },
+        DropsetEvent::MakerFill(e) => RefEvent::MakerFill {
+            order_sector_index: e.order_sector_index,
+            base_filled: e.base_filled,
+            quote_filled: e.quote_filled,
+        },
+        other => panic!("Unexpected event: {other:?}"),
+    }
+}
+
+struct Harness {
+    mollusk: MolluskContext<HashMap<Address, Account>>,
+    reference: ReferenceBook,
+    market: Address,
+    base_mint: Address,
+    quote_mint: Address,
+    traders: Vec<Address>,
+}
+
+impl Harness {
+    /// Creates a market and traders that have each deposited half of their initial balances.
+    fn new() -> Self {
+        let base_mint = Address::new_unique();
+        let quote_mint = Address::new_unique();
+        let (market, bump) = find_market_address(&base_mint, &quote_mint);
+        let traders: Vec<Address> = (0..NUM_TRADERS).map(|_| Address::new_unique()).collect();
+
+        let mut accounts = vec![
+            (base_mint, mint_account(INITIAL_BASE * NUM_TRADERS as u64)),
+            (quote_mint, mint_account(INITIAL_QUOTE * NUM_TRADERS as u64)),
+            (
+                market,
+                market_account(bump, &base_mint, &quote_mint, NUM_SECTORS),
+            ),
+            (
+                get_associated_token_address(&market, &base_mint),
+                token_account(base_mint, market, 0),
+            ),
+            (
+                get_associated_token_address(&market, &quote_mint),
+                token_account(quote_mint, market, 0),
+            ),
+            (
+                dropset::ID,
+                mollusk_svm::program::create_program_account_loader_v3(&dropset::ID),
+            ),
+            mollusk_svm_programs_token::token::keyed_account(),
+        ];
+        let mut reference = ReferenceBook::new(NUM_SECTORS);
+        for trader in traders.iter() {
+            accounts.extend([
+                (*trader, Account::new(LAMPORTS, 0, &SYSTEM_PROGRAM_ID)),
+                (
+                    get_associated_token_address(trader, &base_mint),
+                    token_account(base_mint, *trader, INITIAL_BASE),
+                ),
+                (
+                    get_associated_token_address(trader, &quote_mint),
+                    token_account(quote_mint, *trader, INITIAL_QUOTE),
+                ),
+            ]);
+            reference.fund_wallet(*trader, INITIAL_BASE, INITIAL_QUOTE);
+        }
+
+        let mut mollusk = new_dropset_mollusk_context(accounts);
+        mollusk_svm_programs_token::token::add_program(&mut mollusk.mollusk);
+
+        let mut harness = Self {
+            mollusk,
+            reference,
+            market,
+            base_mint,
+            quote_mint,
+            traders,
+        };
+
+        for trader in harness.traders.clone() {
+            harness.deposit(trader, true, INITIAL_BASE / 2);
+            harness.deposit(trader, false, INITIAL_QUOTE / 2);
+        }
+
+        harness
+    }
+
+    fn deposit(&mut self, user: Address, is_base: bool, amount: u64) {
+        let hint = self.reference.seat_index(&user);
+        let mint = if is_base { self.base_mint } else { self.quote_mint };
+        let instruction = Deposit {
+            event_authority: event_authority::ID,
+            user,
+            market_account: self.market,
+            user_ata: get_associated_token_address(&user, &mint),
+            market_ata: get_associated_token_address(&self.market, &mint),
+            mint,
+            token_program: spl_token_interface::ID,
+            dropset_program: dropset::ID,
+        }
+        .create_instruction(DepositInstructionData::new(amount, hint));
+
+        let expected = self.reference.deposit(user, is_base, amount, hint);
+        assert!(expected.is_ok(), "Setup deposits should succeed");
+        self.process(instruction, expected);
+    }
+
+    fn apply(&mut self, op: &Op) {
+        match *op {
+            Op::Post {
+                trader,
+                is_bid,
+                price_level,
+                base_scalar,
+            } => {
+                let user = self.traders[trader];
+                let hint = self.reference.seat_index(&user);
+                let args = OrderInfoArgs::new_unscaled(
+                    MIN_PRICE_MANTISSA + price_level * PRICE_TICK,
+                    base_scalar,
+                );
+                let instruction = PostOrder {
+                    event_authority: event_authority::ID,
+                    user,
+                    market_account: self.market,
+                    dropset_program: dropset::ID,
+                }
+                .create_instruction(PostOrderInstructionData::new(args.clone(), is_bid, hint));
+
+                let expected = self.reference.post_order(user, args, is_bid, hint);
+                self.process(instruction, expected);
+            }
+            Op::Cancel {
+                trader,
+                is_bid,
+                selector,
+            } => {
+                let user = self.traders[trader];
+                let hint = self.reference.seat_index(&user);
+                let prices = self.reference.order_prices(&user, is_bid);
+                let encoded_price = match prices.len() {
+                    0 => MIN_PRICE_MANTISSA,
+                    len => prices[selector % len],
+                };
+                let instruction = CancelOrder {
+                    event_authority: event_authority::ID,
+                    user,
+                    market_account: self.market,
+                    dropset_program: dropset::ID,
+                }
+                .create_instruction(CancelOrderInstructionData::new(encoded_price, is_bid, hint));
+
+                let expected = self
+                    .reference
+                    .cancel_order(user, encoded_price, is_bid, hint);
+                self.process(instruction, expected);
+            }
+            Op::MarketOrder {
+                trader,
+                is_buy,
+                is_base,
+                order_size,
+            } => {
+                let user = self.traders[trader];
+                let instruction = MarketOrder {
+                    event_authority: event_authority::ID,
+                    user,
+                    market_account: self.market,
+                    base_user_ata: get_associated_token_address(&user, &self.base_mint),
+                    quote_user_ata: get_associated_token_address(&user, &self.quote_mint),
+                    base_market_ata: get_associated_token_address(&self.market, &self.base_mint),
+                    quote_market_ata: get_associated_token_address(&self.market, &self.quote_mint),
+                    base_mint: self.base_mint,
+                    quote_mint: self.quote_mint,
+                    base_token_program: spl_token_interface::ID,
+                    quote_token_program: spl_token_interface::ID,
+                    dropset_program: dropset::ID,
+                }
+                .create_instruction(MarketOrderInstructionData::new(order_size, is_buy, is_base));
+
+                let expected = self
+                    .reference
+                    .market_order(user, order_size, is_buy, is_base);
+                self.process(instruction, expected);
+            }
+        }
+    }
+
+    fn process(&mut self, instruction: Instruction, expected: RefResult) {
+        let result = self.mollusk.process_instruction(&instruction);
+        assert_eq!(
+            result.program_result.is_ok(),
+            expected.is_ok(),
+            "Program result {:?} doesn't match the reference result {expected:?}",
+            result.program_result,
+        );
+        if let Ok(expected_events) = expected {
+            assert_eq!(emitted_events(&result), expected_events);
+        }
+        self.assert_state_matches();
+    }
+
+    fn get_account(&self, address: &Address) -> Account {
+        self.mollusk
+            .account_store
+            .borrow()
+            .get(address)
+            .cloned()
+            .expect("Account should exist")
+    }
+
+    fn token_balance(&self, owner: &Address, mint: &Address) -> u64 {
+        let account = self.get_account(&get_associated_token_address(owner, mint));
+        TokenAccount::unpack(&account.data)
+            .expect("Should unpack token account")
+            .amount
+    }
+
+    fn assert_state_matches(&self) {
+        let market_account = self.get_account(&self.market);
+        let view =
+            try_market_view_all_from_owner_and_data(market_account.owner, &market_account.data)
+                .expect("Should parse market account");
+
+        let to_orders = |orders: &[OrderView]| {
+            orders
+                .iter()
+                .map(|order| RefOrder {
+                    index: order.index,
+                    encoded_price: order.encoded_price,
+                    user_seat: order.user_seat,
+                    base_remaining: order.base_remaining,
+                    quote_remaining: order.quote_remaining,
+                })
+                .collect()
+        };
+
+        let actual = BookSnapshot {
+            seats: view
+                .seats
+                .iter()
+                .map(|seat| SeatSnapshot {
+                    index: seat.index,
+                    user: seat.user,
+                    base_available: seat.base_available,
+                    quote_available: seat.quote_available,
+                    bids: order_sector_pairs(&seat.user_order_sectors.bids),
+                    asks: order_sector_pairs(&seat.user_order_sectors.asks),
+                })
+                .collect(),
+            bids: to_orders(&view.bids),
+            asks: to_orders(&view.asks),
+            num_sectors: ((market_account.data.len() - MarketHeader::LEN) / SECTOR_SIZE) as u32,
+            num_free_sectors: view.header.num_free_sectors,
+            num_events: view.header.nonce,
+        };
+        assert_eq!(actual, self.reference.snapshot());
+
+        for trader in self.traders.iter() {
+            let wallet = self.reference.wallet(trader);
+            assert_eq!(self.token_balance(trader, &self.base_mint), wallet.base);
+            assert_eq!(self.token_balance(trader, &self.quote_mint), wallet.quote);
+        }
+        let vault = self.reference.vault();
+        assert_eq!(
+            self.token_balance(&self.market, &self.base_mint),
+            vault.base
+        );
+        assert_eq!(
+            self.token_balance(&self.market, &self.quote_mint),
+            vault.quote
+        );
+    }
+}
+
+/// Returns a seat's non-free `(encoded price, order sector index)` entries, sorted by price.
+fn order_sector_pairs(order_sectors: &OrderSectors) -> Vec<(u32, SectorIndex)> {
+    let mut pairs: Vec<_> = order_sectors
+        .iter()
+        .filter(|entry| !entry.is_free())
+        .map(|entry| {
+            (
+                u32::from_le_bytes(entry.encoded_price.as_array()),
+                u32::from_le_bytes(entry.sector_index),
+            )
+        })
+        .collect();
+    pairs.sort_unstable();
+    pairs
+}
+
+/// Collects the events from every `FlushEvents` self-CPI in the instruction's inner instructions.
+fn emitted_events(result: &InstructionResult) -> Vec<RefEvent> {
+    result
+        .inner_instructions
+        .iter()
+        .filter_map(|inner| match inner.instruction.data.split_first() {
+            Some((tag, data)) if *tag == DropsetInstruction::FlushEvents as u8 => Some(data),
+            _ => None,
+        })
+        .flat_map(|data| unpack_instruction_events(data).expect("Should unpack flushed events"))
+        .map(to_ref_event)
+        .collect()
+}
+
+#[test]
+fn setup_matches_reference() {
+    let harness = Harness::new();
+    assert_eq!(harness.reference.snapshot().seats.len(), NUM_TRADERS);
+    assert_eq!(
+        harness.reference.snapshot().num_events,
+        NUM_TRADERS as u64 * 2
+    );
+}
+
+proptest! {
+    // Each case spins up a Mollusk instance and processes every instruction through the SVM, so
+    // keep the number of cases modest.
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn matching_engine_matches_reference(ops in prop::collection::vec(op(), 1..64)) {
+        let mut harness = Harness::new();
+        for op in ops.iter() {
+            harness.apply(op);
+        }
+    }
+}
@@ -0,0 +1,605 @@
+//! A plain-Rust reference model of a single `dropset` market, used for differential testing.
+//!
+//! It shares nothing with the program beyond price encoding: seats, orders and the free sector
+//! stack live in ordinary collections, and matching walks a `Vec` of orders in price-time priority.
+//! Sector indices are still modeled exactly, since they're observable through seat hints, order
+//! sector indices and emitted events.
+//!
+//! Every operation is atomic: it runs against a copy of the book and is only committed on success,
+//! mirroring a transaction that fails and rolls back.
+
+use std::collections::{
+    BTreeMap,
+    HashMap,
+};
+
+use dropset_interface::state::{
+    sector::{
+        SectorIndex,
+        NIL,
+    },
+    user_order_sectors::MAX_ORDERS,
+};
+use price::{
+    to_order_info,
+    OrderInfoArgs,
+};
+use solana_address::Address;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Balances {
+    pub base: u64,
+    pub quote: u64,
+}
+
+impl Balances {
+    fn get_mut(&mut self, is_base: bool) -> &mut u64 {
+        match is_base {
+            true => &mut self.base,
+            false => &mut self.quote,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RefOrder {
+    pub index: SectorIndex,
+    pub encoded_price: u32,
+    pub user_seat: SectorIndex,
+    pub base_remaining: u64,
+    pub quote_remaining: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SeatSnapshot {
+    pub index: SectorIndex,
+    pub user: Address,
+    pub base_available: u64,
+    pub quote_available: u64,
+    /// The seat's bids as `(encoded price, order sector index)` pairs, sorted by price.
+    pub bids: Vec<(u32, SectorIndex)>,
+    /// The seat's asks as `(encoded price, order sector index)` pairs, sorted by price.
+    pub asks: Vec<(u32, SectorIndex)>,
+}
+
+/// Everything about a market that's observable from its account data.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BookSnapshot {
+    /// Seats sorted by user address.
+    pub seats: Vec<SeatSnapshot>,
+    /// Bids in price-time priority, i.e., the order they'd be filled in.
+    pub bids: Vec<RefOrder>,
+    /// Asks in price-time priority, i.e., the order they'd be filled in.
+    pub asks: Vec<RefOrder>,
+    pub num_sectors: u32,
+    pub num_free_sectors: u32,
+    pub num_events: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RefEvent {
+    Deposit {
+        amount: u64,
+        is_base: bool,
+        seat_sector_index: SectorIndex,
+    },
+    Withdraw {
+        amount: u64,
+        is_base: bool,
+    },
+    PostOrder {
+        user: Address,
+        is_bid: bool,
+        encoded_price: u32,
+        user_seat_sector_index: SectorIndex,
+        order_sector_index: SectorIndex,
+        base_atoms: u64,
+        quote_atoms: u64,
+    },
+    CancelOrder {
+        user: Address,
+        is_bid: bool,
+        encoded_price: u32,
+        user_seat_sector_index: SectorIndex,
+        order_sector_index: SectorIndex,
+        base_remaining: u64,
+        quote_remaining: u64,
+    },
+    MarketOrder {
+        order_size: u64,
+        is_buy: bool,
+        is_base: bool,
+        base_filled: u64,
+        quote_filled: u64,
+    },
+    MakerFill {
+        order_sector_index: SectorIndex,
+        base_filled: u64,
+        quote_filled: u64,
+    },
+}
+
+/// Why an operation failed. The harness only compares success against failure, so these don't
+/// need to map one-to-one to program errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RefError {
+    AmountCannotBeZero,
+    InsufficientTokenBalance,
+    InsufficientSeatBalance,
+    InvalidIndexHint,
+    InvalidOrderInfo,
+    NoFreeSectors,
+    OrderNotFound,
+    OrderWithPriceAlreadyExists,
+    Overflow,
+    PostOnlyWouldImmediatelyFill,
+    UserAlreadyExists,
+    UserHasMaxOrders,
+}
+
+pub type RefResult = Result<Vec<RefEvent>, RefError>;
+
+#[derive(Clone, Debug)]
+struct Seat {
+    index: SectorIndex,
+    base_available: u64,
+    quote_available: u64,
+    /// Maps each resting bid's encoded price to its order sector index.
+    bids: BTreeMap<u32, SectorIndex>,
+    /// Maps each resting ask's encoded price to its order sector index.
+    asks: BTreeMap<u32, SectorIndex>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ReferenceBook {
+    num_sectors: u32,
+    /// The free sector stack, with the top at the end.
+    free: Vec<SectorIndex>,
+    seats: BTreeMap<Address, Seat>,
+    /// Descending by price, then ascending by time.
+    bids: Vec<RefOrder>,
+    /// Ascending by price, then ascending by time.
+    asks: Vec<RefOrder>,
+    /// Each user's token account balances.
+    wallets: HashMap<Address, Balances>,
+    /// The market's token account balances.
+    vault: Balances,
+    num_events: u64,
+}
+
+fn credit(balance: &mut u64, amount: u64) -> Result<(), RefError> {
+    *balance = balance.checked_add(amount).ok_or(RefError::Overflow)?;
+    Ok(())
+}
+
+fn debit(balance: &mut u64, amount: u64, err: RefError) -> Result<(), RefError> {
+    *balance = balance.checked_sub(amount).ok_or(err)?;
+    Ok(())
+}
+
+impl ReferenceBook {
+    /// Creates a freshly registered market with `num_sectors` free sectors.
+    pub fn new(num_sectors: u32) -> Self {
+        Self {
+            num_sectors,
+            // Sectors are pushed in reverse so that sector 0 is on top.
+            free: (0..num_sectors).rev().collect(),
+            seats: BTreeMap::new(),
+            bids: vec![],
+            asks: vec![],
+            wallets: HashMap::new(),
+            vault: Balances::default(),
+            num_events: 0,
+        }
+    }
+
+    pub fn fund_wallet(&mut self, user: Address, base: u64, quote: u64) {
+        self.wallets.insert(user, Balances { base, quote });
+    }
+
+    pub fn wallet(&self, user: &Address) -> Balances {
+        self.wallets.get(user).copied().unwrap_or_default()
+    }
+
+    pub fn vault(&self) -> Balances {
+        self.vault
+    }
+
+    /// Returns the user's seat sector index, or [`NIL`] if they don't have a seat.
+    pub fn seat_index(&self, user: &Address) -> SectorIndex {
+        self.seats.get(user).map_or(NIL, |seat| seat.index)
+    }
+
+    /// Returns the encoded prices of the user's resting orders on one side of the book.
+    pub fn order_prices(&self, user: &Address, is_bid: bool) -> Vec<u32> {
+        self.seats.get(user).map_or(vec![], |seat| {
+            let orders = if is_bid { &seat.bids } else { &seat.asks };
+            orders.keys().copied().collect()
+        })
+    }
+
+    pub fn snapshot(&self) -> BookSnapshot {
+        let to_pairs = |orders: &BTreeMap<u32, SectorIndex>| {
+            orders
+                .iter()
+                .map(|(price, index)| (*price, *index))
+                .collect()
+        };
+
+        BookSnapshot {
+            seats: self
+                .seats
+                .iter()
+                .map(|(user, seat)| SeatSnapshot {
+                    index: seat.index,
+                    user: *user,
+                    base_available: seat.base_available,
+                    quote_available: seat.quote_available,
+                    bids: to_pairs(&seat.bids),
+                    asks: to_pairs(&seat.asks),
+                })
+                .collect(),
+            bids: self.bids.clone(),
+            asks: self.asks.clone(),
+            num_sectors: self.num_sectors,
+            num_free_sectors: self.free.len() as u32,
+            num_events: self.num_events,
+        }
+    }
+
+    pub fn deposit(
+        &mut self,
+        user: Address,
+        is_base: bool,
+        amount: u64,
+        hint: SectorIndex,
+    ) -> RefResult {
+        self.transact(|book| book.try_deposit(user, is_base, amount, hint))
+    }
+
+    pub fn post_order(
+        &mut self,
+        user: Address,
+        args: OrderInfoArgs,
+        is_bid: bool,
+        hint: SectorIndex,
+    ) -> RefResult {
+        self.transact(|book| book.try_post_order(user, args, is_bid, hint))
+    }
+
+    pub fn cancel_order(
+        &mut self,
+        user: Address,
+        encoded_price: u32,
+        is_bid: bool,
+        hint: SectorIndex,
+    ) -> RefResult {
+        self.transact(|book| book.try_cancel_order(user, encoded_price, is_bid, hint))
+    }
+
+    pub fn market_order(
+        &mut self,
+        user: Address,
+        order_size: u64,
+        is_buy: bool,
+        is_base: bool,
+    ) -> RefResult {
+        self.transact(|book| book.try_market_order(user, order_size, is_buy, is_base))
+    }
+
+    /// Runs `op` on a copy of the book and commits it only if it succeeds. Any emitted events are
+    /// counted towards the market's total number of events.
+    fn transact(&mut self, op: impl FnOnce(&mut Self) -> RefResult) -> RefResult {
+        let mut next = self.clone();
+        let events = op(&mut next)?;
+        next.num_events += events.len() as u64;
+        *self = next;
+        Ok(events)
+    }
+
+    fn seat_with_hint(&mut self, user: &Address, hint: SectorIndex) -> Result<&mut Seat, RefError> {
+        self.seats
+            .get_mut(user)
+            .filter(|seat| seat.index == hint)
+            .ok_or(RefError::InvalidIndexHint)
+    }
+
+    fn try_deposit(
+        &mut self,
+        user: Address,
+        is_base: bool,
+        amount: u64,
+        hint: SectorIndex,
+    ) -> RefResult {
+        let wallet = self.wallets.entry(user).or_default();
+        debit(
+            wallet.get_mut(is_base),
+            amount,
+            RefError::InsufficientTokenBalance,
+        )?;
+        credit(self.vault.get_mut(is_base), amount)?;
+        if amount == 0 {
+            return Err(RefError::AmountCannotBeZero);
+        }
+
+        let seat_sector_index = if hint != NIL {
+            let seat = self.seat_with_hint(&user, hint)?;
+            match is_base {
+                true => credit(&mut seat.base_available, amount)?,
+                false => credit(&mut seat.quote_available, amount)?,
+            }
+            hint
+        } else {
+            // Registering a seat grows the market by a single sector if none are free.
+            if self.free.is_empty() {
+                self.free.push(self.num_sectors);
+                self.num_sectors += 1;
+            }
+            if self.seats.contains_key(&user) {
+                return Err(RefError::UserAlreadyExists);
+            }
+            let index = self.free.pop().expect("A free sector was just ensured");
+            let (base_available, quote_available) = match is_base {
+                true => (amount, 0),
+                false => (0, amount),
+            };
+            self.seats.insert(
+                user,
+                Seat {
+                    index,
+                    base_available,
+                    quote_available,
+                    bids: BTreeMap::new(),
+                    asks: BTreeMap::new(),
+                },
+            );
+            index
+        };
+
+        Ok(vec![RefEvent::Deposit {
+            amount,
+            is_base,
+            seat_sector_index,
+        }])
+    }
+
+    fn try_post_order(
+        &mut self,
+        user: Address,
+        args: OrderInfoArgs,
+        is_bid: bool,
+        hint: SectorIndex,
+    ) -> RefResult {
+        let order_info = to_order_info(args).map_err(|_| RefError::InvalidOrderInfo)?;
+        let price = order_info.encoded_price.as_u32();
+
+        // Orders are post-only, so they can't cross the best price on the other side.
+        let crosses = match is_bid {
+            true => self
+                .asks
+                .first()
+                .is_some_and(|ask| price >= ask.encoded_price),
+            false => self
+                .bids
+                .first()
+                .is_some_and(|bid| price <= bid.encoded_price),
+        };
+        if crosses {
+            return Err(RefError::PostOnlyWouldImmediatelyFill);
+        }
+
+        let index = self.free.pop().ok_or(RefError::NoFreeSectors)?;
+        let seat = self.seat_with_hint(&user, hint)?;
+        let user_seat = seat.index;
+        let (collateral, collateral_amount, orders) = match is_bid {
+            true => (
+                &mut seat.quote_available,
+                order_info.quote_atoms,
+                &mut seat.bids,
+            ),
+            false => (
+                &mut seat.base_available,
+                order_info.base_atoms,
+                &mut seat.asks,
+            ),
+        };
+        debit(
+            collateral,
+            collateral_amount,
+            RefError::InsufficientSeatBalance,
+        )?;
+        if orders.contains_key(&price) {
+            return Err(RefError::OrderWithPriceAlreadyExists);
+        }
+        if orders.len() == MAX_ORDERS as usize {
+            return Err(RefError::UserHasMaxOrders);
+        }
+        orders.insert(price, index);
+
+        // New orders go behind every resting order at the same price.
+        let side = if is_bid { &mut self.bids } else { &mut self.asks };
+        let position = side
+            .iter()
+            .position(|order| match is_bid {
+                true => order.encoded_price < price,
+                false => order.encoded_price > price,
+            })
+            .unwrap_or(side.len());
+        side.insert(
+            position,
+            RefOrder {
+                index,
+                encoded_price: price,
+                user_seat,
+                base_remaining: order_info.base_atoms,
+                quote_remaining: order_info.quote_atoms,
+            },
+        );
+
+        Ok(vec![RefEvent::PostOrder {
+            user,
+            is_bid,
+            encoded_price: price,
+            user_seat_sector_index: user_seat,
+            order_sector_index: index,
+            base_atoms: order_info.base_atoms,
+            quote_atoms: order_info.quote_atoms,
+        }])
+    }
+
+    fn try_cancel_order(
+        &mut self,
+        user: Address,
+        encoded_price: u32,
+        is_bid: bool,
+        hint: SectorIndex,
+    ) -> RefResult {
+        let seat = self.seat_with_hint(&user, hint)?;
+        let orders = if is_bid { &mut seat.bids } else { &mut seat.asks };
+        let index = orders
+            .remove(&encoded_price)
+            .ok_or(RefError::OrderNotFound)?;
+
+        let side = if is_bid { &mut self.bids } else { &mut self.asks };
+        let position = side
+            .iter()
+            .position(|order| order.index == index)
+            .expect("A seat's orders should always be on the book");
+        let order = side.remove(position);
+        self.free.push(index);
+
+        // Refund the collateral still locked in the order.
+        let seat = self.seats.get_mut(&user).expect("Seat was just found");
+        match is_bid {
+            true => credit(&mut seat.quote_available, order.quote_remaining)?,
+            false => credit(&mut seat.base_available, order.base_remaining)?,
+        }
+
+        Ok(vec![RefEvent::CancelOrder {
+            user,
+            is_bid,
+            encoded_price,
+            user_seat_sector_index: hint,
+            order_sector_index: index,
+            base_remaining: order.base_remaining,
+            quote_remaining: order.quote_remaining,
+        }])
+    }
+
+    fn try_market_order(
+        &mut self,
+        user: Address,
+        order_size: u64,
+        is_buy: bool,
+        is_base: bool,
+    ) -> RefResult {
+        // The amount of the constraint asset, i.e., the asset `order_size` is denominated in, that
+        // hasn't been filled yet.
+        let mut remaining = order_size;
+        let mut counter_asset_filled: u64 = 0;
+        // One maker fill event per order filled, followed by the taker's market order event.
+        let mut events = vec![];
+
+        loop {
+            let side = if is_buy { &mut self.asks } else { &mut self.bids };
+            let Some(top) = side.first().cloned() else {
+                break;
+            };
+            if remaining == 0 {
+                break;
+            }
+
+            let (constrained, counter) = match is_base {
+                true => (top.base_remaining, top.quote_remaining),
+                false => (top.quote_remaining, top.base_remaining),
+            };
+
+            let is_full_fill = constrained <= remaining;
+            let (base_filled, quote_filled) = if is_full_fill {
+                side.remove(0);
+                self.free.push(top.index);
+                remaining -= constrained;
+                credit(&mut counter_asset_filled, counter)?;
+                (top.base_remaining, top.quote_remaining)
+            } else {
+                // Round the counter asset down, in the maker's favor.
+                let partial = (remaining as u128 * counter as u128 / constrained as u128) as u64;
+                credit(&mut counter_asset_filled, partial)?;
+                let (base_filled, quote_filled) = match is_base {
+                    true => (remaining, partial),
+                    false => (partial, remaining),
+                };
+                side[0].base_remaining -= base_filled;
+                side[0].quote_remaining -= quote_filled;
+                remaining = 0;
+                (base_filled, quote_filled)
+            };
+
+            // A filled ask pays the maker quote, and a filled bid pays the maker base.
+            let maker = self
+                .seats
+                .values_mut()
+                .find(|seat| seat.index == top.user_seat)
+                .expect("An order's seat should always exist");
+            if is_buy {
+                credit(&mut maker.quote_available, quote_filled)?;
+                if is_full_fill {
+                    maker.asks.remove(&top.encoded_price);
+                }
+            } else {
+                credit(&mut maker.base_available, base_filled)?;
+                if is_full_fill {
+                    maker.bids.remove(&top.encoded_price);
+                }
+            }
+
+            events.push(RefEvent::MakerFill {
+                order_sector_index: top.index,
+                base_filled,
+                quote_filled,
+            });
+
+            if !is_full_fill {
+                break;
+            }
+        }
+
+        let constraint_asset_filled = order_size - remaining;
+        let (base_filled, quote_filled) = match is_base {
+            true => (constraint_asset_filled, counter_asset_filled),
+            false => (counter_asset_filled, constraint_asset_filled),
+        };
+
+        // A buy pays quote and receives base, and a sell pays base and receives quote. Both legs
+        // of the transfer must be non-zero.
+        let (paid, received) = match is_buy {
+            true => (quote_filled, base_filled),
+            false => (base_filled, quote_filled),
+        };
+        let wallet = self.wallets.entry(user).or_default();
+        debit(
+            wallet.get_mut(!is_buy),
+            paid,
+            RefError::InsufficientTokenBalance,
+        )?;
+        credit(self.vault.get_mut(!is_buy), paid)?;
+        if paid == 0 || received == 0 {
+            return Err(RefError::AmountCannotBeZero);
+        }
+        debit(
+            self.vault.get_mut(is_buy),
+            received,
+            RefError::InsufficientTokenBalance,
+        )?;
+        credit(wallet.get_mut(is_buy), received)?;
+
+        events.push(RefEvent::MarketOrder {
+            order_size,
+            is_buy,
+            is_base,
+            base_filled,
+            quote_filled,
+        });
+        Ok(events)
+    }
+}
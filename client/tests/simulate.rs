@@ -0,0 +1,240 @@
+use client::{
+    e2e_helpers::mollusk::{
+        market_account,
+        mint_account,
+        new_dropset_mollusk_context,
+        token_account,
+    },
+    pda::find_market_address,
+    simulate::Simulate,
+};
+use dropset_interface::{
+    instructions::{
+        generated_client::*,
+        DepositInstructionData,
+        DropsetInstruction,
+        MarketOrderInstructionData,
+        PostOrderInstructionData,
+    },
+    seeds::event_authority,
+    state::{
+        sector::NIL,
+        SYSTEM_PROGRAM_ID,
+    },
+};
+use futures::executor::block_on;
+use price::OrderInfoArgs;
+use solana_account::Account;
+use solana_address::Address;
+use solana_sdk::{
+    program_pack::Pack,
+    transaction::TransactionError,
+};
+use solana_system_interface::instruction::transfer;
+use spl_associated_token_account_interface::address::get_associated_token_address;
+use spl_token_interface::state::Account as TokenAccount;
+use transaction_parser::{
+    events::dropset_event::{
+        unpack_instruction_events,
+        DropsetEvent,
+    },
+    views::try_market_view_all_from_owner_and_data,
+};
+
+#[test]
+fn simulate_without_committing() {
+    const LAMPORTS: u64 = 10_000_000;
+    let alice = Address::new_unique();
+    let bob = Address::new_unique();
+    let mollusk = new_dropset_mollusk_context(vec![
+        (alice, Account::new(LAMPORTS, 0, &SYSTEM_PROGRAM_ID)),
+        (bob, Account::new(LAMPORTS, 0, &SYSTEM_PROGRAM_ID)),
+    ]);
+
+    let simulation = block_on(mollusk.simulate(
+        &alice,
+        &[transfer(&alice, &bob, LAMPORTS / 2)],
+        &[alice, bob],
+    ))
+    .unwrap();
+
+    assert!(simulation.is_ok());
+    assert!(simulation.events.is_empty());
+    assert!(simulation.units_consumed.is_some_and(|units| units > 0));
+    let lamports = |address: &Address| simulation.accounts[address].as_ref().unwrap().lamports;
+    assert_eq!(lamports(&alice), LAMPORTS / 2);
+    assert_eq!(lamports(&bob), LAMPORTS + LAMPORTS / 2);
+
+    // The simulation doesn't touch the context's accounts.
+    let store = mollusk.account_store.borrow();
+    assert_eq!(store[&alice].lamports, LAMPORTS);
+    assert_eq!(store[&bob].lamports, LAMPORTS);
+}
+
+#[test]
+fn simulate_reports_failing_instruction() {
+    const LAMPORTS: u64 = 10_000_000;
+    let alice = Address::new_unique();
+    let bob = Address::new_unique();
+    let mollusk = new_dropset_mollusk_context(vec![
+        (alice, Account::new(LAMPORTS, 0, &SYSTEM_PROGRAM_ID)),
+        (bob, Account::new(LAMPORTS, 0, &SYSTEM_PROGRAM_ID)),
+    ]);
+
+    // The second transfer overdraws alice's remaining balance.
+    let simulation = block_on(mollusk.simulate(
+        &alice,
+        &[
+            transfer(&alice, &bob, LAMPORTS / 2),
+            transfer(&alice, &bob, LAMPORTS),
+        ],
+        &[alice],
+    ))
+    .unwrap();
+
+    assert!(matches!(
+        simulation.err,
+        Some(TransactionError::InstructionError(1, _))
+    ));
+    // Only dropset errors are decoded.
+    assert!(simulation.dropset_error.is_none());
+}
+
+const NUM_SECTORS: u32 = 16;
+
+#[test]
+fn simulate_market_order_fills() {
+    const BASE: u64 = 1_000;
+    const QUOTE: u64 = 100_000_000_000;
+    let base_mint = Address::new_unique();
+    let quote_mint = Address::new_unique();
+    let (market, bump) = find_market_address(&base_mint, &quote_mint);
+    let maker = Address::new_unique();
+    let taker = Address::new_unique();
+    let ata = get_associated_token_address;
+
+    let mut mollusk = new_dropset_mollusk_context(vec![
+        (base_mint, mint_account(BASE)),
+        (quote_mint, mint_account(QUOTE)),
+        (
+            market,
+            market_account(bump, &base_mint, &quote_mint, NUM_SECTORS),
+        ),
+        (
+            ata(&market, &base_mint),
+            token_account(base_mint, market, 0),
+        ),
+        (
+            ata(&market, &quote_mint),
+            token_account(quote_mint, market, 0),
+        ),
+        (maker, Account::new(10_000_000_000, 0, &SYSTEM_PROGRAM_ID)),
+        (
+            ata(&maker, &base_mint),
+            token_account(base_mint, maker, BASE),
+        ),
+        (taker, Account::new(10_000_000_000, 0, &SYSTEM_PROGRAM_ID)),
+        (ata(&taker, &base_mint), token_account(base_mint, taker, 0)),
+        (
+            ata(&taker, &quote_mint),
+            token_account(quote_mint, taker, QUOTE),
+        ),
+        (
+            dropset::ID,
+            mollusk_svm::program::create_program_account_loader_v3(&dropset::ID),
+        ),
+        mollusk_svm_programs_token::token::keyed_account(),
+    ]);
+    mollusk_svm_programs_token::token::add_program(&mut mollusk.mollusk);
+
+    // The maker deposits base and posts an ask.
+    let deposit = Deposit {
+        event_authority: event_authority::ID,
+        user: maker,
+        market_account: market,
+        user_ata: ata(&maker, &base_mint),
+        market_ata: ata(&market, &base_mint),
+        mint: base_mint,
+        token_program: spl_token_interface::ID,
+        dropset_program: dropset::ID,
+    }
+    .create_instruction(DepositInstructionData::new(BASE, NIL));
+    assert!(mollusk.process_instruction(&deposit).program_result.is_ok());
+
+    let seat_index = {
+        let store = mollusk.account_store.borrow();
+        let view =
+            try_market_view_all_from_owner_and_data(store[&market].owner, &store[&market].data)
+                .expect("Should parse market account");
+        view.seats[0].index
+    };
+    let post_ask = PostOrder {
+        event_authority: event_authority::ID,
+        user: maker,
+        market_account: market,
+        dropset_program: dropset::ID,
+    }
+    .create_instruction(PostOrderInstructionData::new(
+        OrderInfoArgs::new_unscaled(10_000_000, 10),
+        false,
+        seat_index,
+    ));
+    assert!(mollusk
+        .process_instruction(&post_ask)
+        .program_result
+        .is_ok());
+
+    // The taker buys part of the ask.
+    let market_order = MarketOrder {
+        event_authority: event_authority::ID,
+        user: taker,
+        market_account: market,
+        base_user_ata: ata(&taker, &base_mint),
+        quote_user_ata: ata(&taker, &quote_mint),
+        base_market_ata: ata(&market, &base_mint),
+        quote_market_ata: ata(&market, &quote_mint),
+        base_mint,
+        quote_mint,
+        base_token_program: spl_token_interface::ID,
+        quote_token_program: spl_token_interface::ID,
+        dropset_program: dropset::ID,
+    }
+    .create_instruction(MarketOrderInstructionData::new(4, true, true));
+
+    let taker_base = ata(&taker, &base_mint);
+    let simulation =
+        block_on(mollusk.simulate(&taker, std::slice::from_ref(&market_order), &[taker_base]))
+            .unwrap();
+    assert!(simulation.is_ok());
+
+    // Processing the order for real emits the same fills and balances the simulation predicted.
+    let result = mollusk.process_instruction(&market_order);
+    assert!(result.program_result.is_ok());
+    let fills: Vec<_> = result
+        .inner_instructions
+        .iter()
+        .filter_map(|inner| match inner.instruction.data.split_first() {
+            Some((tag, data)) if *tag == DropsetInstruction::FlushEvents as u8 => Some(data),
+            _ => None,
+        })
+        .flat_map(|data| unpack_instruction_events(data).expect("Should unpack flushed events"))
+        .filter_map(|event| match event {
+            DropsetEvent::MarketOrder(e) => Some((e.base_filled, e.quote_filled)),
+            _ => None,
+        })
+        .collect();
+    let simulated_fills: Vec<_> = simulation
+        .fills()
+        .map(|e| (e.base_filled, e.quote_filled))
+        .collect();
+
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].0, 4);
+    assert_eq!(simulated_fills, fills);
+
+    let balance = TokenAccount::unpack(&mollusk.account_store.borrow()[&taker_base].data)
+        .expect("Should unpack token account")
+        .amount;
+    assert_eq!(balance, 4);
+    assert_eq!(simulation.token_balance(&taker_base), Some(balance));
+}
@@ -0,0 +1,350 @@
+//! See [`BookState`].
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+};
+
+use dropset_interface::state::sector::SectorIndex;
+use solana_address::Address;
+use tokio::sync::broadcast;
+use transaction_parser::views::{
+    MarketViewAll,
+    OrderView,
+};
+
+use crate::parse_update::MarketUpdate;
+
+/// The default number of unreceived [`BookUpdate`]s buffered per subscriber before the oldest are
+/// dropped.
+pub const DEFAULT_CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    Bid,
+    Ask,
+}
+
+/// A single resting order, i.e. an L3 book entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct L3Order {
+    pub side: Side,
+    /// The order's sector index. Sector indices are reused once an order is filled or canceled, so
+    /// an order is only the same order across snapshots if its user and price match too.
+    pub index: SectorIndex,
+    pub user: Address,
+    pub encoded_price: u32,
+    pub base_remaining: u64,
+    pub quote_remaining: u64,
+}
+
+impl L3Order {
+    /// Returns `None` if the order's seat isn't in `seat_users`, which only happens with an
+    /// inconsistent market snapshot.
+    fn from_view(
+        side: Side,
+        order: &OrderView,
+        seat_users: &HashMap<SectorIndex, Address>,
+    ) -> Option<Self> {
+        Some(Self {
+            side,
+            index: order.index,
+            user: *seat_users.get(&order.user_seat)?,
+            encoded_price: order.encoded_price,
+            base_remaining: order.base_remaining,
+            quote_remaining: order.quote_remaining,
+        })
+    }
+
+    fn is_same_order(&self, other: &Self) -> bool {
+        self.side == other.side
+            && self.index == other.index
+            && self.user == other.user
+            && self.encoded_price == other.encoded_price
+    }
+}
+
+/// The total resting size at a single price, i.e. an L2 book entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct L2Level {
+    pub encoded_price: u32,
+    pub base_atoms: u128,
+    pub quote_atoms: u128,
+    pub num_orders: u32,
+}
+
+/// The orders on each side of a market's book, best price first and in time priority within a
+/// price.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BookSnapshot {
+    /// The slot of the account write this snapshot was built from.
+    pub slot: u64,
+    pub bids: Vec<L3Order>,
+    pub asks: Vec<L3Order>,
+}
+
+impl BookSnapshot {
+    /// Orders whose seat can't be found are skipped.
+    pub fn from_view(slot: u64, view: &MarketViewAll) -> Self {
+        let seat_users = view
+            .seats
+            .iter()
+            .map(|seat| (seat.index, seat.user))
+            .collect::<HashMap<_, _>>();
+        let orders = |side: Side, orders: &[OrderView]| -> Vec<L3Order> {
+            orders
+                .iter()
+                .filter_map(|order| L3Order::from_view(side, order, &seat_users))
+                .collect()
+        };
+
+        Self {
+            slot,
+            bids: orders(Side::Bid, &view.bids),
+            asks: orders(Side::Ask, &view.asks),
+        }
+    }
+
+    /// Aggregates the bids into price levels, best price first.
+    pub fn l2_bids(&self) -> Vec<L2Level> {
+        aggregate_levels(&self.bids)
+    }
+
+    /// Aggregates the asks into price levels, best price first.
+    pub fn l2_asks(&self) -> Vec<L2Level> {
+        aggregate_levels(&self.asks)
+    }
+
+    /// The orders added, removed, and changed going from `previous` to `self`.
+    pub fn diff(&self, previous: &BookSnapshot) -> Vec<BookDelta> {
+        let mut deltas = diff_side(&previous.bids, &self.bids);
+        deltas.extend(diff_side(&previous.asks, &self.asks));
+        deltas
+    }
+}
+
+/// Book orders are already sorted by price, so each level is a run of orders with the same price.
+fn aggregate_levels(orders: &[L3Order]) -> Vec<L2Level> {
+    let mut levels: Vec<L2Level> = vec![];
+    for order in orders {
+        match levels.last_mut() {
+            Some(level) if level.encoded_price == order.encoded_price => {
+                level.base_atoms += order.base_remaining as u128;
+                level.quote_atoms += order.quote_remaining as u128;
+                level.num_orders += 1;
+            }
+            _ => levels.push(L2Level {
+                encoded_price: order.encoded_price,
+                base_atoms: order.base_remaining as u128,
+                quote_atoms: order.quote_remaining as u128,
+                num_orders: 1,
+            }),
+        }
+    }
+
+    levels
+}
+
+fn diff_side(previous: &[L3Order], current: &[L3Order]) -> Vec<BookDelta> {
+    let previous_by_index = previous
+        .iter()
+        .map(|order| (order.index, order))
+        .collect::<HashMap<_, _>>();
+    let current_by_index = current
+        .iter()
+        .map(|order| (order.index, order))
+        .collect::<HashMap<_, _>>();
+
+    let removed = previous
+        .iter()
+        .filter(|order| {
+            !current_by_index
+                .get(&order.index)
+                .is_some_and(|current| current.is_same_order(order))
+        })
+        .map(|order| BookDelta::Removed(order.clone()));
+
+    let added_or_changed =
+        current
+            .iter()
+            .filter_map(|order| match previous_by_index.get(&order.index) {
+                Some(previous) if previous.is_same_order(order) => {
+                    (*previous != order).then(|| BookDelta::Changed {
+                        previous: (*previous).clone(),
+                        current: order.clone(),
+                    })
+                }
+                _ => Some(BookDelta::Added(order.clone())),
+            });
+
+    removed.chain(added_or_changed).collect()
+}
+
+/// A single change to a market's L3 book between two snapshots.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BookDelta {
+    Added(L3Order),
+    /// The order was filled or canceled.
+    Removed(L3Order),
+    /// The order was partially filled.
+    Changed {
+        previous: L3Order,
+        current: L3Order,
+    },
+}
+
+/// A market's new book snapshot along with the deltas from its previous snapshot.
+#[derive(Clone, Debug)]
+pub struct BookUpdate {
+    pub market: Address,
+    pub snapshot: Arc<BookSnapshot>,
+    pub deltas: Vec<BookDelta>,
+}
+
+/// Maintains the latest L3 book for each market from streamed market account updates and
+/// publishes each change to subscribers.
+pub struct BookState {
+    books: HashMap<Address, Arc<BookSnapshot>>,
+    sender: broadcast::Sender<BookUpdate>,
+}
+
+impl Default for BookState {
+    fn default() -> Self {
+        Self::new(DEFAULT_CHANNEL_CAPACITY)
+    }
+}
+
+impl BookState {
+    pub fn new(channel_capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(channel_capacity);
+        Self {
+            books: HashMap::new(),
+            sender,
+        }
+    }
+
+    /// Subscribes to all subsequent [`BookUpdate`]s. Subscribers that fall too far behind miss the
+    /// oldest updates and should re-read the current books with [`BookState::book`].
+    pub fn subscribe(&self) -> broadcast::Receiver<BookUpdate> {
+        self.sender.subscribe()
+    }
+
+    /// The markets with at least one applied update.
+    pub fn markets(&self) -> impl Iterator<Item = &Address> {
+        self.books.keys()
+    }
+
+    /// The latest snapshot of `market`'s book, if any updates for it have been applied.
+    pub fn book(&self, market: &Address) -> Option<Arc<BookSnapshot>> {
+        self.books.get(market).cloned()
+    }
+
+    /// Applies a market account update and publishes the resulting [`BookUpdate`], unless the
+    /// update is older than the market's current snapshot or didn't change the book.
+    ///
+    /// The first update for a market is published with every order as an added delta.
+    pub fn apply(&mut self, update: &MarketUpdate) -> Option<BookUpdate> {
+        let previous = self.books.get(&update.address).cloned();
+        if previous
+            .as_ref()
+            .is_some_and(|previous| update.slot < previous.slot)
+        {
+            return None;
+        }
+
+        let snapshot = BookSnapshot::from_view(update.slot, &update.view);
+        let deltas = snapshot.diff(previous.as_deref().unwrap_or(&BookSnapshot::default()));
+        let snapshot = Arc::new(snapshot);
+        self.books.insert(update.address, snapshot.clone());
+        if deltas.is_empty() && previous.is_some() {
+            return None;
+        }
+
+        let update = BookUpdate {
+            market: update.address,
+            snapshot,
+            deltas,
+        };
+        // Sending only fails when there are no subscribers, which is fine.
+        let _ = self.sender.send(update.clone());
+
+        Some(update)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(side: Side, index: SectorIndex, encoded_price: u32, base: u64) -> L3Order {
+        L3Order {
+            side,
+            index,
+            user: Address::new_from_array([index as u8; 32]),
+            encoded_price,
+            base_remaining: base,
+            quote_remaining: base * 2,
+        }
+    }
+
+    #[test]
+    fn l2_levels() {
+        let snapshot = BookSnapshot {
+            slot: 0,
+            bids: vec![
+                order(Side::Bid, 1, 100, 5),
+                order(Side::Bid, 2, 100, 7),
+                order(Side::Bid, 3, 90, 1),
+            ],
+            asks: vec![],
+        };
+
+        assert_eq!(
+            snapshot.l2_bids(),
+            [
+                L2Level {
+                    encoded_price: 100,
+                    base_atoms: 12,
+                    quote_atoms: 24,
+                    num_orders: 2,
+                },
+                L2Level {
+                    encoded_price: 90,
+                    base_atoms: 1,
+                    quote_atoms: 2,
+                    num_orders: 1,
+                },
+            ]
+        );
+        assert!(snapshot.l2_asks().is_empty());
+    }
+
+    #[test]
+    fn diff_snapshots() {
+        let previous = BookSnapshot {
+            slot: 1,
+            bids: vec![order(Side::Bid, 1, 100, 5), order(Side::Bid, 2, 90, 5)],
+            asks: vec![order(Side::Ask, 3, 110, 5)],
+        };
+        // Bid 1 is partially filled, bid 2 is canceled and its sector is reused by a new ask at a
+        // different price, and ask 3 is unchanged.
+        let current = BookSnapshot {
+            slot: 2,
+            bids: vec![order(Side::Bid, 1, 100, 3)],
+            asks: vec![order(Side::Ask, 2, 105, 4), order(Side::Ask, 3, 110, 5)],
+        };
+
+        assert_eq!(
+            current.diff(&previous),
+            [
+                BookDelta::Removed(order(Side::Bid, 2, 90, 5)),
+                BookDelta::Changed {
+                    previous: order(Side::Bid, 1, 100, 5),
+                    current: order(Side::Bid, 1, 100, 3),
+                },
+                BookDelta::Added(order(Side::Ask, 2, 105, 4)),
+            ]
+        );
+        assert!(current.diff(&current).is_empty());
+    }
+}
@@ -0,0 +1,675 @@
+//! See [`DropsetStream`].
+
+use std::{
+    collections::{
+        BTreeMap,
+        HashMap,
+        HashSet,
+        VecDeque,
+    },
+    future::Future,
+    time::Duration,
+};
+
+use dropset_interface::{
+    seeds::event_authority,
+    state::market_header::MARKET_ACCOUNT_DISCRIMINANT,
+};
+use futures::{
+    channel::mpsc,
+    stream::BoxStream,
+    SinkExt,
+    StreamExt,
+};
+use solana_sdk::clock::UnixTimestamp;
+use tokio::sync::mpsc as tokio_mpsc;
+use yellowstone_grpc_client::GeyserGrpcClient;
+use yellowstone_grpc_proto::{
+    geyser::{
+        subscribe_request_filter_accounts_filter::Filter,
+        subscribe_request_filter_accounts_filter_memcmp::Data,
+        subscribe_update::UpdateOneof,
+        SlotStatus,
+    },
+    prelude::*,
+};
+
+use crate::parse_update::{
+    parse_update,
+    ParsedUpdate,
+};
+
+/// Builds the subscription request for all `dropset` market account writes and transactions that
+/// emit events, along with slot status updates for promoting updates to confirmed and block metas
+/// for their block times.
+///
+/// If `from_slot` is provided, the server replays updates starting at that slot.
+pub fn dropset_subscribe_request(from_slot: Option<u64>) -> SubscribeRequest {
+    SubscribeRequest {
+        accounts: HashMap::from([(
+            "owned market account PDA data".to_string(),
+            SubscribeRequestFilterAccounts {
+                account: vec![],
+                owner: vec![dropset_interface::program::ID.to_string()],
+                filters: vec![SubscribeRequestFilterAccountsFilter {
+                    filter: Some(Filter::Memcmp(SubscribeRequestFilterAccountsFilterMemcmp {
+                        offset: 0,
+                        data: Some(Data::Bytes(
+                            MARKET_ACCOUNT_DISCRIMINANT.to_le_bytes().to_vec(),
+                        )),
+                    })),
+                }],
+                nonempty_txn_signature: Some(true),
+            },
+        )]),
+        slots: HashMap::from([(
+            "slot statuses".to_string(),
+            SubscribeRequestFilterSlots::default(),
+        )]),
+        blocks_meta: HashMap::from([(
+            "block times".to_string(),
+            SubscribeRequestFilterBlocksMeta::default(),
+        )]),
+        transactions: HashMap::from([(
+            "event authority pda instruction data".to_string(),
+            SubscribeRequestFilterTransactions {
+                failed: None,
+                signature: None,
+                vote: None,
+                account_exclude: vec![],
+                account_include: vec![],
+                account_required: vec![event_authority::ID.to_string()],
+            },
+        )]),
+        commitment: Some(CommitmentLevel::Processed.into()),
+        from_slot,
+        ..Default::default()
+    }
+}
+
+/// The request half and update half of a single live subscription.
+pub struct Subscription {
+    pub requests: mpsc::UnboundedSender<SubscribeRequest>,
+    pub updates: BoxStream<'static, anyhow::Result<SubscribeUpdate>>,
+}
+
+/// Opens subscriptions to a Geyser server. Abstracted so the stream can be driven by a mock server
+/// in tests.
+pub trait GeyserConnect {
+    fn subscribe(
+        &mut self,
+        request: SubscribeRequest,
+    ) -> impl Future<Output = anyhow::Result<Subscription>> + Send;
+}
+
+/// Connects to a Geyser gRPC endpoint, e.g. `http://localhost:10000`.
+pub struct GrpcConnector {
+    pub endpoint: String,
+    pub x_token: Option<String>,
+}
+
+impl GeyserConnect for GrpcConnector {
+    async fn subscribe(&mut self, request: SubscribeRequest) -> anyhow::Result<Subscription> {
+        let mut client = GeyserGrpcClient::build_from_shared(self.endpoint.clone())?
+            .x_token(self.x_token.clone())?
+            .connect()
+            .await?;
+
+        let (requests, mut requests_rx) = mpsc::unbounded();
+        let (updates_tx, updates) = mpsc::unbounded();
+
+        // The client's subscription borrows the client, so the client and subscription live in a
+        // task that forwards requests and updates until either side hangs up.
+        tokio::spawn(async move {
+            let (mut sink, mut stream) = match client.subscribe_with_request(Some(request)).await {
+                Ok(subscription) => subscription,
+                Err(error) => {
+                    let _ = updates_tx.unbounded_send(Err(error.into()));
+                    return;
+                }
+            };
+
+            loop {
+                tokio::select! {
+                    request = requests_rx.next() => match request {
+                        Some(request) if sink.send(request).await.is_ok() => {}
+                        _ => break,
+                    },
+                    update = stream.next() => match update {
+                        Some(update) => {
+                            if updates_tx.unbounded_send(update.map_err(Into::into)).is_err() {
+                                break;
+                            }
+                        }
+                        None => break,
+                    },
+                }
+            }
+        });
+
+        Ok(Subscription {
+            requests,
+            updates: updates.boxed(),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DropsetStreamConfig {
+    /// How often to ping the server to keep the connection alive.
+    pub ping_interval: Duration,
+    /// The delay before the first reconnect attempt. Doubles after each consecutive failure.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// The number of recent updates remembered for deduplicating updates replayed after a
+    /// reconnect.
+    pub dedupe_history: usize,
+}
+
+impl Default for DropsetStreamConfig {
+    fn default() -> Self {
+        Self {
+            ping_interval: Duration::from_secs(10),
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            dedupe_history: 10_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum StreamMessage {
+    /// A parsed update, first delivered as soon as it's processed and then again once its slot is
+    /// confirmed.
+    Update {
+        slot: u64,
+        /// The slot's block time, once its block meta has been received. Block metas arrive when
+        /// the block completes, so this is usually only set once the update is confirmed.
+        block_time: Option<UnixTimestamp>,
+        commitment: CommitmentLevel,
+        update: ParsedUpdate,
+    },
+    /// The processed updates from a slot that died or was abandoned by a fork. They'll never be
+    /// confirmed and any state derived from them should be rolled back.
+    Dropped {
+        slot: u64,
+        updates: Vec<ParsedUpdate>,
+    },
+    /// The subscription failed or ended, and the stream will try to reconnect after `retry_in`.
+    Disconnected { reason: String, retry_in: Duration },
+    /// The stream reconnected and resubscribed from `from_slot`. Replayed updates that were
+    /// already delivered are skipped.
+    Reconnected { from_slot: Option<u64> },
+}
+
+/// A resilient subscription to `dropset` market account writes and event-emitting transactions.
+///
+/// The stream reconnects with exponential backoff whenever the connection fails or ends,
+/// resubscribes from the last slot it processed, keeps the connection alive with pings, and skips
+/// updates the server replays after resubscribing. Updates are delivered at the processed
+/// commitment level and then promoted to confirmed or dropped as their slot's status changes.
+pub struct DropsetStream<C> {
+    connector: C,
+    config: DropsetStreamConfig,
+    state: StreamState,
+}
+
+impl<C: GeyserConnect> DropsetStream<C> {
+    pub fn new(connector: C, config: DropsetStreamConfig) -> Self {
+        let state = StreamState::new(config.dedupe_history);
+        Self {
+            connector,
+            config,
+            state,
+        }
+    }
+
+    /// The highest slot an update has been processed for, i.e. where the next resubscription
+    /// starts.
+    pub fn last_slot(&self) -> Option<u64> {
+        self.state.last_slot
+    }
+
+    /// Runs the stream, sending messages to `sender` until the receiving half is dropped.
+    pub async fn run(mut self, sender: tokio_mpsc::Sender<StreamMessage>) {
+        let mut backoff = self.config.initial_backoff;
+        let mut connected_before = false;
+
+        loop {
+            let from_slot = self.state.last_slot;
+            let request = dropset_subscribe_request(from_slot);
+            let reason = match self.connector.subscribe(request).await {
+                Ok(subscription) => {
+                    if connected_before
+                        && sender
+                            .send(StreamMessage::Reconnected { from_slot })
+                            .await
+                            .is_err()
+                    {
+                        return;
+                    }
+                    connected_before = true;
+
+                    match self.drive(subscription, &sender, &mut backoff).await {
+                        Ok(()) => "Stream ended".to_string(),
+                        Err(SessionEnd::ReceiverDropped) => return,
+                        Err(SessionEnd::Error(error)) => format!("Stream error: {error:#}"),
+                    }
+                }
+                Err(error) => format!("Failed to subscribe: {error:#}"),
+            };
+            let disconnected = StreamMessage::Disconnected {
+                reason,
+                retry_in: backoff,
+            };
+            if sender.send(disconnected).await.is_err() {
+                return;
+            }
+
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(self.config.max_backoff);
+        }
+    }
+
+    /// Handles a single subscription until it ends, resetting `backoff` once updates arrive.
+    async fn drive(
+        &mut self,
+        mut subscription: Subscription,
+        sender: &tokio_mpsc::Sender<StreamMessage>,
+        backoff: &mut Duration,
+    ) -> Result<(), SessionEnd> {
+        let mut ping = tokio::time::interval(self.config.ping_interval);
+        let mut ping_id = 0;
+
+        loop {
+            tokio::select! {
+                _ = ping.tick() => {
+                    ping_id += 1;
+                    subscription
+                        .requests
+                        .unbounded_send(ping_request(ping_id))
+                        .map_err(|e| SessionEnd::Error(e.into()))?;
+                }
+                update = subscription.updates.next() => {
+                    let Some(update) = update else {
+                        return Ok(());
+                    };
+                    let update = update.map_err(SessionEnd::Error)?;
+                    *backoff = self.config.initial_backoff;
+
+                    if matches!(update.update_oneof, Some(UpdateOneof::Ping(_))) {
+                        // Reply to the server's pings so it doesn't consider the client idle.
+                        ping_id += 1;
+                        subscription
+                            .requests
+                            .unbounded_send(ping_request(ping_id))
+                            .map_err(|e| SessionEnd::Error(e.into()))?;
+                    }
+
+                    for message in self.state.handle(update) {
+                        sender
+                            .send(message)
+                            .await
+                            .map_err(|_| SessionEnd::ReceiverDropped)?;
+                    }
+                }
+            }
+        }
+    }
+}
+
+enum SessionEnd {
+    ReceiverDropped,
+    Error(anyhow::Error),
+}
+
+fn ping_request(id: i32) -> SubscribeRequest {
+    SubscribeRequest {
+        ping: Some(SubscribeRequestPing { id }),
+        ..Default::default()
+    }
+}
+
+/// Identifies an update across subscriptions so replays can be skipped.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum UpdateKey {
+    Account { pubkey: Vec<u8>, write_version: u64 },
+    Transaction { signature: Vec<u8> },
+}
+
+impl UpdateKey {
+    fn from_update(update: &UpdateOneof) -> Option<Self> {
+        match update {
+            UpdateOneof::Account(account) => account.account.as_ref().map(|info| Self::Account {
+                pubkey: info.pubkey.clone(),
+                write_version: info.write_version,
+            }),
+            UpdateOneof::Transaction(transaction) => {
+                transaction
+                    .transaction
+                    .as_ref()
+                    .map(|info| Self::Transaction {
+                        signature: info.signature.clone(),
+                    })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Slot tracking, deduplication, and commitment promotion, independent of the connection.
+struct StreamState {
+    last_slot: Option<u64>,
+    seen: HashSet<UpdateKey>,
+    seen_order: VecDeque<UpdateKey>,
+    dedupe_history: usize,
+    /// Processed updates that haven't been confirmed or dropped yet, by slot.
+    pending: BTreeMap<u64, Vec<ParsedUpdate>>,
+    /// Block times from block metas for slots that haven't been finalized yet.
+    block_times: BTreeMap<u64, UnixTimestamp>,
+}
+
+impl StreamState {
+    fn new(dedupe_history: usize) -> Self {
+        Self {
+            last_slot: None,
+            seen: HashSet::new(),
+            seen_order: VecDeque::new(),
+            dedupe_history: dedupe_history.max(1),
+            pending: BTreeMap::new(),
+            block_times: BTreeMap::new(),
+        }
+    }
+
+    fn handle(&mut self, update: SubscribeUpdate) -> Vec<StreamMessage> {
+        let Some(update) = update.update_oneof else {
+            return vec![];
+        };
+
+        let slot = match &update {
+            UpdateOneof::Account(account) => account.slot,
+            UpdateOneof::Transaction(transaction) => transaction.slot,
+            UpdateOneof::Slot(slot) => return self.handle_slot_status(slot),
+            UpdateOneof::BlockMeta(meta) => {
+                if let Some(block_time) = meta.block_time {
+                    self.block_times.insert(meta.slot, block_time.timestamp);
+                }
+                return vec![];
+            }
+            _ => return vec![],
+        };
+
+        if let Some(key) = UpdateKey::from_update(&update) {
+            if !self.remember(key) {
+                return vec![];
+            }
+        }
+        self.last_slot = Some(self.last_slot.map_or(slot, |last| last.max(slot)));
+
+        let Some(parsed) = parse_update(update) else {
+            return vec![];
+        };
+        self.pending.entry(slot).or_default().push(parsed.clone());
+
+        vec![StreamMessage::Update {
+            slot,
+            block_time: self.block_times.get(&slot).copied(),
+            commitment: CommitmentLevel::Processed,
+            update: parsed,
+        }]
+    }
+
+    /// Returns whether or not the update is new.
+    fn remember(&mut self, key: UpdateKey) -> bool {
+        if !self.seen.insert(key.clone()) {
+            return false;
+        }
+        self.seen_order.push_back(key);
+        while self.seen_order.len() > self.dedupe_history {
+            if let Some(oldest) = self.seen_order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+
+        true
+    }
+
+    fn handle_slot_status(&mut self, slot: &SubscribeUpdateSlot) -> Vec<StreamMessage> {
+        let block_time = self.block_times.get(&slot.slot).copied();
+        let confirmed = |slot, updates: Vec<ParsedUpdate>| {
+            updates
+                .into_iter()
+                .map(move |update| StreamMessage::Update {
+                    slot,
+                    block_time,
+                    commitment: CommitmentLevel::Confirmed,
+                    update,
+                })
+        };
+
+        match SlotStatus::try_from(slot.status) {
+            Ok(SlotStatus::SlotConfirmed) => self
+                .pending
+                .remove(&slot.slot)
+                .map(|updates| confirmed(slot.slot, updates).collect())
+                .unwrap_or_default(),
+            Ok(SlotStatus::SlotFinalized) => {
+                // A finalized slot may not have been seen as confirmed, e.g. right after
+                // resubscribing. Any older pending slot is on an abandoned fork.
+                let mut messages = self
+                    .pending
+                    .remove(&slot.slot)
+                    .map(|updates| confirmed(slot.slot, updates).collect::<Vec<_>>())
+                    .unwrap_or_default();
+                let newer = self.pending.split_off(&slot.slot);
+                let abandoned = std::mem::replace(&mut self.pending, newer);
+                self.block_times = self.block_times.split_off(&(slot.slot + 1));
+                messages.extend(
+                    abandoned
+                        .into_iter()
+                        .map(|(slot, updates)| StreamMessage::Dropped { slot, updates }),
+                );
+                messages
+            }
+            Ok(SlotStatus::SlotDead) => self
+                .pending
+                .remove(&slot.slot)
+                .map(|updates| {
+                    vec![StreamMessage::Dropped {
+                        slot: slot.slot,
+                        updates,
+                    }]
+                })
+                .unwrap_or_default(),
+            _ => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        Mutex,
+    };
+
+    use futures::stream;
+
+    use super::*;
+
+    fn transaction_update(slot: u64, signature: u8) -> SubscribeUpdate {
+        SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+                transaction: Some(SubscribeUpdateTransactionInfo {
+                    signature: vec![signature; 64],
+                    transaction: Some(Transaction {
+                        message: Some(Message {
+                            account_keys: vec![vec![signature; 32]],
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                slot,
+            })),
+            ..Default::default()
+        }
+    }
+
+    fn block_meta_update(slot: u64, timestamp: UnixTimestamp) -> SubscribeUpdate {
+        SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::BlockMeta(SubscribeUpdateBlockMeta {
+                slot,
+                block_time: Some(yellowstone_grpc_proto::prelude::UnixTimestamp { timestamp }),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+    }
+
+    fn slot_update(slot: u64, status: SlotStatus) -> SubscribeUpdate {
+        SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::Slot(SubscribeUpdateSlot {
+                slot,
+                status: status as i32,
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+    }
+
+    fn summarize(message: &StreamMessage) -> (u64, &'static str) {
+        match message {
+            StreamMessage::Update {
+                slot, commitment, ..
+            } => match commitment {
+                CommitmentLevel::Processed => (*slot, "processed"),
+                _ => (*slot, "confirmed"),
+            },
+            StreamMessage::Dropped { slot, .. } => (*slot, "dropped"),
+            StreamMessage::Disconnected { .. } => (0, "disconnected"),
+            StreamMessage::Reconnected { from_slot } => (from_slot.unwrap_or(0), "reconnected"),
+        }
+    }
+
+    #[test]
+    fn promote_dedupe_and_drop() {
+        let mut state = StreamState::new(16);
+        let mut handle = |update| {
+            state
+                .handle(update)
+                .iter()
+                .map(summarize)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(handle(transaction_update(5, 1)), [(5, "processed")]);
+        assert_eq!(handle(transaction_update(6, 2)), [(6, "processed")]);
+        assert_eq!(handle(transaction_update(7, 3)), [(7, "processed")]);
+        // A replay of an update that was already delivered.
+        assert!(handle(transaction_update(5, 1)).is_empty());
+
+        assert_eq!(
+            handle(slot_update(5, SlotStatus::SlotConfirmed)),
+            [(5, "confirmed")]
+        );
+        assert_eq!(
+            handle(slot_update(7, SlotStatus::SlotDead)),
+            [(7, "dropped")]
+        );
+        // Slot 6 was never confirmed before slot 8 was finalized, so it was on another fork.
+        assert_eq!(
+            handle(slot_update(8, SlotStatus::SlotFinalized)),
+            [(6, "dropped")]
+        );
+    }
+
+    #[test]
+    fn confirmed_updates_carry_block_times() {
+        let mut state = StreamState::new(16);
+        state.handle(transaction_update(5, 1));
+        assert!(state.handle(block_meta_update(5, 1_700_000_000)).is_empty());
+
+        let confirmed = state.handle(slot_update(5, SlotStatus::SlotConfirmed));
+        let [StreamMessage::Update { block_time, .. }] = &confirmed[..] else {
+            panic!("Expected one confirmed update: {confirmed:?}");
+        };
+        assert_eq!(*block_time, Some(1_700_000_000));
+
+        // Finalized slots no longer need their block times.
+        state.handle(slot_update(5, SlotStatus::SlotFinalized));
+        assert!(state.block_times.is_empty());
+    }
+
+    /// A mock Geyser server that serves one scripted session per subscription and records the
+    /// slot each subscription started from.
+    struct MockConnector {
+        sessions: VecDeque<Vec<anyhow::Result<SubscribeUpdate>>>,
+        from_slots: Arc<Mutex<Vec<Option<u64>>>>,
+        /// Kept open so the client's pings don't fail.
+        request_receivers: Vec<mpsc::UnboundedReceiver<SubscribeRequest>>,
+    }
+
+    impl GeyserConnect for MockConnector {
+        async fn subscribe(&mut self, request: SubscribeRequest) -> anyhow::Result<Subscription> {
+            self.from_slots.lock().unwrap().push(request.from_slot);
+            let session = self
+                .sessions
+                .pop_front()
+                .ok_or_else(|| anyhow::anyhow!("Server unavailable"))?;
+            let (requests, request_receiver) = mpsc::unbounded();
+            self.request_receivers.push(request_receiver);
+
+            Ok(Subscription {
+                requests,
+                updates: stream::iter(session).boxed(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn reconnects_and_resubscribes() {
+        let from_slots = Arc::new(Mutex::new(vec![]));
+        let connector = MockConnector {
+            sessions: VecDeque::from([
+                vec![
+                    Ok(transaction_update(10, 1)),
+                    Ok(transaction_update(11, 2)),
+                    Err(anyhow::anyhow!("Connection reset")),
+                ],
+                // The server replays from slot 11.
+                vec![Ok(transaction_update(11, 2)), Ok(transaction_update(12, 3))],
+            ]),
+            from_slots: from_slots.clone(),
+            request_receivers: vec![],
+        };
+        let config = DropsetStreamConfig {
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+
+        let (sender, mut receiver) = tokio_mpsc::channel(16);
+        let task = tokio::spawn(DropsetStream::new(connector, config).run(sender));
+
+        let mut messages = vec![];
+        while messages.len() < 5 {
+            messages.push(summarize(&receiver.recv().await.unwrap()));
+        }
+        drop(receiver);
+        task.abort();
+
+        assert_eq!(
+            messages,
+            [
+                (10, "processed"),
+                (11, "processed"),
+                (0, "disconnected"),
+                (11, "reconnected"),
+                (12, "processed"),
+            ]
+        );
+        assert_eq!(from_slots.lock().unwrap()[..2], [None, Some(11)]);
+    }
+}
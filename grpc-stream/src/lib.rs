@@ -1,3 +1,7 @@
 //! Utilities for parsing transaction data that comes from the `yellowstone` `geyser` plugin.
 
+pub mod book_state;
+pub mod dropset_stream;
 pub mod parse_update;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_sink;
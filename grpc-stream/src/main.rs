@@ -1,115 +1,115 @@
 //! See [`main`].
 
-use std::collections::HashMap;
-
-use dropset_interface::{
-    seeds::event_authority,
-    state::market_header::MARKET_ACCOUNT_DISCRIMINANT,
-};
-use futures::StreamExt;
-use grpc_stream::parse_update::{
-    parse_update,
-    InstructionEventsWithIndices,
-    ParsedUpdate,
-};
-use tokio::time::Duration;
-use yellowstone_grpc_client::GeyserGrpcClient;
-use yellowstone_grpc_proto::{
-    geyser::{
-        subscribe_request_filter_accounts_filter::Filter,
-        subscribe_request_filter_accounts_filter_memcmp::Data,
+use grpc_stream::{
+    book_state::BookState,
+    dropset_stream::{
+        DropsetStream,
+        DropsetStreamConfig,
+        GrpcConnector,
+        StreamMessage,
+    },
+    parse_update::{
+        InstructionEventsWithIndices,
+        ParsedUpdate,
     },
-    prelude::*,
 };
+use transaction_parser::json::write_ndjson;
+use yellowstone_grpc_proto::prelude::CommitmentLevel;
 
 /// An example for streaming and parsing `dropset` events from an active, local GRPC stream on
 /// a `geyser`-enabled client.
+///
+/// Pass `--ndjson` to print market views and events as newline-delimited JSON instead. With the
+/// `sqlite` feature enabled, pass `--sqlite <path>` to also persist confirmed updates to a
+/// database.
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let endpoint = "http://localhost:10000";
+    let ndjson = std::env::args().any(|arg| arg == "--ndjson");
+    #[cfg(feature = "sqlite")]
+    let mut sink = std::env::args()
+        .skip_while(|arg| arg != "--sqlite")
+        .nth(1)
+        .map(grpc_stream::sqlite_sink::SqliteSink::open)
+        .transpose()?;
 
-    let mut client = GeyserGrpcClient::build_from_static(endpoint)
-        .connect()
-        .await?;
+    let connector = GrpcConnector {
+        endpoint: "http://localhost:10000".to_string(),
+        x_token: None,
+    };
+    let (sender, mut receiver) = tokio::sync::mpsc::channel(1024);
+    tokio::spawn(DropsetStream::new(connector, DropsetStreamConfig::default()).run(sender));
 
-    let mut stream = client
-        .subscribe_once(SubscribeRequest {
-            accounts: HashMap::from([(
-                "owned market account PDA data".to_string(),
-                SubscribeRequestFilterAccounts {
-                    account: vec![],
-                    owner: vec![dropset_interface::program::ID.to_string()],
-                    filters: vec![SubscribeRequestFilterAccountsFilter {
-                        filter: Some(Filter::Memcmp(SubscribeRequestFilterAccountsFilterMemcmp {
-                            offset: 0,
-                            data: Some(Data::Bytes(
-                                MARKET_ACCOUNT_DISCRIMINANT.to_le_bytes().to_vec(),
-                            )),
-                        })),
-                    }],
-                    nonempty_txn_signature: Some(true),
-                },
-            )]),
-            slots: HashMap::new(),
-            transactions: HashMap::from([(
-                "event authority pda instruction data".to_string(),
-                SubscribeRequestFilterTransactions {
-                    failed: None,
-                    signature: None,
-                    vote: None,
-                    account_exclude: vec![],
-                    account_include: vec![],
-                    account_required: vec![event_authority::ID.to_string()],
-                },
-            )]),
-            transactions_status: HashMap::new(),
-            blocks: HashMap::new(),
-            entry: HashMap::new(),
-            blocks_meta: HashMap::new(),
-            commitment: Some(CommitmentLevel::Processed.into()),
-            accounts_data_slice: vec![],
-            ping: None,
-            from_slot: None,
-        })
-        .await?;
+    let mut book_state = BookState::default();
 
-    while let Some(message) = stream.next().await {
-        match message {
-            Ok(msg) => {
-                if let Some(update) = msg.update_oneof {
-                    let update = parse_update(update);
+    while let Some(message) = receiver.recv().await {
+        let update = match message {
+            StreamMessage::Update {
+                commitment: CommitmentLevel::Processed,
+                update,
+                ..
+            } => update,
+            #[cfg(feature = "sqlite")]
+            StreamMessage::Update {
+                slot,
+                block_time,
+                commitment: CommitmentLevel::Confirmed,
+                update,
+            } => {
+                if let Some(sink) = sink.as_mut() {
+                    sink.record_update(slot, block_time, &update)?;
+                }
+                continue;
+            }
+            StreamMessage::Update { .. } => continue,
+            StreamMessage::Dropped { slot, updates } => {
+                eprintln!("Dropped {} updates from slot {slot}", updates.len());
+                continue;
+            }
+            StreamMessage::Disconnected { reason, retry_in } => {
+                eprintln!("{reason}, reconnecting in {retry_in:?}");
+                continue;
+            }
+            StreamMessage::Reconnected { from_slot } => {
+                eprintln!("Reconnected from slot {from_slot:?}");
+                continue;
+            }
+        };
 
-                    match update {
-                        Some(ParsedUpdate::Market(market)) => {
-                            println!("{:?}", market);
-                        }
-                        Some(ParsedUpdate::EmittedEvents { logs, events }) => {
-                            if !logs.is_empty() {
-                                for log in logs.iter().filter(|s| s.contains("[DEBUG]: ")) {
-                                    println!("------ LOGS -------");
-                                    println!("{:?}", log);
-                                }
-                            }
-                            for inner_ixn_with_events in events {
-                                let InstructionEventsWithIndices {
-                                    parent_index,
-                                    inner_index: _,
-                                    events,
-                                } = inner_ixn_with_events;
-                                if !events.is_empty() {
-                                    println!("----- EVENTS ------");
-                                    println!("Parent index: {}", parent_index);
-                                    println!("{:?}", events);
-                                }
-                            }
-                        }
-                        None => {}
+        match update {
+            ParsedUpdate::Market(market) => {
+                let book_update = book_state.apply(&market);
+                if ndjson {
+                    write_ndjson(std::io::stdout().lock(), [market.view])?;
+                } else {
+                    println!("{:?}", market.view);
+                    if let Some(book_update) = book_update {
+                        println!("Book deltas: {:?}", book_update.deltas);
                     }
                 }
             }
-            Err(error) => {
-                eprintln!("❌ Stream error: {}", error);
-                tokio::time::sleep(Duration::from_secs(1)).await;
+            ParsedUpdate::EmittedEvents { events, .. } if ndjson => {
+                let events = events.into_iter().flat_map(|ixn| ixn.events);
+                write_ndjson(std::io::stdout().lock(), events)?;
+            }
+            ParsedUpdate::EmittedEvents { logs, events, .. } => {
+                if !logs.is_empty() {
+                    for log in logs.iter().filter(|s| s.contains("[DEBUG]: ")) {
+                        println!("------ LOGS -------");
+                        println!("{:?}", log);
+                    }
+                }
+                for inner_ixn_with_events in events {
+                    let InstructionEventsWithIndices {
+                        parent_index,
+                        inner_index: _,
+                        events,
+                    } = inner_ixn_with_events;
+                    if !events.is_empty() {
+                        println!("----- EVENTS ------");
+                        println!("Parent index: {}", parent_index);
+                        println!("{:?}", events);
+                    }
+                }
             }
         }
     }
@@ -1,8 +1,10 @@
 //! See [`parse_update`].
 
+use dropset_interface::instructions::DropsetInstruction;
 use solana_address::Address;
+use solana_sdk::signature::Signature;
 use transaction_parser::{
-    events::dropset_event::DropsetEvent,
+    events::dropset_event::SequencedEvent,
     views::{
         try_market_view_all_from_owner_and_data,
         MarketViewAll,
@@ -15,6 +17,7 @@ use yellowstone_grpc_proto::{
         SubscribeUpdateTransactionInfo,
     },
     prelude::{
+        CompiledInstruction,
         InnerInstruction,
         InnerInstructions,
     },
@@ -50,18 +53,34 @@ impl ParsedInnerInstruction {
     }
 }
 
+/// A market account write along with the account's address and the slot it was written in.
+#[derive(Clone, Debug)]
+pub struct MarketUpdate {
+    pub address: Address,
+    pub slot: u64,
+    pub view: MarketViewAll,
+}
+
+#[derive(Clone, Debug)]
 pub enum ParsedUpdate {
-    Market(MarketViewAll),
+    Market(MarketUpdate),
     EmittedEvents {
+        signature: Signature,
+        /// The transaction's fee payer, i.e. its first signer.
+        signer: Address,
         logs: Vec<String>,
         events: Vec<InstructionEventsWithIndices>,
     },
 }
 
+#[derive(Clone, Debug)]
 pub struct InstructionEventsWithIndices {
     pub parent_index: u32,
     pub inner_index: usize,
-    pub events: Vec<DropsetEvent>,
+    /// The `user` account of the `dropset` instruction that emitted the events, which isn't
+    /// necessarily the transaction's fee payer.
+    pub user: Option<Address>,
+    pub events: Vec<SequencedEvent>,
 }
 
 /// Parses the `dropset` market account updates and events emitted in inner instruction data.
@@ -69,6 +88,10 @@ pub fn parse_update(update: UpdateOneof) -> Option<ParsedUpdate> {
     match update {
         UpdateOneof::Account(acc) => {
             if let Some(account_info) = acc.account {
+                let address: Address = account_info
+                    .pubkey
+                    .try_into()
+                    .expect("Should be a valid address");
                 let owner: Address = account_info
                     .owner
                     .try_into()
@@ -81,15 +104,33 @@ pub fn parse_update(update: UpdateOneof) -> Option<ParsedUpdate> {
                     "The account filter should ensure only valid market accounts are passed here",
                 );
 
-                return Some(ParsedUpdate::Market(market_view));
+                return Some(ParsedUpdate::Market(MarketUpdate {
+                    address,
+                    slot: acc.slot,
+                    view: market_view,
+                }));
             }
         }
         UpdateOneof::Transaction(update) => {
             if let Some(txn) = update.transaction {
+                let signature = Signature::try_from(txn.signature.as_slice())
+                    .expect("Should be a valid signature");
                 let account_keys = get_flattened_accounts_in_txn_update(&txn);
+                let signer = txn
+                    .transaction
+                    .as_ref()
+                    .and_then(|txn| txn.message.as_ref())
+                    .and_then(|msg| msg.account_keys.first())
+                    .and_then(|key| Address::try_from(key.as_slice()).ok())
+                    .expect("Should have a fee payer");
+                let outer_instructions = txn
+                    .transaction
+                    .as_ref()
+                    .and_then(|txn| txn.message.as_ref())
+                    .map_or(&[][..], |msg| &msg.instructions[..]);
                 let (logs, parsed_inner_instructions) = if let Some(meta) = txn.meta {
                     meta.compute_units_consumed
-                        .inspect(|cu| println!("CU consumed: {}", cu));
+                        .inspect(|cu| eprintln!("CU consumed: {}", cu));
                     let logs = meta.log_messages;
                     let parsed_inner_instructions: Vec<ParsedInnerInstruction> = meta
                         .inner_instructions
@@ -112,13 +153,24 @@ pub fn parse_update(update: UpdateOneof) -> Option<ParsedUpdate> {
                     .map(|(i, inner)| InstructionEventsWithIndices {
                         parent_index: inner.parent_index,
                         inner_index: i,
+                        user: emitting_user(
+                            &account_keys,
+                            outer_instructions,
+                            &parsed_inner_instructions[..i],
+                            inner,
+                        ),
                         events: inner
-                            .parse_events()
+                            .parse_sequenced_events()
                             .expect("Should be able to parse events"),
                     })
                     .collect::<Vec<_>>();
 
-                return Some(ParsedUpdate::EmittedEvents { logs, events });
+                return Some(ParsedUpdate::EmittedEvents {
+                    signature,
+                    signer,
+                    logs,
+                    events,
+                });
             }
         }
         _ => (),
@@ -127,8 +179,49 @@ pub fn parse_update(update: UpdateOneof) -> Option<ParsedUpdate> {
     None
 }
 
+/// The `user` account of the `dropset` instruction that emitted events through the `FlushEvents`
+/// self-CPI `flush`: the closest preceding `dropset` instruction invoked within the same outer
+/// instruction, e.g. when `dropset` is called through CPI, or else the outer instruction itself.
+fn emitting_user(
+    account_keys: &[Address],
+    outer_instructions: &[CompiledInstruction],
+    preceding: &[ParsedInnerInstruction],
+    flush: &ParsedInnerInstruction,
+) -> Option<Address> {
+    let is_dropset = |program_id: &Address| program_id == &dropset_interface::program::ID;
+    let invoked = preceding
+        .iter()
+        .rev()
+        .take_while(|inner| inner.parent_index == flush.parent_index)
+        .find(|inner| {
+            is_dropset(&inner.program_id)
+                && inner.inner_instruction.data.first()
+                    != Some(&(DropsetInstruction::FlushEvents as u8))
+        });
+
+    let accounts = match invoked {
+        Some(inner) => &inner.inner_instruction.accounts,
+        None => {
+            let outer = outer_instructions.get(flush.parent_index as usize)?;
+            if !is_dropset(account_keys.get(outer.program_id_index as usize)?) {
+                return None;
+            }
+            &outer.accounts
+        }
+    };
+
+    // Every `dropset` instruction that emits events takes the user as its second account.
+    account_keys.get(*accounts.get(1)? as usize).copied()
+}
+
+/// The transaction's account keys in the order instructions index them: the message's static keys
+/// followed by the writable and then readonly keys loaded from lookup tables.
 fn get_flattened_accounts_in_txn_update(txn: &SubscribeUpdateTransactionInfo) -> Vec<Address> {
     [
+        txn.transaction
+            .as_ref()
+            .and_then(|txn| txn.message.as_ref())
+            .map_or(vec![], |msg| msg.account_keys.clone()),
         txn.meta.as_ref().map_or(vec![], |meta| {
             [
                 meta.loaded_writable_addresses.clone(),
@@ -136,10 +229,6 @@ fn get_flattened_accounts_in_txn_update(txn: &SubscribeUpdateTransactionInfo) ->
             ]
             .concat()
         }),
-        txn.transaction
-            .as_ref()
-            .and_then(|txn| txn.message.as_ref())
-            .map_or(vec![], |msg| msg.account_keys.clone()),
     ]
     .concat()
     .into_iter()
@@ -0,0 +1,530 @@
+//! See [`SqliteSink`].
+
+use std::path::Path;
+
+use rusqlite::{
+    params,
+    Connection,
+    Transaction,
+};
+use solana_address::Address;
+use solana_sdk::{
+    clock::UnixTimestamp,
+    signature::Signature,
+};
+use transaction_parser::{
+    events::dropset_event::DropsetEvent,
+    market_data::trade::Trade,
+};
+
+use crate::parse_update::{
+    InstructionEventsWithIndices,
+    MarketUpdate,
+    ParsedUpdate,
+};
+
+/// The schema migrations, in order. The database's `user_version` is the number of migrations
+/// applied so far, so existing migrations must never be edited; append a new one instead.
+const MIGRATIONS: &[&str] = &[
+    // Version 1. Block times are only known once a slot's block meta arrives, so they're nullable.
+    "CREATE TABLE events (
+        signature TEXT NOT NULL,
+        event_index INTEGER NOT NULL,
+        slot INTEGER NOT NULL,
+        block_time INTEGER,
+        market TEXT NOT NULL,
+        seq INTEGER NOT NULL,
+        signer TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (signature, event_index)
+    );
+    CREATE INDEX events_by_market ON events (market, seq);
+
+    CREATE TABLE trades (
+        signature TEXT NOT NULL,
+        event_index INTEGER NOT NULL,
+        user TEXT NOT NULL,
+        market TEXT NOT NULL,
+        seq INTEGER NOT NULL,
+        slot INTEGER NOT NULL,
+        block_time INTEGER,
+        is_buy INTEGER NOT NULL,
+        base_atoms INTEGER NOT NULL,
+        quote_atoms INTEGER NOT NULL,
+        price TEXT NOT NULL,
+        PRIMARY KEY (signature, event_index)
+    );
+    CREATE INDEX trades_by_user ON trades (user, block_time);
+    CREATE INDEX trades_by_market ON trades (market, seq);
+
+    CREATE TABLE transfers (
+        signature TEXT NOT NULL,
+        event_index INTEGER NOT NULL,
+        user TEXT NOT NULL,
+        market TEXT NOT NULL,
+        seq INTEGER NOT NULL,
+        slot INTEGER NOT NULL,
+        block_time INTEGER,
+        kind TEXT NOT NULL,
+        is_base INTEGER NOT NULL,
+        amount INTEGER NOT NULL,
+        PRIMARY KEY (signature, event_index)
+    );
+    CREATE INDEX transfers_by_user ON transfers (user, block_time);
+
+    CREATE TABLE market_snapshots (
+        market TEXT NOT NULL,
+        slot INTEGER NOT NULL,
+        num_events INTEGER NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (market, slot)
+    );",
+];
+
+/// The schema version of a fully migrated database.
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// A deposit, withdrawal, or fill made by a user.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserActivity {
+    pub signature: Signature,
+    /// The event's index among all the `dropset` events emitted in its transaction.
+    pub event_index: u32,
+    pub market: Address,
+    pub seq: u64,
+    pub slot: u64,
+    pub block_time: UnixTimestamp,
+    pub kind: UserActivityKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UserActivityKind {
+    Deposit {
+        is_base: bool,
+        amount: u64,
+    },
+    Withdraw {
+        is_base: bool,
+        amount: u64,
+    },
+    /// A market order the user placed that filled at least partially. Only the taker's side is
+    /// recorded: fills against the user's resting orders only identify the maker's order by its
+    /// sector index, so they aren't attributed to anyone.
+    Fill {
+        is_buy: bool,
+        base_atoms: u64,
+        quote_atoms: u64,
+    },
+}
+
+/// Persists parsed `dropset` events, derived trades, and market snapshots to a SQLite database.
+///
+/// Every write is idempotent: events and everything derived from them are keyed by transaction
+/// signature and event index, and snapshots by market and slot, so replaying the same updates
+/// (e.g. after a reconnect) leaves the database unchanged.
+///
+/// Deposits, withdrawals, and market orders don't record the user in the event itself, so they're
+/// attributed to the `user` account of the `dropset` instruction that emitted them, which can
+/// differ from the transaction's fee payer.
+pub struct SqliteSink {
+    conn: Connection,
+}
+
+impl SqliteSink {
+    /// Opens or creates the database at `path` and migrates it to [`SCHEMA_VERSION`].
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> anyhow::Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(mut conn: Connection) -> anyhow::Result<Self> {
+        let version: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        anyhow::ensure!(
+            version <= SCHEMA_VERSION,
+            "Database schema version {version} is newer than the supported version {SCHEMA_VERSION}"
+        );
+
+        let txn = conn.transaction()?;
+        for migration in &MIGRATIONS[version as usize..] {
+            txn.execute_batch(migration)?;
+        }
+        txn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        txn.commit()?;
+
+        Ok(Self { conn })
+    }
+
+    pub fn schema_version(&self) -> anyhow::Result<u32> {
+        Ok(self
+            .conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))?)
+    }
+
+    /// Records a parsed update. `slot` and `block_time` are only used for transaction updates,
+    /// since market updates carry their own slot and have no block time. Pass `None` if the block
+    /// time isn't known, rather than a time the update was received.
+    pub fn record_update(
+        &mut self,
+        slot: u64,
+        block_time: Option<UnixTimestamp>,
+        update: &ParsedUpdate,
+    ) -> anyhow::Result<()> {
+        match update {
+            ParsedUpdate::Market(market) => self.record_snapshot(market),
+            ParsedUpdate::EmittedEvents {
+                signature,
+                signer,
+                events,
+                ..
+            } => self.record_events(slot, block_time, signature, signer, events),
+        }
+    }
+
+    /// Records a market account snapshot.
+    pub fn record_snapshot(&mut self, update: &MarketUpdate) -> anyhow::Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO market_snapshots (market, slot, num_events, data)
+            VALUES (?1, ?2, ?3, ?4)",
+            params![
+                update.address.to_string(),
+                update.slot,
+                update.view.header.nonce,
+                serde_json::to_string(&update.view)?,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Records every event emitted in a transaction along with the trades and transfers derived
+    /// from them, all in a single database transaction.
+    pub fn record_events(
+        &mut self,
+        slot: u64,
+        block_time: Option<UnixTimestamp>,
+        signature: &Signature,
+        signer: &Address,
+        events: &[InstructionEventsWithIndices],
+    ) -> anyhow::Result<()> {
+        let txn = self.conn.transaction()?;
+        let events = events.iter().flat_map(|ixn| {
+            // The user is only unknown if the emitting instruction couldn't be found, in which
+            // case the fee payer is the best guess.
+            let user = ixn.user.as_ref().unwrap_or(signer);
+            ixn.events.iter().map(move |event| (user, event))
+        });
+        for (event_index, (user, event)) in events.enumerate() {
+            // The market and sequence number have their own columns.
+            let data = serde_json::to_value(&event.event)?;
+            let kind = data["type"]
+                .as_str()
+                .expect("Events should serialize with a type tag");
+            txn.execute(
+                "INSERT OR IGNORE INTO events
+                (signature, event_index, slot, block_time, market, seq, signer, kind, data)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    signature.to_string(),
+                    event_index,
+                    slot,
+                    block_time,
+                    event.market.to_string(),
+                    event.seq,
+                    signer.to_string(),
+                    kind,
+                    data.to_string(),
+                ],
+            )?;
+
+            let activity = Activity {
+                txn: &txn,
+                signature,
+                event_index,
+                user,
+                market: &event.market,
+                seq: event.seq,
+                slot,
+                block_time,
+            };
+            match &event.event {
+                DropsetEvent::Deposit(deposit) => {
+                    activity.insert_transfer("deposit", deposit.is_base, deposit.amount)?
+                }
+                DropsetEvent::Withdraw(withdraw) => {
+                    activity.insert_transfer("withdraw", withdraw.is_base, withdraw.amount)?
+                }
+                DropsetEvent::MarketOrder(order) if order.base_filled > 0 => {
+                    activity.insert_fill(order.is_buy, order.base_filled, order.quote_filled)?
+                }
+                _ => (),
+            }
+        }
+        txn.commit()?;
+
+        Ok(())
+    }
+
+    /// The user's deposits, withdrawals, and fills with a block time in `start..end`, in the order
+    /// they landed. Activity whose block time isn't known is left out.
+    pub fn user_activity(
+        &self,
+        user: &Address,
+        start: UnixTimestamp,
+        end: UnixTimestamp,
+    ) -> anyhow::Result<Vec<UserActivity>> {
+        let mut statement = self.conn.prepare(
+            "SELECT signature, event_index, market, seq, slot, block_time,
+                'fill', is_buy, base_atoms, quote_atoms
+            FROM trades WHERE user = ?1 AND block_time >= ?2 AND block_time < ?3
+            UNION ALL
+            SELECT signature, event_index, market, seq, slot, block_time,
+                kind, is_base, amount, 0
+            FROM transfers WHERE user = ?1 AND block_time >= ?2 AND block_time < ?3
+            ORDER BY slot, signature, event_index",
+        )?;
+
+        let rows = statement.query_map(params![user.to_string(), start, end], |row| {
+            let flag: bool = row.get(7)?;
+            let amount: u64 = row.get(8)?;
+            let kind = match row.get::<_, String>(6)?.as_str() {
+                "deposit" => UserActivityKind::Deposit {
+                    is_base: flag,
+                    amount,
+                },
+                "withdraw" => UserActivityKind::Withdraw {
+                    is_base: flag,
+                    amount,
+                },
+                _ => UserActivityKind::Fill {
+                    is_buy: flag,
+                    base_atoms: amount,
+                    quote_atoms: row.get(9)?,
+                },
+            };
+
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get(1)?,
+                row.get::<_, String>(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+                kind,
+            ))
+        })?;
+
+        rows.map(|row| {
+            let (signature, event_index, market, seq, slot, block_time, kind) = row?;
+            Ok(UserActivity {
+                signature: signature.parse()?,
+                event_index,
+                market: market
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid market address: {market}"))?,
+                seq,
+                slot,
+                block_time,
+                kind,
+            })
+        })
+        .collect()
+    }
+}
+
+/// The columns shared by every row derived from a single event.
+struct Activity<'a> {
+    txn: &'a Transaction<'a>,
+    signature: &'a Signature,
+    event_index: usize,
+    user: &'a Address,
+    market: &'a Address,
+    seq: u64,
+    slot: u64,
+    block_time: Option<UnixTimestamp>,
+}
+
+impl Activity<'_> {
+    fn insert_transfer(&self, kind: &str, is_base: bool, amount: u64) -> rusqlite::Result<()> {
+        self.txn.execute(
+            "INSERT OR IGNORE INTO transfers
+            (signature, event_index, user, market, seq, slot, block_time, kind, is_base, amount)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                self.signature.to_string(),
+                self.event_index,
+                self.user.to_string(),
+                self.market.to_string(),
+                self.seq,
+                self.slot,
+                self.block_time,
+                kind,
+                is_base,
+                amount,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    fn insert_fill(&self, is_buy: bool, base_atoms: u64, quote_atoms: u64) -> rusqlite::Result<()> {
+        self.txn.execute(
+            "INSERT OR IGNORE INTO trades
+            (signature, event_index, user, market, seq, slot, block_time, is_buy, base_atoms,
+                quote_atoms, price)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                self.signature.to_string(),
+                self.event_index,
+                self.user.to_string(),
+                self.market.to_string(),
+                self.seq,
+                self.slot,
+                self.block_time,
+                is_buy,
+                base_atoms,
+                quote_atoms,
+                Trade::atoms_price(base_atoms, quote_atoms).to_string(),
+            ],
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use transaction_parser::events::{
+        display_types::{
+            DisplayDepositData,
+            DisplayMarketOrderData,
+            DisplayWithdrawData,
+        },
+        dropset_event::SequencedEvent,
+    };
+
+    use super::*;
+
+    const MARKET: Address = Address::new_from_array([1; 32]);
+    const USER: Address = Address::new_from_array([2; 32]);
+    /// A fee payer that isn't the user, e.g. a relayer.
+    const PAYER: Address = Address::new_from_array([3; 32]);
+
+    fn events(first_seq: u64, events: Vec<DropsetEvent>) -> Vec<InstructionEventsWithIndices> {
+        vec![InstructionEventsWithIndices {
+            parent_index: 0,
+            inner_index: 0,
+            user: Some(USER),
+            events: events
+                .into_iter()
+                .zip(first_seq..)
+                .map(|(event, seq)| SequencedEvent {
+                    market: MARKET,
+                    seq,
+                    event,
+                })
+                .collect(),
+        }]
+    }
+
+    fn event_count(sink: &SqliteSink) -> u64 {
+        sink.conn
+            .query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn migrates_once() {
+        let sink = SqliteSink::open_in_memory().unwrap();
+        assert_eq!(sink.schema_version().unwrap(), SCHEMA_VERSION);
+
+        // Reopening an already migrated database doesn't re-run any migrations.
+        let sink = SqliteSink::from_connection(sink.conn).unwrap();
+        assert_eq!(sink.schema_version().unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn records_user_activity_idempotently() {
+        let mut sink = SqliteSink::open_in_memory().unwrap();
+        let deposit_signature = Signature::from([1; 64]);
+        let order_signature = Signature::from([2; 64]);
+        let deposit = events(
+            1,
+            vec![DropsetEvent::Deposit(DisplayDepositData {
+                amount: 500,
+                is_base: false,
+                seat_sector_index: 0,
+            })],
+        );
+        let market_order = events(
+            2,
+            vec![
+                DropsetEvent::MarketOrder(DisplayMarketOrderData {
+                    order_size: 10,
+                    is_buy: true,
+                    is_base: true,
+                    base_filled: 10,
+                    quote_filled: 200,
+                }),
+                DropsetEvent::Withdraw(DisplayWithdrawData {
+                    amount: 10,
+                    is_base: true,
+                }),
+            ],
+        );
+
+        // Record everything twice, as a replayed stream would.
+        for _ in 0..2 {
+            sink.record_events(5, Some(100), &deposit_signature, &PAYER, &deposit)
+                .unwrap();
+            sink.record_events(6, Some(200), &order_signature, &PAYER, &market_order)
+                .unwrap();
+        }
+        assert_eq!(event_count(&sink), 3);
+
+        let activity = sink.user_activity(&USER, 0, 1000).unwrap();
+        assert_eq!(
+            activity.iter().map(|a| &a.kind).collect::<Vec<_>>(),
+            [
+                &UserActivityKind::Deposit {
+                    is_base: false,
+                    amount: 500,
+                },
+                &UserActivityKind::Fill {
+                    is_buy: true,
+                    base_atoms: 10,
+                    quote_atoms: 200,
+                },
+                &UserActivityKind::Withdraw {
+                    is_base: true,
+                    amount: 10,
+                },
+            ]
+        );
+        assert_eq!(activity[1].signature, order_signature);
+        assert_eq!(activity[1].event_index, 0);
+        assert_eq!(activity[2].seq, 3);
+
+        // The range is half-open and only includes the given user.
+        assert_eq!(sink.user_activity(&USER, 100, 200).unwrap().len(), 1);
+        assert!(sink.user_activity(&PAYER, 0, 1000).unwrap().is_empty());
+
+        // Activity without a known block time is recorded but can't be found by time.
+        let deposit_signature = Signature::from([3; 64]);
+        let deposit = events(
+            4,
+            vec![DropsetEvent::Deposit(DisplayDepositData {
+                amount: 1,
+                is_base: true,
+                seat_sector_index: 0,
+            })],
+        );
+        sink.record_events(7, None, &deposit_signature, &PAYER, &deposit)
+            .unwrap();
+        assert_eq!(event_count(&sink), 4);
+        assert_eq!(sink.user_activity(&USER, 0, i64::MAX).unwrap().len(), 3);
+    }
+}
@@ -15,10 +15,12 @@ use crate::{
     render::Feature,
 };
 
-/// Render the account loader function.
+/// Render the account loader functions.
 ///
-/// The account loader function fallibly attempts to structure a slice of `AccountView`s into the
-/// corresponding struct of ordered accounts.
+/// The account loader functions fallibly attempt to structure a slice of `AccountView`s into the
+/// corresponding struct of ordered accounts. `load_accounts` requires the exact number of accounts,
+/// whereas `load_accounts_with_remaining` also accepts trailing accounts and returns them as a
+/// separate slice.
 pub fn render_account_loader(
     feature: Feature,
     instruction_variant: &InstructionVariant,
@@ -50,5 +52,21 @@ pub fn render_account_loader(
                 #(#accounts),*
             })
         }
+
+        #[inline(always)]
+        pub fn load_accounts_with_remaining(
+            accounts: #lifetimed_ref [#account_field_type],
+        ) -> Result<(Self, #lifetimed_ref [#account_field_type]), #base> {
+            let [ #(#accounts,)* remaining_accounts @ .. ] = accounts else {
+                return Err(#base::#variant);
+            };
+
+            Ok((
+                Self {
+                    #(#accounts),*
+                },
+                remaining_accounts,
+            ))
+        }
     }
 }
@@ -0,0 +1,406 @@
+//! Feeds arbitrary sequences of seat and order operations into an in-memory [`MarketRefMut`] and
+//! checks the full-book invariants after every operation.
+//!
+//! The operations mirror the program's instruction handlers closely enough to exercise every
+//! linked list and free stack code path, without any of the account or token plumbing.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use dropset_interface::state::{
+    asks_dll::AskOrders,
+    bids_dll::BidOrders,
+    linked_list::{
+        LinkedList,
+        LinkedListHeaderOperations,
+    },
+    market::MarketRefMut,
+    market_header::MarketHeader,
+    market_invariants::check_market_balances,
+    market_seat::MarketSeat,
+    order::{
+        Order,
+        OrdersCollection,
+    },
+    sector::{
+        Sector,
+        SectorIndex,
+        NIL,
+        PAYLOAD_SIZE,
+        SECTOR_SIZE,
+    },
+    transmutable::Transmutable,
+};
+use libfuzzer_sys::fuzz_target;
+use price::{
+    to_order_info,
+    OrderInfoArgs,
+};
+use solana_address::Address;
+
+const NUM_USERS: u8 = 4;
+const MAX_SECTORS: usize = 256;
+
+#[derive(Arbitrary, Debug)]
+enum MarketOp {
+    Deposit {
+        user: u8,
+        is_base: bool,
+        amount: u32,
+    },
+    Withdraw {
+        user: u8,
+        is_base: bool,
+        amount: u32,
+    },
+    PostOrder {
+        user: u8,
+        is_bid: bool,
+        price_mantissa: u32,
+        base_scalar: u16,
+    },
+    CancelOrder {
+        user: u8,
+        is_bid: bool,
+        slot: u8,
+    },
+    CloseSeat {
+        user: u8,
+    },
+    Grow {
+        num_sectors: u8,
+    },
+}
+
+/// The market's token account balances, i.e., everything deposited minus everything withdrawn.
+#[derive(Default)]
+struct Vault {
+    base: u64,
+    quote: u64,
+}
+
+fn user_address(user: u8) -> Address {
+    Address::new_from_array([user % NUM_USERS + 1; 32])
+}
+
+fn new_market() -> Vec<u8> {
+    let mut bytes = vec![0u8; MarketHeader::LEN];
+    // Safety: `bytes` is exactly `MarketHeader::LEN` bytes and isn't borrowed elsewhere.
+    unsafe {
+        MarketHeader::init(
+            bytes.as_mut_ptr() as *mut MarketHeader,
+            0,
+            &Address::new_from_array([0xba; 32]),
+            &Address::new_from_array([0x9e; 32]),
+        );
+    }
+    bytes
+}
+
+fn capacity(bytes: &[u8]) -> u32 {
+    ((bytes.len() - MarketHeader::LEN) / SECTOR_SIZE) as u32
+}
+
+/// Mimics the program's account resize: extend with zeroed bytes and free the new sectors.
+fn grow(bytes: &mut Vec<u8>, num_sectors: u32) {
+    let start = capacity(bytes);
+    if num_sectors == 0 || start as usize + num_sectors as usize > MAX_SECTORS {
+        return;
+    }
+    bytes.resize(bytes.len() + num_sectors as usize * SECTOR_SIZE, 0);
+    // Safety: The new sectors were just zero-initialized and are in-bounds.
+    unsafe {
+        MarketRefMut::from_bytes_mut(bytes)
+            .free_stack()
+            .convert_zeroed_bytes_to_free_sectors(start, start + num_sectors)
+            .expect("Should convert zeroed bytes");
+    }
+}
+
+/// Grows the market by one sector if there are no free sectors left.
+fn ensure_free_sector(bytes: &mut Vec<u8>) {
+    // Safety: `bytes` always holds an initialized market.
+    if unsafe { MarketRefMut::from_bytes_mut(bytes) }
+        .header
+        .num_free_sectors()
+        == 0
+    {
+        grow(bytes, 1);
+    }
+}
+
+fn find_seat(market: &MarketRefMut, user: &Address) -> Option<SectorIndex> {
+    market
+        .iter_seats()
+        .find(|(_, sector)| &sector.load_payload::<MarketSeat>().user == user)
+        .map(|(index, _)| index)
+}
+
+fn seat_mut<'a>(market: &'a mut MarketRefMut, index: SectorIndex) -> &'a mut MarketSeat {
+    // Safety: Seat indices are only ever taken from the seats list.
+    unsafe { Sector::from_sector_index_mut(market.sectors, index) }.load_payload_mut::<MarketSeat>()
+}
+
+/// Inserts a sorted payload given the index of the sector it should be inserted before.
+fn insert_before_next<T: LinkedListHeaderOperations>(
+    list: &mut LinkedList<'_, T>,
+    next_index: SectorIndex,
+    payload: &[u8; PAYLOAD_SIZE],
+) -> SectorIndex {
+    let result = if next_index == T::head(list.header) {
+        list.push_front(payload)
+    } else if next_index == NIL {
+        list.push_back(payload)
+    } else {
+        // Safety: `next_index` came from iterating the list, so it's in-bounds.
+        unsafe { list.insert_before(next_index, payload) }
+    };
+    result.expect("A free sector should be available")
+}
+
+fn insert_order<T: OrdersCollection + LinkedListHeaderOperations>(
+    list: &mut LinkedList<'_, T>,
+    order: Order,
+) -> SectorIndex {
+    let next_index = T::find_new_order_next_index(list, &order);
+    insert_before_next(list, next_index, order.as_bytes())
+}
+
+fn deposit(bytes: &mut Vec<u8>, vault: &mut Vault, user: Address, is_base: bool, amount: u64) {
+    if amount == 0 {
+        return;
+    }
+    ensure_free_sector(bytes);
+    // Safety: `bytes` always holds an initialized market.
+    let mut market = unsafe { MarketRefMut::from_bytes_mut(bytes) };
+
+    match find_seat(&market, &user) {
+        Some(index) => {
+            let seat = seat_mut(&mut market, index);
+            let result = match is_base {
+                true => seat.try_increment_base_available(amount),
+                false => seat.try_increment_quote_available(amount),
+            };
+            if result.is_err() {
+                return;
+            }
+        }
+        None => {
+            let (base, quote) = if is_base { (amount, 0) } else { (0, amount) };
+            let seat = MarketSeat::new(user, base, quote);
+            let mut seats = market.seats();
+            let next_index = seats
+                .iter()
+                .find(|(_, sector)| user < sector.load_payload::<MarketSeat>().user)
+                .map_or(NIL, |(index, _)| index);
+            insert_before_next(&mut seats, next_index, seat.as_bytes());
+        }
+    }
+
+    match is_base {
+        true => vault.base += amount,
+        false => vault.quote += amount,
+    }
+}
+
+fn withdraw(bytes: &mut [u8], vault: &mut Vault, user: Address, is_base: bool, amount: u64) {
+    // Safety: `bytes` always holds an initialized market.
+    let mut market = unsafe { MarketRefMut::from_bytes_mut(bytes) };
+    let Some(index) = find_seat(&market, &user) else {
+        return;
+    };
+    let seat = seat_mut(&mut market, index);
+    let result = match is_base {
+        true => seat.try_decrement_base_available(amount),
+        false => seat.try_decrement_quote_available(amount),
+    };
+    if result.is_ok() {
+        match is_base {
+            true => vault.base -= amount,
+            false => vault.quote -= amount,
+        }
+    }
+}
+
+fn post_order(bytes: &mut Vec<u8>, user: Address, is_bid: bool, mantissa: u32, scalar: u16) {
+    let args =
+        OrderInfoArgs::new_unscaled(10_000_000 + mantissa % 90_000_000, scalar.max(1).into());
+    let Ok(order_info) = to_order_info(args) else {
+        return;
+    };
+
+    ensure_free_sector(bytes);
+    // Safety: `bytes` always holds an initialized market.
+    let mut market = unsafe { MarketRefMut::from_bytes_mut(bytes) };
+    let Some(seat_index) = find_seat(&market, &user) else {
+        return;
+    };
+
+    let order = Order::new(order_info.clone(), seat_index);
+    let crosses = match is_bid {
+        true => BidOrders::post_only_crossing_check(&order, &market),
+        false => AskOrders::post_only_crossing_check(&order, &market),
+    };
+    if crosses.is_err() {
+        return;
+    }
+
+    // Check everything that could fail before mutating anything.
+    let seat = seat_mut(&mut market, seat_index);
+    let order_sectors = match is_bid {
+        true => &seat.user_order_sectors.bids,
+        false => &seat.user_order_sectors.asks,
+    };
+    let has_free_entry = order_sectors.iter().any(|entry| entry.is_free());
+    if !has_free_entry || order_sectors.get(order.le_encoded_price()).is_some() {
+        return;
+    }
+    let collateral = match is_bid {
+        true => seat.try_decrement_quote_available(order_info.quote_atoms),
+        false => seat.try_decrement_base_available(order_info.base_atoms),
+    };
+    if collateral.is_err() {
+        return;
+    }
+
+    let le_encoded_price = *order.le_encoded_price();
+    let order_index = match is_bid {
+        true => insert_order(&mut market.bids(), order),
+        false => insert_order(&mut market.asks(), order),
+    };
+
+    let seat = seat_mut(&mut market, seat_index);
+    let order_sectors = match is_bid {
+        true => &mut seat.user_order_sectors.bids,
+        false => &mut seat.user_order_sectors.asks,
+    };
+    order_sectors
+        .add(&le_encoded_price, &order_index.to_le_bytes())
+        .expect("Entry was checked as available");
+}
+
+fn cancel_order(bytes: &mut [u8], user: Address, is_bid: bool, slot: u8) {
+    // Safety: `bytes` always holds an initialized market.
+    let mut market = unsafe { MarketRefMut::from_bytes_mut(bytes) };
+    let Some(seat_index) = find_seat(&market, &user) else {
+        return;
+    };
+
+    let seat = seat_mut(&mut market, seat_index);
+    let order_sectors = match is_bid {
+        true => &mut seat.user_order_sectors.bids,
+        false => &mut seat.user_order_sectors.asks,
+    };
+    let used = order_sectors.iter().filter(|e| !e.is_free()).count();
+    if used == 0 {
+        return;
+    }
+    let entry = *order_sectors
+        .iter()
+        .filter(|e| !e.is_free())
+        .nth(slot as usize % used)
+        .expect("Should be in range");
+    let encoded_price = u32::from_le_bytes(entry.encoded_price.as_array());
+    let order_index = SectorIndex::from_le_bytes(
+        order_sectors
+            .remove(encoded_price)
+            .expect("Entry was just found"),
+    );
+
+    // Safety: Order indices in a seat always point to in-bounds orders.
+    let order = unsafe { Sector::from_sector_index(market.sectors, order_index) }
+        .load_payload::<Order>()
+        .clone();
+    let seat = seat_mut(&mut market, seat_index);
+    let refund = match is_bid {
+        true => seat.try_increment_quote_available(order.quote_remaining()),
+        false => seat.try_increment_base_available(order.base_remaining()),
+    };
+    refund.expect("Refunding collateral can't overflow what was deposited");
+
+    // Safety: `order_index` is in the corresponding orders list.
+    unsafe {
+        match is_bid {
+            true => market.bids().remove_at(order_index),
+            false => market.asks().remove_at(order_index),
+        }
+    }
+}
+
+fn close_seat(bytes: &mut [u8], vault: &mut Vault, user: Address) {
+    // Safety: `bytes` always holds an initialized market.
+    let mut market = unsafe { MarketRefMut::from_bytes_mut(bytes) };
+    let Some(seat_index) = find_seat(&market, &user) else {
+        return;
+    };
+    let seat = seat_mut(&mut market, seat_index);
+    let orders = &seat.user_order_sectors;
+    if orders
+        .bids
+        .iter()
+        .chain(orders.asks.iter())
+        .any(|e| !e.is_free())
+    {
+        return;
+    }
+    vault.base -= seat.base_available();
+    vault.quote -= seat.quote_available();
+
+    // Safety: `seat_index` is in the seats list.
+    unsafe { market.seats().remove_at(seat_index) };
+}
+
+fuzz_target!(|ops: Vec<MarketOp>| {
+    let mut bytes = new_market();
+    let mut vault = Vault::default();
+
+    for op in ops {
+        match op {
+            MarketOp::Deposit {
+                user,
+                is_base,
+                amount,
+            } => deposit(
+                &mut bytes,
+                &mut vault,
+                user_address(user),
+                is_base,
+                amount.into(),
+            ),
+            MarketOp::Withdraw {
+                user,
+                is_base,
+                amount,
+            } => withdraw(
+                &mut bytes,
+                &mut vault,
+                user_address(user),
+                is_base,
+                amount.into(),
+            ),
+            MarketOp::PostOrder {
+                user,
+                is_bid,
+                price_mantissa,
+                base_scalar,
+            } => post_order(
+                &mut bytes,
+                user_address(user),
+                is_bid,
+                price_mantissa,
+                base_scalar,
+            ),
+            MarketOp::CancelOrder { user, is_bid, slot } => {
+                cancel_order(&mut bytes, user_address(user), is_bid, slot)
+            }
+            MarketOp::CloseSeat { user } => close_seat(&mut bytes, &mut vault, user_address(user)),
+            MarketOp::Grow { num_sectors } => grow(&mut bytes, (num_sectors % 4).into()),
+        }
+
+        // Safety: `bytes` always holds an initialized market.
+        let market = unsafe { MarketRefMut::from_bytes_mut(&mut bytes) };
+        if let Err(e) = check_market_balances(&market, vault.base, vault.quote) {
+            panic!("Market invariant violated: {e}");
+        }
+    }
+});
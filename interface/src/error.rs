@@ -5,6 +5,7 @@ use price::OrderInfoError;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "client", derive(strum_macros::FromRepr))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum DropsetError {
     InvalidInstructionTag,
@@ -45,6 +46,9 @@ pub enum DropsetError {
     InfinityIsNotAFloat,
     PostOnlyWouldImmediatelyFill,
     AmountFilledVsTransferredMismatch,
+    TooManyTransferHookAccounts,
+    OutdatedMarketLayout,
+    UnsupportedMarketLayout,
 }
 
 impl From<DropsetError> for ProgramError {
@@ -111,6 +115,9 @@ impl From<DropsetError> for &'static str {
             DropsetError::AmountFilledVsTransferredMismatch => {
                 "The amount filled doesn't match the amount transferred."
             }
+            DropsetError::TooManyTransferHookAccounts => "Too many transfer hook accounts passed",
+            DropsetError::OutdatedMarketLayout => "Market account layout must be migrated first",
+            DropsetError::UnsupportedMarketLayout => "Market account layout version is unsupported",
         }
     }
 }
@@ -13,6 +13,7 @@ use instruction_macros::ProgramInstructionEvent;
 #[derive(Clone, Copy, Debug, PartialEq, ProgramInstructionEvent)]
 #[cfg_attr(test, derive(strum_macros::FromRepr, strum_macros::EnumIter))]
 #[cfg_attr(feature = "client", derive(strum_macros::Display))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[program_id(crate::program::ID)]
 #[rustfmt::skip]
 pub enum DropsetEventTag {
@@ -30,14 +31,21 @@ pub enum DropsetEventTag {
     WithdrawEvent,
     #[args(market: Address, "The newly registered market.")]
     RegisterMarketEvent,
+    #[args(user: Address, "The address of the user that posted the order.")]
     #[args(is_bid: bool, "Whether or not the order is a bid. If false, the order is an ask.")]
+    #[args(encoded_price: u32, "The order's encoded price.")]
     #[args(user_seat_sector_index: u32, "The user's market seat sector index.")]
     #[args(order_sector_index: u32, "The posted order's sector index.")]
     #[args(base_atoms: u64, "The size of the order's base atoms to fill.")]
     #[args(quote_atoms: u64, "The size of the order's quote atoms to fill.")]
     PostOrderEvent,
+    #[args(user: Address, "The address of the user that canceled the order.")]
     #[args(is_bid: bool, "Whether or not the order is a bid. If false, the order is an ask.")]
+    #[args(encoded_price: u32, "The canceled order's encoded price.")]
     #[args(user_seat_sector_index: u32, "The user's market seat sector index.")]
+    #[args(order_sector_index: u32, "The canceled order's former sector index.")]
+    #[args(base_remaining: u64, "The order's unfilled base atoms. Returned to the user's seat if the order was an ask.")]
+    #[args(quote_remaining: u64, "The order's unfilled quote atoms. Returned to the user's seat if the order was a bid.")]
     CancelOrderEvent,
     #[args(order_size: u64, "The order size in atoms.")]
     #[args(is_buy: bool, "Whether or not the order is a market buy. If not, it's a market sell.")]
@@ -47,4 +55,8 @@ pub enum DropsetEventTag {
     MarketOrderEvent,
     #[args(user_seat_sector_index: u32, "The user's market seat sector index.")]
     CloseSeatEvent,
+    #[args(order_sector_index: u32, "The filled maker order's sector index.")]
+    #[args(base_filled: u64, "The amount of the maker order's base atoms filled.")]
+    #[args(quote_filled: u64, "The amount of the maker order's quote atoms filled.")]
+    MakerFillEvent,
 }
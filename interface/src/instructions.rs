@@ -14,6 +14,7 @@ use price::OrderInfoArgs;
 #[derive(Clone, Copy, Debug, PartialEq, ProgramInstruction)]
 #[cfg_attr(test, derive(strum_macros::FromRepr, strum_macros::EnumIter))]
 #[cfg_attr(feature = "client", derive(strum_macros::Display))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[program_id(crate::program::ID)]
 #[rustfmt::skip]
 pub enum DropsetInstruction {
@@ -116,6 +117,12 @@ pub enum DropsetInstruction {
     // instruction data, but it is not used by the program.
     #[account(0, signer,   name = "event_authority", desc = "The event authority PDA signer.")]
     FlushEvents,
+
+    // MigrateMarket is permissionless, since it only ever upgrades a market to the current layout.
+    #[account(0, signer, writable, name = "payer",  desc = "The payer funding any additional rent required by the new layout.")]
+    #[account(1, writable, name = "market_account", desc = "The market account PDA.")]
+    #[account(2,           name = "system_program", desc = "The system program.")]
+    MigrateMarket,
 }
 
 #[cfg(test)]
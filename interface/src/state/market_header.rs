@@ -24,6 +24,14 @@ use crate::{
 
 pub const MARKET_ACCOUNT_DISCRIMINANT: u64 = 0xd00d00b00b00f00du64;
 
+/// The layout version of market accounts created before the header stored a version. The version
+/// byte was carved out of the header's trailing padding, so these accounts read as version `0`.
+pub const UNVERSIONED_MARKET_LAYOUT_VERSION: u8 = 0;
+
+/// The layout version of markets created by the current program. Any change to the header or
+/// sector layout must bump this and add the corresponding step to the program's migration path.
+pub const CURRENT_MARKET_LAYOUT_VERSION: u8 = 1;
+
 /// The lightweight header for each market account. This header contains metadata used to interpret
 /// a market's account data properly.
 ///
@@ -72,8 +80,10 @@ pub struct MarketHeader {
     pub market_bump: u8,
     /// The u64 number of events as LE bytes.
     num_events: LeU64,
+    /// The version of the account data layout, used to load and migrate older market accounts.
+    layout_version: u8,
     // Although not necessary, add extra padding to make this alignment 8.
-    _padding: [u8; 3],
+    _padding: [u8; 2],
 }
 
 // Safety:
@@ -100,7 +110,8 @@ unsafe impl Transmutable for MarketHeader {
     /* quote_mint */       + size_of::<Address>()
     /* market_bump */      + size_of::<u8>()
     /* num_events */       + size_of::<LeU64>()
-    /* _padding */         + size_of::<[u8; 3]>();
+    /* layout_version */   + size_of::<u8>()
+    /* _padding */         + size_of::<[u8; 2]>();
 
     fn validate_bit_patterns(_bytes: &[u8]) -> DropsetResult {
         // All bit patterns are valid: no enums, bools, or other types with invalid states.
@@ -227,7 +238,8 @@ impl MarketHeader {
             quote_mint: *quote_mint,
             market_bump,
             num_events: [0; U64_SIZE],
-            _padding: [0; 3],
+            layout_version: CURRENT_MARKET_LAYOUT_VERSION,
+            _padding: [0; 2],
         };
         core::ptr::write(header_dst_ptr, header);
     }
@@ -240,6 +252,28 @@ impl MarketHeader {
         Ok(())
     }
 
+    /// Verifies that the market account uses the layout expected by the current program.
+    ///
+    /// Older layouts must be upgraded with the `MigrateMarket` instruction before they can be used.
+    #[inline(always)]
+    pub fn verify_layout_version(&self) -> DropsetResult {
+        match self.layout_version {
+            CURRENT_MARKET_LAYOUT_VERSION => Ok(()),
+            v if v < CURRENT_MARKET_LAYOUT_VERSION => Err(DropsetError::OutdatedMarketLayout),
+            _ => Err(DropsetError::UnsupportedMarketLayout),
+        }
+    }
+
+    #[inline(always)]
+    pub fn layout_version(&self) -> u8 {
+        self.layout_version
+    }
+
+    #[inline(always)]
+    pub fn set_layout_version(&mut self, layout_version: u8) {
+        self.layout_version = layout_version;
+    }
+
     #[inline(always)]
     pub fn discriminant(&self) -> u64 {
         u64::from_le_bytes(self.discriminant)
@@ -255,3 +289,56 @@ impl MarketHeader {
         self.num_events = (self.num_events().saturating_add(amount)).to_le_bytes();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_header_bytes() -> [u8; MarketHeader::LEN] {
+        let mut bytes = [0u8; MarketHeader::LEN];
+        // Safety: `bytes` is exactly `MarketHeader::LEN` bytes and isn't borrowed elsewhere.
+        unsafe {
+            MarketHeader::init(
+                bytes.as_mut_ptr() as *mut MarketHeader,
+                255,
+                &Address::new_from_array([1; 32]),
+                &Address::new_from_array([2; 32]),
+            );
+        }
+        bytes
+    }
+
+    #[test]
+    fn init_uses_current_layout_version() {
+        let bytes = new_header_bytes();
+        let header = MarketHeader::load(&bytes).unwrap();
+        assert_eq!(header.layout_version(), CURRENT_MARKET_LAYOUT_VERSION);
+        assert_eq!(header.verify_layout_version(), Ok(()));
+    }
+
+    #[test]
+    fn layout_version_lives_in_former_padding() {
+        // Unversioned markets zeroed the trailing padding, so they must read as version 0 without
+        // any of the other header fields moving.
+        let bytes = new_header_bytes();
+        assert_eq!(MarketHeader::LEN, 128);
+        assert_eq!(bytes[MarketHeader::LEN - 3], CURRENT_MARKET_LAYOUT_VERSION);
+        assert_eq!(bytes[MarketHeader::LEN - 2..], [0, 0]);
+    }
+
+    #[test]
+    fn verify_layout_version() {
+        let mut bytes = new_header_bytes();
+        let header = MarketHeader::load_mut(&mut bytes).unwrap();
+        header.set_layout_version(UNVERSIONED_MARKET_LAYOUT_VERSION);
+        assert_eq!(
+            header.verify_layout_version(),
+            Err(DropsetError::OutdatedMarketLayout)
+        );
+        header.set_layout_version(CURRENT_MARKET_LAYOUT_VERSION + 1);
+        assert_eq!(
+            header.verify_layout_version(),
+            Err(DropsetError::UnsupportedMarketLayout)
+        );
+    }
+}
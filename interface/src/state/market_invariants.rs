@@ -0,0 +1,677 @@
+//! Full-book consistency checks for market account data.
+//!
+//! These walk every structure in a [`Market`] and verify the invariants the program relies on but
+//! never checks in a single place. They're intended for tests, bots, and off-chain tooling that
+//! want to assert a market is consistent after every mutation.
+
+use std::{
+    fmt,
+    vec,
+    vec::Vec,
+};
+
+use solana_address::Address;
+
+use crate::state::{
+    market::Market,
+    market_header::MarketHeader,
+    market_seat::MarketSeat,
+    order::Order,
+    sector::{
+        Sector,
+        SectorIndex,
+        NIL,
+    },
+    user_order_sectors::OrderSectors,
+};
+
+/// The structure a sector belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectorKind {
+    Free,
+    Seat,
+    Bid,
+    Ask,
+}
+
+/// A violated market invariant, with enough context to locate the offending sector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarketInvariantError {
+    /// The header's discriminant doesn't match a market account's.
+    Uninitialized,
+    /// A structure links to a sector index that's out of bounds of the sector bytes.
+    IndexOutOfBounds {
+        kind: SectorKind,
+        index: SectorIndex,
+    },
+    /// A sector is reachable from more than one structure, or twice from the same one.
+    SectorAlreadyVisited {
+        index: SectorIndex,
+        first: SectorKind,
+        second: SectorKind,
+    },
+    /// A sector isn't reachable from any structure.
+    UnreachableSector { index: SectorIndex },
+    /// A linked list sector's `prev` doesn't point back to the sector before it.
+    BrokenPrevLink {
+        kind: SectorKind,
+        index: SectorIndex,
+        expected_prev: SectorIndex,
+        actual_prev: SectorIndex,
+    },
+    /// A linked list's tail in the header doesn't match the last sector in the list.
+    TailMismatch {
+        kind: SectorKind,
+        expected: SectorIndex,
+        actual: SectorIndex,
+    },
+    /// A header counter doesn't match the number of sectors in its structure.
+    CountMismatch {
+        kind: SectorKind,
+        header_count: u32,
+        actual_count: u32,
+    },
+    /// Seats aren't sorted in strictly ascending order by user address.
+    SeatsNotSorted { index: SectorIndex },
+    /// Bids aren't sorted in descending order or asks aren't sorted in ascending order.
+    OrdersNotSorted {
+        kind: SectorKind,
+        index: SectorIndex,
+    },
+    /// An order's `user_seat` doesn't point to a seat sector.
+    OrderSeatNotFound {
+        kind: SectorKind,
+        order_index: SectorIndex,
+        user_seat: SectorIndex,
+    },
+    /// An order's seat doesn't map the order's price back to the order's sector index.
+    OrderNotReferencedBySeat {
+        kind: SectorKind,
+        order_index: SectorIndex,
+        user_seat: SectorIndex,
+    },
+    /// A seat maps a price to a sector that isn't one of the seat's orders at that price.
+    DanglingSeatOrderEntry {
+        kind: SectorKind,
+        seat_index: SectorIndex,
+        order_index: SectorIndex,
+    },
+    /// The sum of the seats' available balances and the orders' collateral overflowed a `u64`.
+    BalanceOverflow,
+    /// The seat balances plus order collateral don't equal the market token account's balance.
+    BalanceMismatch {
+        is_base: bool,
+        expected: u64,
+        actual: u64,
+    },
+}
+
+impl fmt::Display for MarketInvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for MarketInvariantError {}
+
+/// The total amounts of base and quote the market owes its users, i.e., the seats' available
+/// balances plus the collateral locked in resting orders.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MarketLiabilities {
+    pub base: u64,
+    pub quote: u64,
+}
+
+/// Walks the header, the seats, bids and asks linked lists, and the free stack and checks that:
+/// - every sector is in exactly one structure;
+/// - `num_seats`, `num_bids`, `num_asks` and `num_free_sectors` match each structure's length;
+/// - every linked list's `prev` links and tail are consistent with its `next` links;
+/// - seats are sorted by user address, bids are descending and asks are ascending;
+/// - every order's `user_seat` points to a seat whose [`OrderSectors`] references it back, and vice
+///   versa.
+///
+/// On success, returns the market's total liabilities, which can be compared against the market
+/// token account balances with [`check_market_balances`].
+pub fn check_market_invariants<H, S>(
+    market: &Market<H, S>,
+) -> Result<MarketLiabilities, MarketInvariantError>
+where
+    H: AsRef<MarketHeader>,
+    S: AsRef<[u8]>,
+{
+    let kinds = walk_sectors(market)?;
+    let sectors = market.sectors.as_ref();
+
+    check_seats_sorted(market)?;
+    check_orders_sorted(market)?;
+
+    let mut liabilities = MarketLiabilities::default();
+
+    for (seat_index, sector) in market.iter_seats() {
+        let seat = sector.load_payload::<MarketSeat>();
+        liabilities.base = checked_add(liabilities.base, seat.base_available())?;
+        liabilities.quote = checked_add(liabilities.quote, seat.quote_available())?;
+
+        let order_sectors = &seat.user_order_sectors;
+        check_seat_order_entries(
+            sectors,
+            &kinds,
+            seat_index,
+            &order_sectors.bids,
+            SectorKind::Bid,
+        )?;
+        check_seat_order_entries(
+            sectors,
+            &kinds,
+            seat_index,
+            &order_sectors.asks,
+            SectorKind::Ask,
+        )?;
+    }
+
+    for (kind, orders) in [
+        (SectorKind::Bid, market.iter_bids()),
+        (SectorKind::Ask, market.iter_asks()),
+    ] {
+        for (order_index, sector) in orders {
+            let order = sector.load_payload::<Order>();
+            check_order_back_reference(sectors, &kinds, kind, order_index, order)?;
+
+            // Bids lock quote as collateral and asks lock base.
+            match kind {
+                SectorKind::Bid => {
+                    liabilities.quote = checked_add(liabilities.quote, order.quote_remaining())?
+                }
+                _ => liabilities.base = checked_add(liabilities.base, order.base_remaining())?,
+            }
+        }
+    }
+
+    Ok(liabilities)
+}
+
+/// Runs [`check_market_invariants`] and then checks that the seat balances plus the order
+/// collateral equal the market's base and quote token account balances.
+pub fn check_market_balances<H, S>(
+    market: &Market<H, S>,
+    base_market_ata_balance: u64,
+    quote_market_ata_balance: u64,
+) -> Result<MarketLiabilities, MarketInvariantError>
+where
+    H: AsRef<MarketHeader>,
+    S: AsRef<[u8]>,
+{
+    let liabilities = check_market_invariants(market)?;
+
+    for (is_base, expected, actual) in [
+        (true, liabilities.base, base_market_ata_balance),
+        (false, liabilities.quote, quote_market_ata_balance),
+    ] {
+        if expected != actual {
+            return Err(MarketInvariantError::BalanceMismatch {
+                is_base,
+                expected,
+                actual,
+            });
+        }
+    }
+
+    Ok(liabilities)
+}
+
+/// Walks every structure in the market, returning the structure each sector belongs to.
+fn walk_sectors<H, S>(market: &Market<H, S>) -> Result<Vec<SectorKind>, MarketInvariantError>
+where
+    H: AsRef<MarketHeader>,
+    S: AsRef<[u8]>,
+{
+    if !market.is_initialized() {
+        return Err(MarketInvariantError::Uninitialized);
+    }
+
+    let header = market.header.as_ref();
+    let sectors = market.sectors.as_ref();
+    let mut kinds: Vec<Option<SectorKind>> = vec![None; market.get_capacity() as usize];
+
+    let num_seats = walk_linked_list(
+        sectors,
+        &mut kinds,
+        SectorKind::Seat,
+        header.seats_dll_head(),
+        header.seats_dll_tail(),
+    )?;
+    let num_bids = walk_linked_list(
+        sectors,
+        &mut kinds,
+        SectorKind::Bid,
+        header.bids_dll_head(),
+        header.bids_dll_tail(),
+    )?;
+    let num_asks = walk_linked_list(
+        sectors,
+        &mut kinds,
+        SectorKind::Ask,
+        header.asks_dll_head(),
+        header.asks_dll_tail(),
+    )?;
+    let num_free = walk_free_stack(sectors, &mut kinds, header.free_stack_top())?;
+
+    if let Some(index) = kinds.iter().position(Option::is_none) {
+        return Err(MarketInvariantError::UnreachableSector {
+            index: index as SectorIndex,
+        });
+    }
+
+    for (kind, header_count, actual_count) in [
+        (SectorKind::Seat, header.num_seats(), num_seats),
+        (SectorKind::Bid, header.num_bids(), num_bids),
+        (SectorKind::Ask, header.num_asks(), num_asks),
+        (SectorKind::Free, header.num_free_sectors(), num_free),
+    ] {
+        if header_count != actual_count {
+            return Err(MarketInvariantError::CountMismatch {
+                kind,
+                header_count,
+                actual_count,
+            });
+        }
+    }
+
+    Ok(kinds.into_iter().flatten().collect())
+}
+
+#[inline(always)]
+fn checked_add(a: u64, b: u64) -> Result<u64, MarketInvariantError> {
+    a.checked_add(b)
+        .ok_or(MarketInvariantError::BalanceOverflow)
+}
+
+/// Marks `index` as belonging to `kind`, failing if it's out of bounds or already claimed.
+fn visit(
+    kinds: &mut [Option<SectorKind>],
+    kind: SectorKind,
+    index: SectorIndex,
+) -> Result<(), MarketInvariantError> {
+    let slot = kinds
+        .get_mut(index as usize)
+        .ok_or(MarketInvariantError::IndexOutOfBounds { kind, index })?;
+
+    match slot {
+        Some(first) => Err(MarketInvariantError::SectorAlreadyVisited {
+            index,
+            first: *first,
+            second: kind,
+        }),
+        None => {
+            *slot = Some(kind);
+            Ok(())
+        }
+    }
+}
+
+/// Walks a linked list from `head`, checking its links and returning its length.
+fn walk_linked_list(
+    sectors: &[u8],
+    kinds: &mut [Option<SectorKind>],
+    kind: SectorKind,
+    head: SectorIndex,
+    tail: SectorIndex,
+) -> Result<u32, MarketInvariantError> {
+    let mut prev = NIL;
+    let mut curr = head;
+    let mut len = 0;
+
+    while curr != NIL {
+        // Visiting first guarantees `curr` is in-bounds and breaks any cycles.
+        visit(kinds, kind, curr)?;
+        // Safety: `visit` just checked that `curr` is in-bounds.
+        let sector = unsafe { Sector::from_sector_index(sectors, curr) };
+
+        if sector.prev() != prev {
+            return Err(MarketInvariantError::BrokenPrevLink {
+                kind,
+                index: curr,
+                expected_prev: prev,
+                actual_prev: sector.prev(),
+            });
+        }
+
+        prev = curr;
+        curr = sector.next();
+        len += 1;
+    }
+
+    if prev != tail {
+        return Err(MarketInvariantError::TailMismatch {
+            kind,
+            expected: prev,
+            actual: tail,
+        });
+    }
+
+    Ok(len)
+}
+
+/// Walks the free stack from `top`, returning its length.
+fn walk_free_stack(
+    sectors: &[u8],
+    kinds: &mut [Option<SectorKind>],
+    top: SectorIndex,
+) -> Result<u32, MarketInvariantError> {
+    let mut curr = top;
+    let mut len = 0;
+
+    while curr != NIL {
+        visit(kinds, SectorKind::Free, curr)?;
+        // Safety: `visit` just checked that `curr` is in-bounds.
+        curr = unsafe { Sector::from_sector_index(sectors, curr) }.next();
+        len += 1;
+    }
+
+    Ok(len)
+}
+
+fn check_seats_sorted<H, S>(market: &Market<H, S>) -> Result<(), MarketInvariantError>
+where
+    H: AsRef<MarketHeader>,
+    S: AsRef<[u8]>,
+{
+    let mut prev_user: Option<&Address> = None;
+    for (index, sector) in market.iter_seats() {
+        let user = &sector.load_payload::<MarketSeat>().user;
+        if prev_user.is_some_and(|prev| prev >= user) {
+            return Err(MarketInvariantError::SeatsNotSorted { index });
+        }
+        prev_user = Some(user);
+    }
+
+    Ok(())
+}
+
+fn check_orders_sorted<H, S>(market: &Market<H, S>) -> Result<(), MarketInvariantError>
+where
+    H: AsRef<MarketHeader>,
+    S: AsRef<[u8]>,
+{
+    for (kind, orders) in [
+        (SectorKind::Bid, market.iter_bids()),
+        (SectorKind::Ask, market.iter_asks()),
+    ] {
+        let mut prev_price: Option<u32> = None;
+        for (index, sector) in orders {
+            let price = sector.load_payload::<Order>().encoded_price();
+            let is_sorted = match (kind, prev_price) {
+                (_, None) => true,
+                (SectorKind::Bid, Some(prev)) => prev >= price,
+                (_, Some(prev)) => prev <= price,
+            };
+            if !is_sorted {
+                return Err(MarketInvariantError::OrdersNotSorted { kind, index });
+            }
+            prev_price = Some(price);
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that an order's `user_seat` is a seat that maps the order's price to the order's index.
+fn check_order_back_reference(
+    sectors: &[u8],
+    kinds: &[SectorKind],
+    kind: SectorKind,
+    order_index: SectorIndex,
+    order: &Order,
+) -> Result<(), MarketInvariantError> {
+    let user_seat = order.user_seat();
+    if kinds.get(user_seat as usize).copied() != Some(SectorKind::Seat) {
+        return Err(MarketInvariantError::OrderSeatNotFound {
+            kind,
+            order_index,
+            user_seat,
+        });
+    }
+
+    // Safety: `user_seat` was just verified as an in-bounds seat sector.
+    let seat =
+        unsafe { Sector::from_sector_index(sectors, user_seat) }.load_payload::<MarketSeat>();
+    let order_sectors = match kind {
+        SectorKind::Bid => &seat.user_order_sectors.bids,
+        _ => &seat.user_order_sectors.asks,
+    };
+
+    if order_sectors.get(order.le_encoded_price()) != Some(order_index) {
+        return Err(MarketInvariantError::OrderNotReferencedBySeat {
+            kind,
+            order_index,
+            user_seat,
+        });
+    }
+
+    Ok(())
+}
+
+/// Checks that every used entry in a seat's [`OrderSectors`] points to an order of the right side
+/// with the same price and a `user_seat` pointing back to the seat.
+fn check_seat_order_entries(
+    sectors: &[u8],
+    kinds: &[SectorKind],
+    seat_index: SectorIndex,
+    order_sectors: &OrderSectors,
+    kind: SectorKind,
+) -> Result<(), MarketInvariantError> {
+    for entry in order_sectors.iter().filter(|entry| !entry.is_free()) {
+        let order_index = SectorIndex::from_le_bytes(entry.sector_index);
+        let dangling = MarketInvariantError::DanglingSeatOrderEntry {
+            kind,
+            seat_index,
+            order_index,
+        };
+
+        if kinds.get(order_index as usize).copied() != Some(kind) {
+            return Err(dangling);
+        }
+
+        // Safety: `order_index` was just verified as an in-bounds order sector.
+        let order =
+            unsafe { Sector::from_sector_index(sectors, order_index) }.load_payload::<Order>();
+        if order.user_seat() != seat_index
+            || order.le_encoded_price().as_slice() != entry.encoded_price.as_slice()
+        {
+            return Err(dangling);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use price::{
+        to_order_info,
+        OrderInfoArgs,
+    };
+
+    use super::*;
+    use crate::state::{
+        market::MarketRefMut,
+        sector::SECTOR_SIZE,
+        transmutable::Transmutable,
+    };
+
+    const NUM_SECTORS: u32 = 8;
+
+    fn new_market_bytes() -> Vec<u8> {
+        let mut bytes = vec![0u8; MarketHeader::LEN + NUM_SECTORS as usize * SECTOR_SIZE];
+        // Safety: `bytes` is long enough for the header and isn't borrowed elsewhere.
+        unsafe {
+            MarketHeader::init(
+                bytes.as_mut_ptr() as *mut MarketHeader,
+                255,
+                &Address::new_from_array([1; 32]),
+                &Address::new_from_array([2; 32]),
+            );
+            let mut market = MarketRefMut::from_bytes_mut(&mut bytes);
+            market
+                .free_stack()
+                .convert_zeroed_bytes_to_free_sectors(0, NUM_SECTORS)
+                .unwrap();
+        }
+        bytes
+    }
+
+    /// Inserts a seat with one bid and one ask, returning the seat, bid and ask sector indices.
+    fn insert_seat_with_orders(
+        market: &mut MarketRefMut,
+    ) -> (SectorIndex, SectorIndex, SectorIndex) {
+        let seat_index = market
+            .seats()
+            .push_back(MarketSeat::new(Address::new_from_array([3; 32]), 100, 200).as_bytes())
+            .unwrap();
+
+        let bid_info = to_order_info(OrderInfoArgs::new_unscaled(11_000_000, 1)).unwrap();
+        let ask_info = to_order_info(OrderInfoArgs::new_unscaled(12_000_000, 1)).unwrap();
+        let bid = Order::new(bid_info, seat_index);
+        let ask = Order::new(ask_info, seat_index);
+        let bid_index = market.bids().push_back(bid.as_bytes()).unwrap();
+        let ask_index = market.asks().push_back(ask.as_bytes()).unwrap();
+
+        // Safety: `seat_index` was just returned from a successful insertion.
+        let seat = unsafe { Sector::from_sector_index_mut(market.sectors, seat_index) }
+            .load_payload_mut::<MarketSeat>();
+        seat.user_order_sectors
+            .bids
+            .add(bid.le_encoded_price(), &bid_index.to_le_bytes())
+            .unwrap();
+        seat.user_order_sectors
+            .asks
+            .add(ask.le_encoded_price(), &ask_index.to_le_bytes())
+            .unwrap();
+
+        (seat_index, bid_index, ask_index)
+    }
+
+    #[test]
+    fn empty_market_is_consistent() {
+        let bytes = new_market_bytes();
+        // Safety: `bytes` holds an initialized market.
+        let market = unsafe { crate::state::market::MarketRef::from_bytes(&bytes) };
+        assert_eq!(
+            check_market_balances(&market, 0, 0),
+            Ok(MarketLiabilities::default())
+        );
+    }
+
+    #[test]
+    fn seat_and_orders_are_consistent() {
+        let mut bytes = new_market_bytes();
+        // Safety: `bytes` holds an initialized market.
+        let mut market = unsafe { MarketRefMut::from_bytes_mut(&mut bytes) };
+        let (_, bid_index, ask_index) = insert_seat_with_orders(&mut market);
+
+        let liabilities = check_market_invariants(&market).unwrap();
+        let bid = unsafe { Sector::from_sector_index(market.sectors, bid_index) }
+            .load_payload::<Order>()
+            .clone();
+        let ask = unsafe { Sector::from_sector_index(market.sectors, ask_index) }
+            .load_payload::<Order>()
+            .clone();
+        assert_eq!(liabilities.base, 100 + ask.base_remaining());
+        assert_eq!(liabilities.quote, 200 + bid.quote_remaining());
+
+        assert!(check_market_balances(&market, liabilities.base, liabilities.quote).is_ok());
+        assert_eq!(
+            check_market_balances(&market, liabilities.base + 1, liabilities.quote),
+            Err(MarketInvariantError::BalanceMismatch {
+                is_base: true,
+                expected: liabilities.base,
+                actual: liabilities.base + 1,
+            })
+        );
+    }
+
+    #[test]
+    fn detects_count_mismatch() {
+        let mut bytes = new_market_bytes();
+        let mut market = unsafe { MarketRefMut::from_bytes_mut(&mut bytes) };
+        insert_seat_with_orders(&mut market);
+        market.header.increment_num_bids();
+
+        assert_eq!(
+            check_market_invariants(&market),
+            Err(MarketInvariantError::CountMismatch {
+                kind: SectorKind::Bid,
+                header_count: 2,
+                actual_count: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn detects_sector_in_two_structures() {
+        let mut bytes = new_market_bytes();
+        let mut market = unsafe { MarketRefMut::from_bytes_mut(&mut bytes) };
+        let (seat_index, ..) = insert_seat_with_orders(&mut market);
+        // Point the free stack at the seat sector.
+        market.header.set_free_stack_top(seat_index);
+
+        assert!(matches!(
+            check_market_invariants(&market),
+            Err(MarketInvariantError::SectorAlreadyVisited { index, .. }) if index == seat_index
+        ));
+    }
+
+    #[test]
+    fn detects_leaked_sector() {
+        let mut bytes = new_market_bytes();
+        let mut market = unsafe { MarketRefMut::from_bytes_mut(&mut bytes) };
+        // Pop a free sector without inserting it anywhere.
+        let leaked = market.free_stack().pop_free_sector().unwrap();
+
+        assert_eq!(
+            check_market_invariants(&market),
+            Err(MarketInvariantError::UnreachableSector { index: leaked })
+        );
+    }
+
+    #[test]
+    fn detects_unsorted_bids() {
+        let mut bytes = new_market_bytes();
+        let mut market = unsafe { MarketRefMut::from_bytes_mut(&mut bytes) };
+        let (seat_index, ..) = insert_seat_with_orders(&mut market);
+        // Append a higher bid after the existing one, breaking the descending order.
+        let higher = to_order_info(OrderInfoArgs::new_unscaled(11_500_000, 1)).unwrap();
+        let order = Order::new(higher, seat_index);
+        let index = market.bids().push_back(order.as_bytes()).unwrap();
+
+        assert_eq!(
+            check_market_invariants(&market),
+            Err(MarketInvariantError::OrdersNotSorted {
+                kind: SectorKind::Bid,
+                index,
+            })
+        );
+    }
+
+    #[test]
+    fn detects_order_without_seat_reference() {
+        let mut bytes = new_market_bytes();
+        let mut market = unsafe { MarketRefMut::from_bytes_mut(&mut bytes) };
+        let (seat_index, _, ask_index) = insert_seat_with_orders(&mut market);
+        let ask_price = unsafe { Sector::from_sector_index(market.sectors, ask_index) }
+            .load_payload::<Order>()
+            .encoded_price();
+        let seat = unsafe { Sector::from_sector_index_mut(market.sectors, seat_index) }
+            .load_payload_mut::<MarketSeat>();
+        seat.user_order_sectors.asks.remove(ask_price).unwrap();
+
+        assert_eq!(
+            check_market_invariants(&market),
+            Err(MarketInvariantError::OrderNotReferencedBySeat {
+                kind: SectorKind::Ask,
+                order_index: ask_index,
+                user_seat: seat_index,
+            })
+        );
+    }
+}
@@ -7,6 +7,8 @@ pub mod free_stack;
 pub mod linked_list;
 pub mod market;
 pub mod market_header;
+#[cfg(feature = "std")]
+pub mod market_invariants;
 pub mod market_seat;
 pub mod order;
 pub mod seats_dll;
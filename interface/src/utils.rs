@@ -1,11 +1,18 @@
 //! Lightweight helper functions and constants used throughout the interface and state modules.
 
+use core::num::{
+    NonZeroU128,
+    NonZeroU64,
+};
+
 use pinocchio::account::AccountView;
 use solana_address::{
     address_eq,
     Address,
 };
 
+use crate::error::DropsetError;
+
 #[inline(always)]
 pub fn owned_by(account: &AccountView, potential_owner: &Address) -> bool {
     // Safety: Scoped borrow of account owner.
@@ -22,3 +29,24 @@ pub fn owned_by(account: &AccountView, potential_owner: &Address) -> bool {
 pub fn is_owned_by_spl_token(account: &AccountView) -> bool {
     owned_by(account, &pinocchio_token::ID)
 }
+
+/// Computes `multiplicand * multiplier / divisor` rounded down, as used to prorate a market
+/// order's partial fill. Clients quoting fills call this too, so quotes match on-chain fills.
+#[inline(always)]
+pub fn mul_div_checked(
+    multiplicand: u64,
+    multiplier: u64,
+    divisor: NonZeroU64,
+) -> Result<u64, DropsetError> {
+    let intermediate = price::checked_mul!(
+        multiplicand as u128,
+        multiplier as u128,
+        DropsetError::ArithmeticOverflow
+    )?;
+
+    let res = intermediate / NonZeroU128::from(divisor);
+    if res > u64::MAX as u128 {
+        return Err(DropsetError::ArithmeticOverflow);
+    }
+    Ok(res as u64)
+}
@@ -0,0 +1,304 @@
+//! Model-based property tests for [`LinkedList`] and [`Stack`].
+//!
+//! Random sequences of operations are applied to both a real market buffer and a plain `Vec`
+//! reference model, and the full sector layout is compared against the model after every step.
+
+use dropset_interface::{
+    error::DropsetError,
+    state::{
+        asks_dll::AskOrders,
+        bids_dll::BidOrders,
+        free_stack::FreePayload,
+        linked_list::{
+            LinkedList,
+            LinkedListHeaderOperations,
+        },
+        market::{
+            MarketRef,
+            MarketRefMut,
+        },
+        market_header::MarketHeader,
+        seats_dll::Seats,
+        sector::{
+            Sector,
+            SectorIndex,
+            NIL,
+            PAYLOAD_SIZE,
+            SECTOR_SIZE,
+        },
+        transmutable::Transmutable,
+    },
+};
+use proptest::prelude::*;
+use solana_address::Address;
+
+const INITIAL_SECTORS: u32 = 6;
+const MAX_GROW: u32 = 4;
+
+#[derive(Debug, Clone, Copy)]
+enum ListId {
+    Seats,
+    Bids,
+    Asks,
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+    PushFront(ListId, u8),
+    PushBack(ListId, u8),
+    /// Inserts before the element at `selector % len`.
+    InsertBefore(ListId, usize, u8),
+    /// Removes the element at `selector % len`.
+    RemoveAt(ListId, usize),
+    /// Pops a free sector and holds onto it outside of any structure.
+    PopFree,
+    /// Pushes the held sector at `selector % len` back onto the free stack.
+    PushFree(usize),
+    /// Extends the account data and converts the new zeroed bytes to free sectors.
+    Grow(u32),
+}
+
+fn list_id() -> impl Strategy<Value = ListId> {
+    prop_oneof![Just(ListId::Seats), Just(ListId::Bids), Just(ListId::Asks)]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    // Payload bytes are never zero so they can be distinguished from zeroed free sectors.
+    let byte = 1..=u8::MAX;
+    prop_oneof![
+        4 => (list_id(), byte.clone()).prop_map(|(l, b)| Op::PushFront(l, b)),
+        4 => (list_id(), byte.clone()).prop_map(|(l, b)| Op::PushBack(l, b)),
+        4 => (list_id(), any::<usize>(), byte).prop_map(|(l, s, b)| Op::InsertBefore(l, s, b)),
+        5 => (list_id(), any::<usize>()).prop_map(|(l, s)| Op::RemoveAt(l, s)),
+        1 => Just(Op::PopFree),
+        1 => any::<usize>().prop_map(Op::PushFree),
+        1 => (1..=MAX_GROW).prop_map(Op::Grow),
+    ]
+}
+
+/// The reference model: each list is an ordered `Vec` of `(sector index, payload byte)` and the
+/// free stack is a `Vec` with the top at the end.
+#[derive(Debug, Default)]
+struct Model {
+    lists: [Vec<(SectorIndex, u8)>; 3],
+    free: Vec<SectorIndex>,
+    held: Vec<SectorIndex>,
+}
+
+fn new_market(num_sectors: u32) -> Vec<u8> {
+    let mut bytes = vec![0u8; MarketHeader::LEN + num_sectors as usize * SECTOR_SIZE];
+    // Safety: `bytes` is long enough for the header and isn't borrowed elsewhere.
+    unsafe {
+        MarketHeader::init(
+            bytes.as_mut_ptr() as *mut MarketHeader,
+            0,
+            &Address::new_from_array([1; 32]),
+            &Address::new_from_array([2; 32]),
+        );
+        MarketRefMut::from_bytes_mut(&mut bytes)
+            .free_stack()
+            .convert_zeroed_bytes_to_free_sectors(0, num_sectors)
+            .unwrap();
+    }
+    bytes
+}
+
+fn list_op<T: LinkedListHeaderOperations>(
+    mut list: LinkedList<'_, T>,
+    model: &mut Model,
+    list_idx: usize,
+    op: &Op,
+) {
+    match *op {
+        Op::PushFront(_, byte) => match list.push_front(&[byte; PAYLOAD_SIZE]) {
+            Ok(index) => {
+                assert_eq!(model.free.pop(), Some(index));
+                model.lists[list_idx].insert(0, (index, byte));
+            }
+            Err(e) => {
+                assert_eq!(e, DropsetError::NoFreeSectorsRemaining);
+                assert!(model.free.is_empty());
+            }
+        },
+        Op::PushBack(_, byte) => match list.push_back(&[byte; PAYLOAD_SIZE]) {
+            Ok(index) => {
+                assert_eq!(model.free.pop(), Some(index));
+                model.lists[list_idx].push((index, byte));
+            }
+            Err(e) => {
+                assert_eq!(e, DropsetError::NoFreeSectorsRemaining);
+                assert!(model.free.is_empty());
+            }
+        },
+        Op::InsertBefore(_, selector, byte) => {
+            let len = model.lists[list_idx].len();
+            if len == 0 {
+                return;
+            }
+            let pos = selector % len;
+            let next_index = model.lists[list_idx][pos].0;
+            // Safety: `next_index` is in the list, so it's in-bounds.
+            match unsafe { list.insert_before(next_index, &[byte; PAYLOAD_SIZE]) } {
+                Ok(index) => {
+                    assert_eq!(model.free.pop(), Some(index));
+                    model.lists[list_idx].insert(pos, (index, byte));
+                }
+                Err(e) => {
+                    assert_eq!(e, DropsetError::NoFreeSectorsRemaining);
+                    assert!(model.free.is_empty());
+                }
+            }
+        }
+        Op::RemoveAt(_, selector) => {
+            let len = model.lists[list_idx].len();
+            if len == 0 {
+                return;
+            }
+            let (index, _) = model.lists[list_idx].remove(selector % len);
+            // Safety: `index` is in the list, so it's in-bounds.
+            unsafe { list.remove_at(index) };
+            model.free.push(index);
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn apply(bytes: &mut Vec<u8>, model: &mut Model, op: &Op) {
+    if let Op::Grow(num_new) = *op {
+        let capacity = ((bytes.len() - MarketHeader::LEN) / SECTOR_SIZE) as u32;
+        bytes.resize(bytes.len() + num_new as usize * SECTOR_SIZE, 0);
+        // Safety: The new bytes were just zero-initialized and `capacity < capacity + num_new`.
+        unsafe {
+            MarketRefMut::from_bytes_mut(bytes)
+                .free_stack()
+                .convert_zeroed_bytes_to_free_sectors(capacity, capacity + num_new)
+                .unwrap();
+        }
+        // The new sectors are pushed in reverse so the lowest index ends up on top.
+        model.free.extend((capacity..capacity + num_new).rev());
+        return;
+    }
+
+    // Safety: `bytes` always holds an initialized market.
+    let mut market = unsafe { MarketRefMut::from_bytes_mut(bytes) };
+    match *op {
+        Op::PushFront(id, ..)
+        | Op::PushBack(id, ..)
+        | Op::InsertBefore(id, ..)
+        | Op::RemoveAt(id, ..) => match id {
+            ListId::Seats => list_op(market.seats(), model, 0, op),
+            ListId::Bids => list_op(market.bids(), model, 1, op),
+            ListId::Asks => list_op(market.asks(), model, 2, op),
+        },
+        Op::PopFree => match market.free_stack().pop_free_sector() {
+            Ok(index) => {
+                assert_eq!(model.free.pop(), Some(index));
+                model.held.push(index);
+            }
+            Err(e) => {
+                assert_eq!(e, DropsetError::NoFreeSectorsRemaining);
+                assert!(model.free.is_empty());
+            }
+        },
+        Op::PushFree(selector) => {
+            if model.held.is_empty() {
+                return;
+            }
+            let index = model.held.remove(selector % model.held.len());
+            // Safety: `index` was popped from the free stack, so it's in-bounds.
+            unsafe { market.free_stack().push_free_sector(index) };
+            model.free.push(index);
+        }
+        Op::Grow(_) => unreachable!(),
+    }
+}
+
+/// Walks a list forwards and backwards and checks it against the model.
+fn assert_list_matches<T: LinkedListHeaderOperations>(
+    market: &MarketRef,
+    expected: &[(SectorIndex, u8)],
+) {
+    let header = market.header;
+    let (head, tail) = (T::head(header), T::tail(header));
+
+    let mut forward = vec![];
+    let (mut prev, mut curr) = (NIL, head);
+    while curr != NIL {
+        Sector::check_in_bounds(market.sectors, curr).expect("Link is out of bounds");
+        // Safety: `curr` was just checked as in-bounds.
+        let sector = unsafe { Sector::from_sector_index(market.sectors, curr) };
+        assert_eq!(sector.prev(), prev, "Broken prev link at {curr}");
+        let payload = sector.load_payload::<FreePayload>().0;
+        assert!(payload.iter().all(|b| *b == payload[0]));
+        forward.push((curr, payload[0]));
+        assert!(
+            forward.len() <= expected.len(),
+            "List is longer than the model"
+        );
+        (prev, curr) = (curr, sector.next());
+    }
+    assert_eq!(forward, expected);
+    assert_eq!(tail, prev);
+}
+
+fn assert_matches_model(bytes: &[u8], model: &Model) {
+    // Safety: `bytes` always holds an initialized market.
+    let market = unsafe { MarketRef::from_bytes(bytes) };
+    let header = market.header;
+
+    assert_list_matches::<Seats>(&market, &model.lists[0]);
+    assert_list_matches::<BidOrders>(&market, &model.lists[1]);
+    assert_list_matches::<AskOrders>(&market, &model.lists[2]);
+    assert_eq!(header.num_seats() as usize, model.lists[0].len());
+    assert_eq!(header.num_bids() as usize, model.lists[1].len());
+    assert_eq!(header.num_asks() as usize, model.lists[2].len());
+
+    let mut free = vec![];
+    let mut curr = header.free_stack_top();
+    while curr != NIL {
+        Sector::check_in_bounds(market.sectors, curr).expect("Free link is out of bounds");
+        // Safety: `curr` was just checked as in-bounds.
+        let sector = unsafe { Sector::from_sector_index(market.sectors, curr) };
+        // Free sectors are either freshly zeroed or zeroed when pushed onto the stack.
+        assert_eq!(sector.load_payload::<FreePayload>().0, [0; PAYLOAD_SIZE]);
+        free.push(curr);
+        assert!(
+            free.len() <= model.free.len(),
+            "Free stack is longer than the model"
+        );
+        curr = sector.next();
+    }
+    free.reverse();
+    assert_eq!(free, model.free);
+    assert_eq!(header.num_free_sectors() as usize, model.free.len());
+
+    // Every sector is accounted for exactly once across the lists, free stack and held sectors.
+    let mut all: Vec<SectorIndex> = model
+        .lists
+        .iter()
+        .flat_map(|list| list.iter().map(|(index, _)| *index))
+        .chain(model.free.iter().copied())
+        .chain(model.held.iter().copied())
+        .collect();
+    all.sort_unstable();
+    assert_eq!(all, (0..market.get_capacity()).collect::<Vec<_>>());
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn linked_lists_and_free_stack_match_model(ops in prop::collection::vec(op(), 1..128)) {
+        let mut bytes = new_market(INITIAL_SECTORS);
+        let mut model = Model {
+            free: (0..INITIAL_SECTORS).rev().collect(),
+            ..Default::default()
+        };
+        assert_matches_model(&bytes, &model);
+
+        for op in ops.iter() {
+            apply(&mut bytes, &mut model, op);
+            assert_matches_model(&bytes, &model);
+        }
+    }
+}
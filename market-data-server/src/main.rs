@@ -0,0 +1,144 @@
+//! See [`main`].
+
+mod market_data;
+mod rest;
+mod ws;
+
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+};
+
+use axum::{
+    routing::get,
+    Router,
+};
+use clap::Parser;
+use grpc_stream::dropset_stream::{
+    DropsetStream,
+    DropsetStreamConfig,
+    GrpcConnector,
+    StreamMessage,
+};
+use tokio::{
+    net::TcpListener,
+    sync::{
+        broadcast,
+        mpsc,
+        RwLock,
+    },
+};
+use yellowstone_grpc_proto::prelude::CommitmentLevel;
+
+use crate::market_data::{
+    ChannelMessage,
+    MarketData,
+};
+
+/// The number of unsent channel messages buffered per WebSocket client before the oldest are
+/// dropped.
+const HUB_CAPACITY: usize = 4096;
+
+#[derive(Parser)]
+#[command(name = "market-data-server")]
+struct CliArgs {
+    /// The `geyser` gRPC endpoint to stream `dropset` updates from.
+    #[arg(long, default_value = "http://localhost:10000")]
+    grpc_endpoint: String,
+
+    /// The `x-token` to authenticate with the gRPC endpoint, if it requires one.
+    #[arg(long)]
+    x_token: Option<String>,
+
+    /// The address to serve the WebSocket and REST APIs on.
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    bind: SocketAddr,
+
+    /// The number of recent trades kept per market.
+    #[arg(long, default_value_t = 1000)]
+    trade_history: usize,
+}
+
+/// The state shared by every WebSocket connection and REST handler.
+#[derive(Clone)]
+pub struct AppState {
+    pub data: Arc<RwLock<MarketData>>,
+    pub hub: broadcast::Sender<ChannelMessage>,
+}
+
+/// Serves confirmed `dropset` books, trades, and user seats from a `geyser` gRPC stream.
+///
+/// WebSocket clients connect to `/ws` and send `{"op": "subscribe", "channel": "book:{market}"}`
+/// (or `trades:{market}` / `user:{address}`) to receive the channel's current state followed by
+/// every change. The same data is available over REST at `/markets`, `/markets/{market}/book`,
+/// `/markets/{market}/trades`, and `/users/{user}/seats`.
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let CliArgs {
+        grpc_endpoint,
+        x_token,
+        bind,
+        trade_history,
+    } = CliArgs::parse();
+
+    let connector = GrpcConnector {
+        endpoint: grpc_endpoint,
+        x_token,
+    };
+    let (sender, receiver) = mpsc::channel(1024);
+    tokio::spawn(DropsetStream::new(connector, DropsetStreamConfig::default()).run(sender));
+
+    let (hub, _) = broadcast::channel(HUB_CAPACITY);
+    let state = AppState {
+        data: Arc::new(RwLock::new(MarketData::new(trade_history))),
+        hub,
+    };
+    tokio::spawn(ingest(receiver, state.clone()));
+
+    let router = Router::new()
+        .route("/ws", get(ws::handler))
+        .route("/markets", get(rest::markets))
+        .route("/markets/{market}/book", get(rest::book))
+        .route("/markets/{market}/trades", get(rest::trades))
+        .route("/users/{user}/seats", get(rest::seats))
+        .with_state(state);
+
+    let listener = TcpListener::bind(bind).await?;
+    println!("Serving market data on {bind}");
+    axum::serve(listener, router).await?;
+
+    Ok(())
+}
+
+/// Applies confirmed updates from the stream to the shared market data and publishes the resulting
+/// channel messages.
+///
+/// Only confirmed updates are applied, so clients never see state from a fork that's later dropped.
+async fn ingest(mut receiver: mpsc::Receiver<StreamMessage>, state: AppState) {
+    while let Some(message) = receiver.recv().await {
+        match message {
+            StreamMessage::Update {
+                slot,
+                block_time,
+                commitment: CommitmentLevel::Confirmed,
+                update,
+            } => {
+                let messages = state.data.write().await.apply(slot, block_time, update);
+                for message in messages {
+                    // Sending only fails when there are no subscribers, which is fine.
+                    let _ = state.hub.send(message);
+                }
+            }
+            StreamMessage::Update { .. } => {}
+            StreamMessage::Dropped { slot, updates } => {
+                eprintln!("Dropped {} updates from slot {slot}", updates.len());
+            }
+            StreamMessage::Disconnected { reason, retry_in } => {
+                eprintln!("{reason}, reconnecting in {retry_in:?}");
+            }
+            StreamMessage::Reconnected { from_slot } => {
+                eprintln!("Reconnected from slot {from_slot:?}");
+            }
+        }
+    }
+}
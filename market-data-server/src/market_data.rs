@@ -0,0 +1,625 @@
+//! The server's view of every market, built from the parsed stream, and the JSON it serves.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    str::FromStr,
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use grpc_stream::{
+    book_state::{
+        BookDelta,
+        BookSnapshot,
+        BookState,
+        L2Level,
+        L3Order,
+    },
+    parse_update::ParsedUpdate,
+};
+use serde::Serialize;
+use serde_json::{
+    json,
+    Value,
+};
+use solana_address::Address;
+use transaction_parser::{
+    json::EncodedPriceJson,
+    market_data::trade::{
+        Trade,
+        TradeTape,
+    },
+    views::MarketUserData,
+};
+
+/// A WebSocket channel a client can subscribe to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Channel {
+    /// Book snapshots and deltas for a market.
+    Book(Address),
+    /// Trades in a market.
+    Trades(Address),
+    /// A user's seat balances and orders across all markets.
+    User(Address),
+}
+
+impl Display for Channel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Book(market) => write!(f, "book:{market}"),
+            Self::Trades(market) => write!(f, "trades:{market}"),
+            Self::User(user) => write!(f, "user:{user}"),
+        }
+    }
+}
+
+impl FromStr for Channel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, address) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("Invalid channel format: {s}"))?;
+        let address: Address = address
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid address in channel: {s}"))?;
+
+        match kind {
+            "book" => Ok(Self::Book(address)),
+            "trades" => Ok(Self::Trades(address)),
+            "user" => Ok(Self::User(address)),
+            _ => Err(anyhow::anyhow!("Unknown channel: {s}")),
+        }
+    }
+}
+
+/// A message published on a channel.
+#[derive(Clone, Debug, Serialize)]
+pub struct ChannelMessage {
+    /// The number of updates applied when the message was created. A channel snapshot already
+    /// reflects every published message with the same or a lower `seq`.
+    pub seq: u64,
+    pub channel: String,
+    pub data: Value,
+}
+
+impl ChannelMessage {
+    fn new(seq: u64, channel: Channel, data: Value) -> Self {
+        Self {
+            seq,
+            channel: channel.to_string(),
+            data,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OrderJson {
+    pub index: u32,
+    pub user: String,
+    pub price: EncodedPriceJson,
+    pub base_remaining: u64,
+    pub quote_remaining: u64,
+}
+
+impl From<&L3Order> for OrderJson {
+    fn from(order: &L3Order) -> Self {
+        Self {
+            index: order.index,
+            user: order.user.to_string(),
+            price: order.encoded_price.into(),
+            base_remaining: order.base_remaining,
+            quote_remaining: order.quote_remaining,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LevelJson {
+    pub price: EncodedPriceJson,
+    pub base_atoms: u128,
+    pub quote_atoms: u128,
+    pub num_orders: u32,
+}
+
+impl From<L2Level> for LevelJson {
+    fn from(level: L2Level) -> Self {
+        Self {
+            price: level.encoded_price.into(),
+            base_atoms: level.base_atoms,
+            quote_atoms: level.quote_atoms,
+            num_orders: level.num_orders,
+        }
+    }
+}
+
+/// A market's L2 levels and L3 orders, best price first.
+#[derive(Clone, Debug, Serialize)]
+pub struct BookJson {
+    pub market: String,
+    pub slot: u64,
+    pub bids: Vec<LevelJson>,
+    pub asks: Vec<LevelJson>,
+    pub bid_orders: Vec<OrderJson>,
+    pub ask_orders: Vec<OrderJson>,
+}
+
+impl BookJson {
+    fn new(market: &Address, snapshot: &BookSnapshot) -> Self {
+        Self {
+            market: market.to_string(),
+            slot: snapshot.slot,
+            bids: snapshot.l2_bids().into_iter().map(Into::into).collect(),
+            asks: snapshot.l2_asks().into_iter().map(Into::into).collect(),
+            bid_orders: snapshot.bids.iter().map(Into::into).collect(),
+            ask_orders: snapshot.asks.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeltaJson {
+    Added {
+        order: OrderJson,
+    },
+    Removed {
+        order: OrderJson,
+    },
+    Changed {
+        previous: OrderJson,
+        order: OrderJson,
+    },
+}
+
+impl From<&BookDelta> for DeltaJson {
+    fn from(delta: &BookDelta) -> Self {
+        match delta {
+            BookDelta::Added(order) => Self::Added {
+                order: order.into(),
+            },
+            BookDelta::Removed(order) => Self::Removed {
+                order: order.into(),
+            },
+            BookDelta::Changed { previous, current } => Self::Changed {
+                previous: previous.into(),
+                order: current.into(),
+            },
+        }
+    }
+}
+
+/// The latest books, recent trades, and seats for every market seen on the stream.
+pub struct MarketData {
+    books: BookState,
+    tapes: HashMap<Address, TradeTape>,
+    /// Each user's serialized seat data, by user and then by market.
+    seats: HashMap<Address, HashMap<Address, Value>>,
+    trade_history: usize,
+    /// The number of updates applied so far.
+    seq: u64,
+}
+
+impl MarketData {
+    pub fn new(trade_history: usize) -> Self {
+        Self {
+            books: BookState::default(),
+            tapes: HashMap::new(),
+            seats: HashMap::new(),
+            trade_history,
+            seq: 0,
+        }
+    }
+
+    /// Applies a confirmed update and returns the messages to publish.
+    pub fn apply(
+        &mut self,
+        slot: u64,
+        block_time: Option<i64>,
+        update: ParsedUpdate,
+    ) -> Vec<ChannelMessage> {
+        self.seq += 1;
+        let seq = self.seq;
+        match update {
+            ParsedUpdate::Market(market) => {
+                let mut messages = vec![];
+                if let Some(book_update) = self.books.apply(&market) {
+                    let deltas = book_update
+                        .deltas
+                        .iter()
+                        .map(DeltaJson::from)
+                        .collect::<Vec<_>>();
+                    messages.push(ChannelMessage::new(
+                        seq,
+                        Channel::Book(market.address),
+                        json!({
+                            "book": BookJson::new(&market.address, &book_update.snapshot),
+                            "deltas": deltas,
+                        }),
+                    ));
+                }
+                messages.extend(self.apply_seats(market.address, &market.view.users));
+                messages
+            }
+            ParsedUpdate::EmittedEvents { events, .. } => {
+                // Trades are stamped with the time they were received if the slot's block meta
+                // hasn't arrived yet, so they still land in the live candle.
+                let timestamp = block_time.unwrap_or_else(|| {
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+                });
+                events
+                    .iter()
+                    .flat_map(|ixn| ixn.events.iter())
+                    .filter_map(|event| Trade::from_event(event, slot, timestamp))
+                    .map(|trade| {
+                        let message = ChannelMessage::new(
+                            seq,
+                            Channel::Trades(trade.market),
+                            serde_json::to_value(&trade).expect("Trades should serialize"),
+                        );
+                        self.tapes
+                            .entry(trade.market)
+                            .or_insert_with(|| TradeTape::new(self.trade_history))
+                            .push(trade);
+                        message
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    fn apply_seats(
+        &mut self,
+        market: Address,
+        users: &HashMap<Address, MarketUserData>,
+    ) -> Vec<ChannelMessage> {
+        let seq = self.seq;
+        let mut messages = vec![];
+        for (user, data) in users {
+            let data = serde_json::to_value(data).expect("Seats should serialize");
+            let seats = self.seats.entry(*user).or_default();
+            if seats.get(&market) != Some(&data) {
+                seats.insert(market, data.clone());
+                messages.push(ChannelMessage::new(
+                    seq,
+                    Channel::User(*user),
+                    seat_json(&market, Some(data)),
+                ));
+            }
+        }
+
+        // Users whose seats were closed since the last update.
+        for (user, seats) in self.seats.iter_mut() {
+            if !users.contains_key(user) && seats.remove(&market).is_some() {
+                messages.push(ChannelMessage::new(
+                    seq,
+                    Channel::User(*user),
+                    seat_json(&market, None),
+                ));
+            }
+        }
+
+        messages
+    }
+
+    pub fn markets(&self) -> Vec<String> {
+        self.books.markets().map(ToString::to_string).collect()
+    }
+
+    pub fn book(&self, market: &Address) -> Option<BookJson> {
+        self.books
+            .book(market)
+            .map(|snapshot| BookJson::new(market, &snapshot))
+    }
+
+    /// Whether the market has a book or any trades.
+    pub fn is_known(&self, market: &Address) -> bool {
+        self.books.book(market).is_some() || self.tapes.contains_key(market)
+    }
+
+    /// Up to `limit` of the market's most recent trades, newest first.
+    pub fn trades(&self, market: &Address, limit: usize) -> Vec<Trade> {
+        self.tapes.get(market).map_or(vec![], |tape| {
+            tape.iter().rev().take(limit).cloned().collect()
+        })
+    }
+
+    /// The user's seats in every market they have one in.
+    pub fn seats(&self, user: &Address) -> Vec<Value> {
+        self.seats.get(user).map_or(vec![], |seats| {
+            seats
+                .iter()
+                .map(|(market, data)| seat_json(market, Some(data.clone())))
+                .collect()
+        })
+    }
+
+    /// The current state of a channel, sent to clients when they subscribe.
+    pub fn snapshot(&self, channel: Channel) -> ChannelMessage {
+        let data = match channel {
+            Channel::Book(market) => json!({ "book": self.book(&market), "deltas": [] }),
+            Channel::Trades(market) => json!(self.trades(&market, self.trade_history)),
+            Channel::User(user) => json!(self.seats(&user)),
+        };
+
+        ChannelMessage::new(self.seq, channel, data)
+    }
+}
+
+fn seat_json(market: &Address, seat: Option<Value>) -> Value {
+    json!({ "market": market.to_string(), "seat": seat })
+}
+
+#[cfg(test)]
+mod tests {
+    use grpc_stream::parse_update::{
+        InstructionEventsWithIndices,
+        MarketUpdate,
+    };
+    use transaction_parser::{
+        events::{
+            display_types::DisplayMarketOrderData,
+            dropset_event::{
+                DropsetEvent,
+                SequencedEvent,
+            },
+        },
+        views::{
+            MarketHeaderView,
+            MarketSeatView,
+            MarketViewAll,
+            OrderView,
+        },
+    };
+
+    use super::*;
+
+    const MARKET: Address = Address::new_from_array([1; 32]);
+    const ALICE: Address = Address::new_from_array([2; 32]);
+    const BOB: Address = Address::new_from_array([3; 32]);
+
+    fn header() -> MarketHeaderView {
+        MarketHeaderView {
+            discriminant: 0,
+            num_seats: 0,
+            num_bids: 0,
+            num_asks: 0,
+            num_free_sectors: 0,
+            free_stack_top: 0,
+            seats_dll_head: 0,
+            seats_dll_tail: 0,
+            bids_dll_head: 0,
+            bids_dll_tail: 0,
+            asks_dll_head: 0,
+            asks_dll_tail: 0,
+            base_mint: Address::new_from_array([4; 32]),
+            quote_mint: Address::new_from_array([5; 32]),
+            market_bump: 0,
+            nonce: 0,
+            layout_version: 0,
+            _padding: [0; 2],
+        }
+    }
+
+    fn seat(index: u32, user: Address) -> MarketSeatView {
+        MarketSeatView {
+            prev_index: 0,
+            index,
+            next_index: 0,
+            user,
+            base_available: 10,
+            quote_available: 20,
+            user_order_sectors: Default::default(),
+        }
+    }
+
+    fn bid(index: u32, user_seat: u32, base_remaining: u64) -> OrderView {
+        OrderView {
+            prev_index: 0,
+            index,
+            next_index: 0,
+            encoded_price: 100,
+            user_seat,
+            base_remaining,
+            quote_remaining: base_remaining * 2,
+        }
+    }
+
+    /// A market update at `slot` with a seat for each of `seats` and the passed bids, which are
+    /// all attributed to the first seat's user.
+    fn market_update(slot: u64, seats: &[(u32, Address)], bids: Vec<OrderView>) -> ParsedUpdate {
+        let seats = seats
+            .iter()
+            .map(|(index, user)| seat(*index, *user))
+            .collect::<Vec<_>>();
+        let users = seats
+            .iter()
+            .enumerate()
+            .map(|(i, seat)| {
+                let data = MarketUserData {
+                    seat: seat.clone(),
+                    bids: if i == 0 { bids.clone() } else { vec![] },
+                    asks: vec![],
+                };
+                (seat.user, data)
+            })
+            .collect();
+        ParsedUpdate::Market(MarketUpdate {
+            address: MARKET,
+            slot,
+            view: MarketViewAll {
+                header: header(),
+                seats,
+                bids,
+                asks: vec![],
+                users,
+            },
+        })
+    }
+
+    fn market_order(seq: u64, base_filled: u64, quote_filled: u64) -> SequencedEvent {
+        SequencedEvent {
+            market: MARKET,
+            seq,
+            event: DropsetEvent::MarketOrder(DisplayMarketOrderData {
+                order_size: base_filled,
+                is_buy: true,
+                is_base: true,
+                base_filled,
+                quote_filled,
+            }),
+        }
+    }
+
+    fn channels(messages: &[ChannelMessage]) -> Vec<String> {
+        let mut channels = messages
+            .iter()
+            .map(|message| message.channel.clone())
+            .collect::<Vec<_>>();
+        channels.sort();
+        channels
+    }
+
+    #[test]
+    fn channel_round_trip() {
+        let address = Address::new_from_array([3; 32]);
+        for channel in [
+            Channel::Book(address),
+            Channel::Trades(address),
+            Channel::User(address),
+        ] {
+            assert_eq!(channel.to_string().parse::<Channel>().unwrap(), channel);
+        }
+        assert!("book".parse::<Channel>().is_err());
+        assert!("orders:11111111111111111111111111111111"
+            .parse::<Channel>()
+            .is_err());
+        assert!("book:not-an-address".parse::<Channel>().is_err());
+    }
+
+    #[test]
+    fn applies_book_and_seat_updates() {
+        let mut data = MarketData::new(10);
+        let messages = data.apply(
+            1,
+            None,
+            market_update(1, &[(0, ALICE), (1, BOB)], vec![bid(2, 0, 5)]),
+        );
+        let mut expected = [
+            Channel::Book(MARKET),
+            Channel::User(ALICE),
+            Channel::User(BOB),
+        ]
+        .map(|channel| channel.to_string())
+        .to_vec();
+        expected.sort();
+        assert_eq!(channels(&messages), expected);
+        assert!(messages.iter().all(|message| message.seq == 1));
+
+        let book = messages
+            .iter()
+            .find(|message| message.channel == Channel::Book(MARKET).to_string())
+            .unwrap();
+        assert_eq!(book.data["deltas"][0]["type"], "added");
+        assert_eq!(book.data["deltas"][0]["order"]["user"], ALICE.to_string());
+
+        let snapshot = data.snapshot(Channel::Book(MARKET));
+        assert_eq!(snapshot.seq, 1);
+        assert_eq!(snapshot.data["book"]["slot"], 1);
+        assert_eq!(snapshot.data["book"]["bid_orders"][0]["index"], 2);
+        assert_eq!(snapshot.data["book"]["bids"][0]["base_atoms"], 5);
+        assert_eq!(snapshot.data["deltas"], json!([]));
+
+        let alice = data.snapshot(Channel::User(ALICE)).data;
+        assert_eq!(alice[0]["market"], MARKET.to_string());
+        assert_eq!(alice[0]["seat"]["seat"]["base_available"], 10);
+        assert_eq!(alice[0]["seat"]["bids"][0]["index"], 2);
+    }
+
+    #[test]
+    fn publishes_only_changes_and_closed_seats() {
+        let mut data = MarketData::new(10);
+        data.apply(
+            1,
+            None,
+            market_update(1, &[(0, ALICE), (1, BOB)], vec![bid(2, 0, 5)]),
+        );
+
+        // Nothing changed, so nothing is published.
+        let messages = data.apply(
+            2,
+            None,
+            market_update(2, &[(0, ALICE), (1, BOB)], vec![bid(2, 0, 5)]),
+        );
+        assert!(messages.is_empty());
+
+        // Bob closes his seat and Alice's bid is partially filled.
+        let messages = data.apply(3, None, market_update(3, &[(0, ALICE)], vec![bid(2, 0, 3)]));
+        let mut expected = [
+            Channel::Book(MARKET),
+            Channel::User(ALICE),
+            Channel::User(BOB),
+        ]
+        .map(|channel| channel.to_string())
+        .to_vec();
+        expected.sort();
+        assert_eq!(channels(&messages), expected);
+
+        let bob = messages
+            .iter()
+            .find(|message| message.channel == Channel::User(BOB).to_string())
+            .unwrap();
+        assert_eq!(
+            bob.data,
+            json!({ "market": MARKET.to_string(), "seat": null })
+        );
+        assert_eq!(data.snapshot(Channel::User(BOB)).data, json!([]));
+        assert_eq!(data.seats(&ALICE).len(), 1);
+
+        let book = messages
+            .iter()
+            .find(|message| message.channel == Channel::Book(MARKET).to_string())
+            .unwrap();
+        assert_eq!(book.data["deltas"][0]["type"], "changed");
+        assert_eq!(book.data["book"]["bid_orders"][0]["base_remaining"], 3);
+        assert_eq!(data.snapshot(Channel::Book(MARKET)).seq, 3);
+    }
+
+    #[test]
+    fn applies_trades() {
+        let mut data = MarketData::new(2);
+        let events = vec![InstructionEventsWithIndices {
+            parent_index: 0,
+            inner_index: 0,
+            user: Some(ALICE),
+            events: vec![market_order(7, 10, 25), market_order(8, 0, 0)],
+        }];
+        let messages = data.apply(
+            5,
+            Some(100),
+            ParsedUpdate::EmittedEvents {
+                signature: Default::default(),
+                signer: ALICE,
+                logs: vec![],
+                events,
+            },
+        );
+
+        // Market orders that didn't fill aren't trades.
+        assert_eq!(channels(&messages), [Channel::Trades(MARKET).to_string()]);
+        assert_eq!(messages[0].data["seq"], 7);
+        assert_eq!(messages[0].data["timestamp"], 100);
+        assert!(data.is_known(&MARKET));
+        assert!(!data.is_known(&ALICE));
+
+        let snapshot = data.snapshot(Channel::Trades(MARKET));
+        assert_eq!(snapshot.seq, 1);
+        assert_eq!(snapshot.data[0]["base_atoms"], 10);
+        assert_eq!(snapshot.data[0]["quote_atoms"], 25);
+    }
+}
@@ -0,0 +1,87 @@
+//! REST handlers for the latest market data.
+
+use axum::{
+    extract::{
+        Path,
+        Query,
+        State,
+    },
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use solana_address::Address;
+use transaction_parser::market_data::trade::Trade;
+
+use crate::{
+    market_data::BookJson,
+    AppState,
+};
+
+/// The number of trades returned when the request doesn't specify a limit.
+const DEFAULT_TRADES_LIMIT: usize = 100;
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, String)>;
+
+fn parse_address(address: &str) -> Result<Address, (StatusCode, String)> {
+    address.parse().map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Invalid address: {address}"),
+        )
+    })
+}
+
+fn unknown_market(market: &Address) -> (StatusCode, String) {
+    (StatusCode::NOT_FOUND, format!("Unknown market: {market}"))
+}
+
+pub async fn markets(State(state): State<AppState>) -> Json<Vec<String>> {
+    Json(state.data.read().await.markets())
+}
+
+pub async fn book(
+    State(state): State<AppState>,
+    Path(market): Path<String>,
+) -> ApiResult<BookJson> {
+    let market = parse_address(&market)?;
+    state
+        .data
+        .read()
+        .await
+        .book(&market)
+        .map(Json)
+        .ok_or_else(|| unknown_market(&market))
+}
+
+#[derive(Deserialize)]
+pub struct TradesQuery {
+    limit: Option<usize>,
+}
+
+/// The market's most recent trades, newest first.
+pub async fn trades(
+    State(state): State<AppState>,
+    Path(market): Path<String>,
+    Query(query): Query<TradesQuery>,
+) -> ApiResult<Vec<Trade>> {
+    let market = parse_address(&market)?;
+    let data = state.data.read().await;
+    if !data.is_known(&market) {
+        return Err(unknown_market(&market));
+    }
+
+    Ok(Json(data.trades(
+        &market,
+        query.limit.unwrap_or(DEFAULT_TRADES_LIMIT),
+    )))
+}
+
+pub async fn seats(
+    State(state): State<AppState>,
+    Path(user): Path<String>,
+) -> ApiResult<Vec<Value>> {
+    let user = parse_address(&user)?;
+    Ok(Json(state.data.read().await.seats(&user)))
+}
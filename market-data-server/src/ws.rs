@@ -0,0 +1,114 @@
+//! The WebSocket endpoint clients subscribe to channels on.
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{
+        ws::{
+            Message,
+            WebSocket,
+        },
+        State,
+        WebSocketUpgrade,
+    },
+    response::Response,
+};
+use futures::{
+    SinkExt,
+    StreamExt,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    market_data::Channel,
+    AppState,
+};
+
+/// A request sent by a client, e.g. `{"op": "subscribe", "channel": "book:{market}"}`.
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum ClientRequest {
+    Subscribe { channel: String },
+    Unsubscribe { channel: String },
+}
+
+pub async fn handler(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
+    ws.on_upgrade(|socket| handle_socket(socket, state))
+}
+
+async fn handle_socket(socket: WebSocket, state: AppState) {
+    let (mut sink, mut stream) = socket.split();
+    let mut hub = state.hub.subscribe();
+    // Each subscribed channel and the `seq` of the snapshot sent for it.
+    let mut subscriptions = HashMap::<String, u64>::new();
+
+    loop {
+        let outgoing = tokio::select! {
+            incoming = stream.next() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    handle_request(&text, &state, &mut subscriptions).await
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered automatically and other messages are ignored.
+                Some(Ok(_)) => continue,
+            },
+            published = hub.recv() => match published {
+                // Messages buffered before the client subscribed are already reflected in the
+                // channel's snapshot.
+                Ok(message)
+                    if subscriptions
+                        .get(&message.channel)
+                        .is_some_and(|snapshot_seq| message.seq > *snapshot_seq) =>
+                {
+                    serde_json::to_value(&message).expect("Channel messages should serialize")
+                }
+                Ok(_) => continue,
+                // The client fell behind and missed updates, so it should resubscribe to get the
+                // current state.
+                Err(RecvError::Lagged(missed)) => json!({ "error": "lagged", "missed": missed }),
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        if sink
+            .send(Message::Text(outgoing.to_string().into()))
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+/// Handles a client request and returns the response to send back.
+async fn handle_request(
+    text: &str,
+    state: &AppState,
+    subscriptions: &mut HashMap<String, u64>,
+) -> serde_json::Value {
+    let request = match serde_json::from_str::<ClientRequest>(text) {
+        Ok(request) => request,
+        Err(e) => return json!({ "error": format!("Invalid request: {e}") }),
+    };
+
+    match request {
+        ClientRequest::Subscribe { channel } => match channel.parse::<Channel>() {
+            Ok(parsed) => {
+                // Published messages use the canonical channel name, which the snapshot has too.
+                let snapshot = state.data.read().await.snapshot(parsed);
+                subscriptions.insert(snapshot.channel.clone(), snapshot.seq);
+                serde_json::to_value(&snapshot).expect("Channel messages should serialize")
+            }
+            Err(e) => json!({ "error": e.to_string() }),
+        },
+        ClientRequest::Unsubscribe { channel } => {
+            let channel = channel
+                .parse::<Channel>()
+                .map_or(channel, |parsed| parsed.to_string());
+            subscriptions.remove(&channel);
+            json!({ "unsubscribed": channel })
+        }
+    }
+}
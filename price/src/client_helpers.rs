@@ -89,6 +89,25 @@ pub fn decimal_pow10_i16(value: Decimal, pow: i16) -> Decimal {
         .normalize()
 }
 
+/// Converts a price in atoms, i.e. quote atoms per base atom, to a price in whole tokens, i.e.
+/// quote tokens per base token.
+pub fn atoms_to_token_price(
+    atoms_price: Decimal,
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> Decimal {
+    decimal_pow10_i16(atoms_price, base_decimals as i16 - quote_decimals as i16)
+}
+
+/// Converts a price in whole tokens to a price in atoms. The inverse of [`atoms_to_token_price`].
+pub fn token_to_atoms_price(
+    token_price: Decimal,
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> Decimal {
+    decimal_pow10_i16(token_price, quote_decimals as i16 - base_decimals as i16)
+}
+
 /// Converts a u32 encoded price to a decoded decimal price. Typical usage would be converting the
 /// on-chain u32 in an order to the decoded decimal price.
 pub fn try_encoded_u32_to_decoded_decimal(encoded_u32: u32) -> Result<Decimal, OrderInfoError> {
@@ -99,6 +118,22 @@ pub fn try_encoded_u32_to_decoded_decimal(encoded_u32: u32) -> Result<Decimal, O
     Ok(decimal_price)
 }
 
+/// Decodes a u32 encoded price in atoms to a price in whole tokens, i.e. quote tokens per base
+/// token.
+pub fn try_encoded_u32_to_token_price(
+    encoded_u32: u32,
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> Result<Decimal, OrderInfoError> {
+    let atoms_price = try_encoded_u32_to_decoded_decimal(encoded_u32)?;
+
+    Ok(atoms_to_token_price(
+        atoms_price,
+        base_decimals,
+        quote_decimals,
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -164,4 +199,13 @@ mod tests {
         assert_eq!(decimal_pow10_i16(dec!(1.23), -2), dec!(0.0123));
         assert_eq!(decimal_pow10_i16(dec!(0.05123), -9), dec!(0.00000000005123));
     }
+
+    #[test]
+    fn test_atoms_token_price_conversion() {
+        // 1 base token with 9 decimals for 150 quote tokens with 6 decimals.
+        let atoms_price = dec!(0.15);
+        assert_eq!(atoms_to_token_price(atoms_price, 9, 6), dec!(150));
+        assert_eq!(token_to_atoms_price(dec!(150), 9, 6), atoms_price);
+        assert_eq!(atoms_to_token_price(dec!(2.5), 6, 6), dec!(2.5));
+    }
 }
@@ -26,6 +26,8 @@ pub struct CloseSeatContext<'a> {
     pub quote_market_ata: TokenAccountView<'a>,
     pub base_mint: MintAccountView<'a>,
     pub quote_mint: MintAccountView<'a>,
+    /// Trailing accounts forwarded to `spl_token_2022` transfers, e.g. for transfer hooks.
+    pub remaining_accounts: &'a [AccountView],
 }
 
 impl<'a> CloseSeatContext<'a> {
@@ -34,6 +36,7 @@ impl<'a> CloseSeatContext<'a> {
     /// Caller guarantees no accounts passed have their data borrowed in any capacity. This is a
     /// more restrictive safety contract than is necessary for soundness but is much simpler.
     pub unsafe fn load(accounts: &'a [AccountView]) -> Result<CloseSeatContext<'a>, ProgramError> {
+        let (close_seat, remaining_accounts) = CloseSeat::load_accounts_with_remaining(accounts)?;
         let CloseSeat {
             event_authority,
             user,
@@ -47,7 +50,7 @@ impl<'a> CloseSeatContext<'a> {
             base_token_program: _,
             quote_token_program: _,
             dropset_program: _,
-        } = CloseSeat::load_accounts(accounts)?;
+        } = close_seat;
 
         // Safety: Scoped borrow of market account data.
         let (market_account, base_mint, quote_mint) = unsafe {
@@ -85,6 +88,7 @@ impl<'a> CloseSeatContext<'a> {
             quote_market_ata,
             base_mint,
             quote_mint,
+            remaining_accounts,
         })
     }
 }
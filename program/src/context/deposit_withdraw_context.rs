@@ -24,6 +24,8 @@ pub struct DepositWithdrawContext<'a> {
     pub user_ata: TokenAccountView<'a>,
     pub market_ata: TokenAccountView<'a>,
     pub mint: MintAccountView<'a>,
+    /// Trailing accounts forwarded to `spl_token_2022` transfers, e.g. for transfer hooks.
+    pub remaining_accounts: &'a [AccountView],
 }
 
 impl<'a> DepositWithdrawContext<'a> {
@@ -35,7 +37,9 @@ impl<'a> DepositWithdrawContext<'a> {
         accounts: &'a [AccountView],
     ) -> Result<DepositWithdrawContext<'a>, ProgramError> {
         // `Withdraw`'s account info fields are in the same exact order as `Deposit`'s, so just use
-        // `Deposit::load_accounts` for both. This invariant is checked below in unit tests.
+        // `Deposit::load_accounts_with_remaining` for both. This invariant is checked below in unit
+        // tests.
+        let (deposit, remaining_accounts) = Deposit::load_accounts_with_remaining(accounts)?;
         let Deposit {
             event_authority,
             user,
@@ -45,7 +49,7 @@ impl<'a> DepositWithdrawContext<'a> {
             mint,
             token_program: _,
             dropset_program: _,
-        } = Deposit::load_accounts(accounts)?;
+        } = deposit;
 
         // Safety: Scoped borrow of market account data.
         let (market_account, mint) = unsafe {
@@ -73,6 +77,7 @@ impl<'a> DepositWithdrawContext<'a> {
             user_ata,
             market_ata,
             mint,
+            remaining_accounts,
         })
     }
 }
@@ -176,4 +181,28 @@ pub(crate) mod tests {
         assert_address_eq(dep_token_program, wd_token_program);
         assert_address_eq(dep_dropset_program, wd_dropset_program);
     }
+
+    #[test]
+    fn trailing_accounts_are_returned_as_remaining() {
+        let mut runtime_accounts: [RuntimeAccount; 10] = core::array::from_fn(|i| {
+            create_zeroed_mock_runtime_account(Address::new_from_array([i as u8; 32]))
+        });
+
+        let accounts_ptr: *mut RuntimeAccount = runtime_accounts.as_mut_ptr();
+        let account_views: [AccountView; 10] =
+            core::array::from_fn(|i| unsafe { AccountView::new_unchecked(accounts_ptr.add(i)) });
+
+        // The exact loader rejects trailing accounts.
+        assert!(Deposit::load_accounts(&account_views).is_err());
+
+        let (deposit, remaining_accounts) =
+            Deposit::load_accounts_with_remaining(&account_views).unwrap();
+        assert_address_eq(deposit.dropset_program, &account_views[7]);
+        assert_eq!(remaining_accounts.len(), 2);
+        assert_address_eq(&remaining_accounts[0], &account_views[8]);
+        assert_address_eq(&remaining_accounts[1], &account_views[9]);
+
+        // Too few accounts still fails.
+        assert!(Deposit::load_accounts_with_remaining(&account_views[..7]).is_err());
+    }
 }
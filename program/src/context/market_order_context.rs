@@ -25,6 +25,8 @@ pub struct MarketOrderContext<'a> {
     pub quote_market_ata: TokenAccountView<'a>,
     pub base_mint: MintAccountView<'a>,
     pub quote_mint: MintAccountView<'a>,
+    /// Trailing accounts forwarded to `spl_token_2022` transfers, e.g. for transfer hooks.
+    pub remaining_accounts: &'a [AccountView],
 }
 
 impl<'a> MarketOrderContext<'a> {
@@ -35,6 +37,8 @@ impl<'a> MarketOrderContext<'a> {
     pub unsafe fn load(
         accounts: &'a [AccountView],
     ) -> Result<MarketOrderContext<'a>, ProgramError> {
+        let (market_order, remaining_accounts) =
+            MarketOrder::load_accounts_with_remaining(accounts)?;
         let MarketOrder {
             event_authority,
             user,
@@ -48,7 +52,7 @@ impl<'a> MarketOrderContext<'a> {
             base_token_program: _,
             quote_token_program: _,
             dropset_program: _,
-        } = MarketOrder::load_accounts(accounts)?;
+        } = market_order;
 
         // Safety: Scoped borrow of market account data.
         let (market_account, base_mint, quote_mint) = unsafe {
@@ -96,6 +100,7 @@ impl<'a> MarketOrderContext<'a> {
             quote_market_ata,
             base_mint,
             quote_mint,
+            remaining_accounts,
         })
     }
 }
@@ -0,0 +1,45 @@
+//! See [`MigrateMarketContext`].
+
+use dropset_interface::instructions::generated_program::MigrateMarket;
+use pinocchio::{
+    account::AccountView,
+    error::ProgramError,
+};
+
+use crate::validation::market_account_view::MarketAccountView;
+
+/// The account context for the [`MigrateMarket`] instruction, validating the market account passed
+/// in regardless of its layout version.
+#[derive(Clone)]
+pub struct MigrateMarketContext<'a> {
+    pub payer: &'a AccountView,
+    pub market_account: MarketAccountView<'a>,
+    /// The market account's layout version prior to migrating.
+    pub layout_version: u8,
+}
+
+impl<'a> MigrateMarketContext<'a> {
+    /// # Safety
+    ///
+    /// Caller guarantees no accounts passed have their data borrowed in any capacity.
+    pub unsafe fn load(
+        accounts: &'a [AccountView],
+    ) -> Result<MigrateMarketContext<'a>, ProgramError> {
+        // The system program is validated by the transfer CPI if the migration requires funding.
+        let MigrateMarket {
+            payer,
+            market_account,
+            system_program: _,
+        } = MigrateMarket::load_accounts(accounts)?;
+
+        // Safety: Scoped borrow of market account data.
+        let (market_account, layout_version) =
+            unsafe { MarketAccountView::new_any_version(market_account) }?;
+
+        Ok(Self {
+            payer,
+            market_account,
+            layout_version,
+        })
+    }
+}
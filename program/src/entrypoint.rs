@@ -73,6 +73,9 @@ pub fn process_instruction(
             DropsetInstruction::BatchReplace => {
                 return process_batch_replace(accounts, instruction_data)
             }
+            DropsetInstruction::MigrateMarket => {
+                return process_migrate_market(accounts, instruction_data)
+            }
         }
     }?;
 
@@ -1,8 +1,7 @@
 //! See [`process_cancel_order`].
 
-#[cfg(feature = "debug")]
-use dropset_interface::events::CancelOrderEventInstructionData;
 use dropset_interface::{
+    events::CancelOrderEventInstructionData,
     instructions::CancelOrderInstructionData,
     state::{
         market_seat::MarketSeat,
@@ -39,7 +38,7 @@ use crate::{
 pub unsafe fn process_cancel_order<'a>(
     accounts: &'a [AccountView],
     instruction_data: &[u8],
-    _event_buffer: &mut EventBuffer,
+    event_buffer: &mut EventBuffer,
 ) -> Result<EventBufferContext<'a>, ProgramError> {
     let CancelOrderInstructionData {
         encoded_price,
@@ -73,18 +72,19 @@ pub unsafe fn process_cancel_order<'a>(
     // The safety comment below explains why this isn't explicitly necessary.
     debug_assert!(Sector::check_in_bounds(market.sectors, order_sector_index).is_ok());
 
-    // Load the order given the order sector index.
-    let order = {
+    // Load the order's remaining amounts given the order sector index.
+    let (base_remaining, quote_remaining) = {
         // Safety: The order sector index returned from the `remove` method still points to a
         // sector with a valid order. All order sector indices in a user seat are thus in-bounds and
         // don't need to be explicitly verified as in-bounds.
-        load_order_from_sector_index(&market, order_sector_index)
+        let order = load_order_from_sector_index(&market, order_sector_index);
+        (order.base_remaining(), order.quote_remaining())
     };
 
     // Increment the user's collateral in their market seat by the amount remaining in the order.
     if is_bid {
         // If the user placed a bid, they provided quote as collateral.
-        let order_size_remaining = order.quote_remaining();
+        let order_size_remaining = quote_remaining;
         // Safety: The seat hint was already validated as in-bounds. It could only possibly be out
         // of bounds now if the account data size was just reduced, which it was not.
         let sector =
@@ -93,7 +93,7 @@ pub unsafe fn process_cancel_order<'a>(
         user_seat.try_increment_quote_available(order_size_remaining)?;
     } else {
         // If the user placed an ask, they provided base as collateral.
-        let order_size_remaining = order.base_remaining();
+        let order_size_remaining = base_remaining;
         // Safety: The seat hint was already validated as in-bounds. It could only possibly be out
         // of bounds now if the account data size was just reduced, which it was not.
         let sector =
@@ -112,9 +112,16 @@ pub unsafe fn process_cancel_order<'a>(
         }
     }
 
-    #[cfg(feature = "debug")]
-    _event_buffer.add_to_buffer(
-        CancelOrderEventInstructionData::new(is_bid, user_sector_index_hint),
+    event_buffer.add_to_buffer(
+        CancelOrderEventInstructionData::new(
+            *ctx.user.address(),
+            is_bid,
+            encoded_price,
+            user_sector_index_hint,
+            order_sector_index,
+            base_remaining,
+            quote_remaining,
+        ),
         ctx.event_authority,
         ctx.market_account.clone(),
     )?;
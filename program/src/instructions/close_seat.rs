@@ -18,7 +18,10 @@ use crate::{
     },
     events::EventBuffer,
     market_signer,
-    shared::seat_operations::find_seat_with_hint,
+    shared::{
+        seat_operations::find_seat_with_hint,
+        token_utils::transfer_checked::TransferCheckedWithHook,
+    },
 };
 
 /// Instruction handler logic for closing an existing market seat and reclaiming associated funds.
@@ -81,14 +84,14 @@ pub unsafe fn process_close_seat<'a>(
         } else {
             // Safety: Scoped immutable borrow of mint account data to get mint decimals.
             let decimals = unsafe { ctx.base_mint.get_mint_decimals() }?;
-            pinocchio_token_2022::instructions::TransferChecked {
+            TransferCheckedWithHook {
                 from: ctx.base_market_ata.account,       // WRITE
                 to: ctx.base_user_ata.account,           // WRITE
                 authority: ctx.market_account.account(), // READ
                 mint: ctx.base_mint.account,             // READ
+                remaining_accounts: ctx.remaining_accounts,
                 amount: base_available,
                 decimals,
-                token_program: &pinocchio_token_2022::ID,
             }
             .invoke_signed(&[market_signer!(
                 ctx.base_mint.account.address(),
@@ -115,14 +118,14 @@ pub unsafe fn process_close_seat<'a>(
         } else {
             // Safety: Scoped immutable borrow of mint account data to get mint decimals.
             let decimals = unsafe { ctx.quote_mint.get_mint_decimals() }?;
-            pinocchio_token_2022::instructions::TransferChecked {
+            TransferCheckedWithHook {
                 from: ctx.quote_market_ata.account,      // WRITE
                 to: ctx.quote_user_ata.account,          // WRITE
                 authority: ctx.market_account.account(), // READ
                 mint: ctx.quote_mint.account,            // READ
+                remaining_accounts: ctx.remaining_accounts,
                 amount: quote_available,
                 decimals,
-                token_program: &pinocchio_token_2022::ID,
             }
             .invoke_signed(&[market_signer!(
                 ctx.base_mint.account.address(),
@@ -64,7 +64,14 @@ pub unsafe fn process_deposit<'a>(
 
     // Safety: No account data is currently borrowed.
     let amount_deposited = unsafe {
-        deposit_non_zero_to_market(&ctx.user_ata, &ctx.market_ata, ctx.user, &ctx.mint, amount)
+        deposit_non_zero_to_market(
+            &ctx.user_ata,
+            &ctx.market_ata,
+            ctx.user,
+            &ctx.mint,
+            ctx.remaining_accounts,
+            amount,
+        )
     }?;
 
     // 1) Update an existing seat.
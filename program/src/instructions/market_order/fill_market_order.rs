@@ -5,6 +5,7 @@ use dropset_interface::{
         DropsetError,
         DropsetResult,
     },
+    events::MakerFillEventInstructionData,
     state::{
         asks_dll::AskOrders,
         bids_dll::BidOrders,
@@ -16,13 +17,18 @@ use dropset_interface::{
             NIL,
         },
     },
+    utils::mul_div_checked,
+};
+use pinocchio::{
+    error::ProgramError,
+    hint,
+    ProgramResult,
 };
-use pinocchio::hint;
 use price::EncodedPrice;
 
 use crate::{
     context::market_order_context::MarketOrderContext,
-    instructions::market_order::mul_div_checked,
+    events::EventBuffer,
     shared::order_operations::{
         load_mut_order_from_sector_index,
         load_order_from_sector_index,
@@ -72,14 +78,17 @@ pub struct AmountsFilled {
 /// This function returns the amounts filled denominated in both base and quote. The ratio of these
 /// two values is effectively the average fill price.
 ///
+/// A [`MakerFillEventInstructionData`] is added to the event buffer for each maker order filled.
+///
 /// # Safety
 ///
 /// The market account data must not be currently borrowed.
 #[inline(always)]
 pub unsafe fn fill_market_order<const IS_BUY: bool, const BASE_DENOM: bool>(
     ctx: &'_ mut MarketOrderContext<'_>,
+    event_buffer: &mut EventBuffer,
     order_size: u64,
-) -> Result<AmountsFilled, DropsetError> {
+) -> Result<AmountsFilled, ProgramError> {
     // All amounts in this function are in atoms.
     let mut constraint_asset_remaining = order_size;
     let mut counter_asset_filled: u64 = 0;
@@ -110,6 +119,7 @@ pub unsafe fn fill_market_order<const IS_BUY: bool, const BASE_DENOM: bool>(
                     // remaining.
                     full_fill::<IS_BUY, BASE_DENOM>(
                         ctx,
+                        event_buffer,
                         &mut constraint_asset_remaining,
                         &mut counter_asset_filled,
                         &top_order,
@@ -124,6 +134,7 @@ pub unsafe fn fill_market_order<const IS_BUY: bool, const BASE_DENOM: bool>(
                     // completely filled and must be mutated to reflect the new amounts remaining.
                     partial_fill::<IS_BUY, BASE_DENOM>(
                         ctx,
+                        event_buffer,
                         &mut constraint_asset_remaining,
                         &mut counter_asset_filled,
                         &top_order,
@@ -183,7 +194,8 @@ fn top_of_book_snapshot<const IS_BUY: bool>(ctx: &'_ MarketOrderContext) -> Opti
 /// 1. Remove the order from the orders collection.
 /// 2. Update the filled maker seat's balance and remove the order from the maker seat's price to
 ///    order map.
-/// 3. Update the constraint asset remaining and the counter asset filled.
+/// 3. Emit the maker fill event.
+/// 4. Update the constraint asset remaining and the counter asset filled.
 ///
 /// # Safety
 ///
@@ -194,10 +206,11 @@ fn top_of_book_snapshot<const IS_BUY: bool>(ctx: &'_ MarketOrderContext) -> Opti
 #[inline(always)]
 unsafe fn full_fill<const IS_BUY: bool, const BASE_DENOM: bool>(
     ctx: &'_ mut MarketOrderContext<'_>,
+    event_buffer: &mut EventBuffer,
     constraint_asset_remaining: &mut u64,
     counter_asset_filled: &mut u64,
     top_order: &OrderSnapshot,
-) -> DropsetResult {
+) -> ProgramResult {
     // 1. Close/remove the order from the orders collection.
     if IS_BUY {
         ctx.market_account
@@ -226,7 +239,19 @@ unsafe fn full_fill<const IS_BUY: bool, const BASE_DENOM: bool>(
         )
     }?;
 
-    // 3. Update the constrained amount not filled yet and the counter asset total filled.
+    // 3. Emit the maker fill event. The whole order was filled, so its amounts remaining are the
+    // amounts filled.
+    event_buffer.add_to_buffer(
+        MakerFillEventInstructionData::new(
+            top_order.order_sector,
+            top_order.base_remaining,
+            top_order.quote_remaining,
+        ),
+        ctx.event_authority,
+        ctx.market_account.clone(),
+    )?;
+
+    // 4. Update the constrained amount not filled yet and the counter asset total filled.
     // Safety: The amount of constraint asset remaining must be >= the denominated constrained
     // amount in the top order or this would not be a full fill.
     *constraint_asset_remaining = constraint_asset_remaining
@@ -242,10 +267,11 @@ unsafe fn full_fill<const IS_BUY: bool, const BASE_DENOM: bool>(
 #[inline(always)]
 fn partial_fill<const IS_BUY: bool, const BASE_DENOM: bool>(
     ctx: &'_ mut MarketOrderContext<'_>,
+    event_buffer: &mut EventBuffer,
     constraint_asset_remaining: &mut u64,
     counter_asset_filled: &mut u64,
     top_order: &OrderSnapshot,
-) -> DropsetResult {
+) -> ProgramResult {
     let remaining_constrained_asset_in_top_order =
         dropset_non_zero_u64(top_order.get_constrained_remaining::<BASE_DENOM>())?;
     let remaining_counter_asset_in_top_order =
@@ -306,6 +332,13 @@ fn partial_fill<const IS_BUY: bool, const BASE_DENOM: bool>(
         )
     }?;
 
+    // Emit the maker fill event with the partial amounts filled.
+    event_buffer.add_to_buffer(
+        MakerFillEventInstructionData::new(top_order.order_sector, base_filled, quote_filled),
+        ctx.event_authority,
+        ctx.market_account.clone(),
+    )?;
+
     Ok(())
 }
 
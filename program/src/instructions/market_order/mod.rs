@@ -1,7 +1,6 @@
 //! See [`process_market_order`].
 
 mod fill_market_order;
-mod mul_div_checked;
 
 // #[cfg(feature = "debug")]
 use dropset_interface::{
@@ -9,7 +8,6 @@ use dropset_interface::{
     events::MarketOrderEventInstructionData,
     instructions::MarketOrderInstructionData,
 };
-use mul_div_checked::mul_div_checked;
 use pinocchio::{
     account::AccountView,
     error::ProgramError,
@@ -41,7 +39,7 @@ use crate::{
 pub unsafe fn process_market_order<'a>(
     accounts: &'a [AccountView],
     instruction_data: &[u8],
-    _event_buffer: &mut EventBuffer,
+    event_buffer: &mut EventBuffer,
 ) -> Result<EventBufferContext<'a>, ProgramError> {
     let MarketOrderInstructionData {
         order_size,
@@ -56,10 +54,10 @@ pub unsafe fn process_market_order<'a>(
         base: base_filled,
         quote: quote_filled,
     } = match (is_buy, is_base) {
-        (false, false) => fill_market_order::<false, false>(&mut ctx, order_size),
-        (true, false) => fill_market_order::<true, false>(&mut ctx, order_size),
-        (false, true) => fill_market_order::<false, true>(&mut ctx, order_size),
-        (true, true) => fill_market_order::<true, true>(&mut ctx, order_size),
+        (false, false) => fill_market_order::<false, false>(&mut ctx, event_buffer, order_size),
+        (true, false) => fill_market_order::<true, false>(&mut ctx, event_buffer, order_size),
+        (false, true) => fill_market_order::<false, true>(&mut ctx, event_buffer, order_size),
+        (true, true) => fill_market_order::<true, true>(&mut ctx, event_buffer, order_size),
     }?;
 
     // Try to transfer the taker side's tokens to the market account.
@@ -72,6 +70,7 @@ pub unsafe fn process_market_order<'a>(
                 &ctx.quote_market_ata,
                 ctx.user,
                 &ctx.quote_mint,
+                ctx.remaining_accounts,
                 quote_filled,
             )?;
 
@@ -81,6 +80,7 @@ pub unsafe fn process_market_order<'a>(
                 &ctx.base_market_ata,
                 &ctx.market_account,
                 &ctx.base_mint,
+                ctx.remaining_accounts,
                 base_filled,
             )?;
 
@@ -92,6 +92,7 @@ pub unsafe fn process_market_order<'a>(
                 &ctx.base_market_ata,
                 ctx.user,
                 &ctx.base_mint,
+                ctx.remaining_accounts,
                 base_filled,
             )?;
 
@@ -101,6 +102,7 @@ pub unsafe fn process_market_order<'a>(
                 &ctx.quote_market_ata,
                 &ctx.market_account,
                 &ctx.quote_mint,
+                ctx.remaining_accounts,
                 quote_filled,
             )?;
 
@@ -114,7 +116,7 @@ pub unsafe fn process_market_order<'a>(
     }
 
     // #[cfg(feature = "debug")]
-    _event_buffer.add_to_buffer(
+    event_buffer.add_to_buffer(
         MarketOrderEventInstructionData::new(
             order_size,
             is_buy,
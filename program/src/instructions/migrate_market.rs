@@ -0,0 +1,30 @@
+//! See [`process_migrate_market`].
+
+use pinocchio::{
+    account::AccountView,
+    ProgramResult,
+};
+
+use crate::{
+    context::migrate_market_context::MigrateMarketContext,
+    shared::market_migration::migrate_market,
+};
+
+/// Instruction handler logic for upgrading a market account from an older layout version to the
+/// current one, resizing the account if the new layout requires it.
+///
+/// # Safety
+///
+/// Caller upholds the safety contract detailed in
+/// [`dropset_interface::instructions::generated_program::MigrateMarket`].
+#[inline(never)]
+pub unsafe fn process_migrate_market(
+    accounts: &[AccountView],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    // Safety: No account data in `accounts` is currently borrowed.
+    let mut ctx = unsafe { MigrateMarketContext::load(accounts) }?;
+
+    // Safety: No account data in `accounts` is currently borrowed.
+    unsafe { migrate_market(ctx.payer, &mut ctx.market_account, ctx.layout_version) }
+}
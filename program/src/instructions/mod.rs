@@ -9,6 +9,7 @@ pub mod close_seat;
 pub mod deposit;
 pub mod flush_events;
 pub mod market_order;
+pub mod migrate_market;
 pub mod post_order;
 pub mod register_market;
 pub mod withdraw;
@@ -19,6 +20,7 @@ pub use close_seat::process_close_seat;
 pub use deposit::process_deposit;
 pub use flush_events::process_flush_events;
 pub use market_order::process_market_order;
+pub use migrate_market::process_migrate_market;
 pub use post_order::process_post_order;
 pub use register_market::process_register_market;
 pub use withdraw::process_withdraw;
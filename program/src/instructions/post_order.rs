@@ -1,9 +1,8 @@
 //! See [`process_post_order`].
 
-#[cfg(feature = "debug")]
-use dropset_interface::events::PostOrderEventInstructionData;
 use dropset_interface::{
     error::DropsetError,
+    events::PostOrderEventInstructionData,
     instructions::PostOrderInstructionData,
     state::{
         asks_dll::AskOrders,
@@ -43,7 +42,7 @@ use crate::{
 pub unsafe fn process_post_order<'a>(
     accounts: &'a [AccountView],
     instruction_data: &[u8],
-    _event_buffer: &mut EventBuffer,
+    event_buffer: &mut EventBuffer,
 ) -> Result<EventBufferContext<'a>, ProgramError> {
     let PostOrderInstructionData {
         order_info_args,
@@ -106,10 +105,11 @@ pub unsafe fn process_post_order<'a>(
             .add(&le_encoded_price, &order_sector_index_bytes)?;
     }
 
-    #[cfg(feature = "debug")]
-    _event_buffer.add_to_buffer(
+    event_buffer.add_to_buffer(
         PostOrderEventInstructionData::new(
+            *ctx.user.address(),
             is_bid,
+            u32::from_le_bytes(le_encoded_price),
             user_sector_index_hint,
             order_sector_index,
             base_atoms,
@@ -48,6 +48,7 @@ pub unsafe fn process_withdraw<'a>(
             &ctx.market_ata,
             &ctx.market_account,
             &ctx.mint,
+            ctx.remaining_accounts,
             amount,
         )
     }?;
@@ -0,0 +1,161 @@
+//! The migration path for upgrading market accounts from older layouts to the current one.
+//!
+//! Each [`MigrationStep`] upgrades a market account by exactly one layout version. A step may grow
+//! the account (e.g. for new header fields or larger sectors), in which case the account is funded
+//! and resized before the step rewrites its data. Steps are applied in order until the market
+//! reaches [`CURRENT_MARKET_LAYOUT_VERSION`].
+
+use dropset_interface::{
+    error::DropsetError,
+    state::{
+        market::Market,
+        market_header::{
+            CURRENT_MARKET_LAYOUT_VERSION,
+            UNVERSIONED_MARKET_LAYOUT_VERSION,
+        },
+    },
+};
+use pinocchio::{
+    account::AccountView,
+    hint::unlikely,
+    ProgramResult,
+};
+use static_assertions::const_assert_eq;
+
+use crate::{
+    shared::account_resize::fund_then_resize_unchecked,
+    validation::market_account_view::MarketAccountView,
+};
+
+/// A single step in the market layout migration path.
+pub struct MigrationStep {
+    /// The layout version this step upgrades from. The step upgrades to `from_version + 1`.
+    pub from_version: u8,
+    /// Returns the account data length required after the step, given the length before it.
+    /// This must never be less than the length before it.
+    pub new_data_len: fn(usize) -> usize,
+    /// Rewrites the market account data into the next layout. The data has already been resized
+    /// to `new_data_len`, with any additional bytes zeroed at the end of the account.
+    pub rewrite: fn(&mut [u8]),
+}
+
+/// All migration steps, where the step at index `i` upgrades from layout version `i`.
+pub const MIGRATION_STEPS: [MigrationStep; CURRENT_MARKET_LAYOUT_VERSION as usize] = [
+    // Unversioned markets have the same byte layout as version 1 with a zeroed version byte.
+    MigrationStep {
+        from_version: UNVERSIONED_MARKET_LAYOUT_VERSION,
+        new_data_len: |len| len,
+        rewrite: |data| {
+            // Safety: The market account data is at least `MarketHeader::LEN` bytes.
+            let market = unsafe { Market::from_bytes_mut(data) };
+            market
+                .header
+                .set_layout_version(UNVERSIONED_MARKET_LAYOUT_VERSION + 1);
+        },
+    },
+];
+
+const_assert_eq!(
+    MIGRATION_STEPS.len(),
+    CURRENT_MARKET_LAYOUT_VERSION as usize
+);
+
+/// Migrates the market account from `layout_version` to [`CURRENT_MARKET_LAYOUT_VERSION`] by
+/// applying each migration step in order. This is a no-op if the market is already current.
+///
+/// # Safety
+///
+/// Caller guarantees:
+/// - WRITE accounts are not currently borrowed in *any* capacity.
+/// - READ accounts are not currently mutably borrowed.
+/// - `layout_version` is the market account's current layout version.
+///
+/// ### Accounts
+///   0. `[WRITE]` Payer
+///   1. `[WRITE]` Market account
+pub unsafe fn migrate_market(
+    payer: &AccountView,
+    market_account: &mut MarketAccountView,
+    layout_version: u8,
+) -> ProgramResult {
+    if unlikely(layout_version > CURRENT_MARKET_LAYOUT_VERSION) {
+        return Err(DropsetError::UnsupportedMarketLayout.into());
+    }
+
+    for step in &MIGRATION_STEPS[layout_version as usize..] {
+        let account = market_account.account();
+        let curr_len = account.data_len();
+        let new_len = (step.new_data_len)(curr_len);
+        debug_assert!(new_len >= curr_len);
+
+        if new_len > curr_len {
+            // Safety: Scoped writes to payer and market account to resize the market account.
+            unsafe { fund_then_resize_unchecked(payer, account, new_len - curr_len) }?;
+        }
+
+        // Safety: Scoped mutable borrow of the market account data.
+        (step.rewrite)(unsafe { account.borrow_unchecked_mut() });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use std::vec;
+
+    use dropset_interface::state::{
+        market_header::MarketHeader,
+        sector::{
+            NIL,
+            SECTOR_SIZE,
+        },
+        transmutable::Transmutable,
+    };
+    use solana_address::Address;
+
+    use super::*;
+    use crate::shared::market_operations::initialize_market_account_data;
+
+    #[test]
+    fn steps_are_ordered_by_version() {
+        for (i, step) in MIGRATION_STEPS.iter().enumerate() {
+            assert_eq!(step.from_version as usize, i);
+        }
+    }
+
+    #[test]
+    fn migrate_unversioned_market() {
+        const N_SECTORS: usize = 4;
+        let mut data = vec![0u8; MarketHeader::LEN + SECTOR_SIZE * N_SECTORS];
+        initialize_market_account_data(
+            &mut data,
+            &Address::new_from_array([1; 32]),
+            &Address::new_from_array([2; 32]),
+            254,
+        )
+        .unwrap();
+
+        // Simulate a market created before the header stored a layout version.
+        let expected = data.clone();
+        // Safety: `data` was just initialized as a market account.
+        unsafe { Market::from_bytes_mut(&mut data) }
+            .header
+            .set_layout_version(UNVERSIONED_MARKET_LAYOUT_VERSION);
+        assert_ne!(data, expected);
+
+        for step in MIGRATION_STEPS.iter() {
+            data.resize((step.new_data_len)(data.len()), 0);
+            (step.rewrite)(&mut data);
+        }
+
+        // The migrated market should be identical to a market created with the current layout.
+        assert_eq!(data, expected);
+        // Safety: `data` is still a valid market account.
+        let market = unsafe { Market::from_bytes(&data) };
+        assert_eq!(market.header.verify_layout_version(), Ok(()));
+        assert_eq!(market.header.num_free_sectors(), N_SECTORS as u32);
+        assert_ne!(market.header.free_stack_top(), NIL);
+    }
+}
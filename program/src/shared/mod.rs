@@ -1,6 +1,7 @@
 //! Shared utilities and helpers for `dropset` program logic.
 
 pub mod account_resize;
+pub mod market_migration;
 pub mod market_operations;
 pub mod order_operations;
 pub mod seat_operations;
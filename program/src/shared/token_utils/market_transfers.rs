@@ -13,6 +13,7 @@ use pinocchio::{
 
 use crate::{
     market_signer,
+    shared::token_utils::transfer_checked::TransferCheckedWithHook,
     validation::{
         market_account_view::MarketAccountView,
         mint_account_view::MintAccountView,
@@ -36,11 +37,13 @@ use crate::{
 ///   1. `[WRITE]` Market token account (destination)
 ///   2. `[READ]` User account (authority)
 ///   3. `[READ]` Mint account
+///   4. .. Transfer hook accounts, only forwarded for `spl_token_2022` mints
 pub unsafe fn deposit_non_zero_to_market<'a, 't>(
     user_ata: &'t TokenAccountView<'a>,
     market_ata: &'t TokenAccountView<'a>,
     user: &'a AccountView,
     mint: &'t MintAccountView<'a>,
+    transfer_hook_accounts: &'a [AccountView],
     amount: u64,
 ) -> Result<u64, ProgramError> {
     let amount_deposited = if is_owned_by_spl_token(mint.account) {
@@ -61,14 +64,14 @@ pub unsafe fn deposit_non_zero_to_market<'a, 't>(
         // Safety: Scoped immutable borrow of the market token account data to get its balance.
         let balance_before = unsafe { market_ata.get_balance() }?;
 
-        pinocchio_token_2022::instructions::TransferChecked {
+        TransferCheckedWithHook {
             from: user_ata.account, // WRITE
             to: market_ata.account, // WRITE
             mint: mint.account,     // READ
             authority: user,        // READ
+            remaining_accounts: transfer_hook_accounts,
             decimals,
             amount,
-            token_program: &pinocchio_token_2022::ID,
         }
         .invoke()?;
 
@@ -105,11 +108,13 @@ pub unsafe fn deposit_non_zero_to_market<'a, 't>(
 ///   1. `[WRITE]` Market token account (source)
 ///   2. `[READ]`  Market account (authority)
 ///   3. `[READ]`  Mint account
+///   4. .. Transfer hook accounts, only forwarded for `spl_token_2022` mints
 pub unsafe fn withdraw_non_zero_from_market<'t, 'a>(
     user_ata: &'t TokenAccountView<'a>,
     market_ata: &'t TokenAccountView<'a>,
     market_account: &'t MarketAccountView<'a>,
     mint: &'t MintAccountView<'a>,
+    transfer_hook_accounts: &'a [AccountView],
     amount: u64,
 ) -> ProgramResult {
     if amount == 0 {
@@ -138,14 +143,14 @@ pub unsafe fn withdraw_non_zero_from_market<'t, 'a>(
         // Safety: Scoped immutable borrow of mint account data to get the mint decimals.
         let decimals = unsafe { mint.get_mint_decimals() }?;
 
-        pinocchio_token_2022::instructions::TransferChecked {
+        TransferCheckedWithHook {
             from: market_ata.account,            // WRITE
             to: user_ata.account,                // WRITE
             mint: mint.account,                  // READ
             authority: market_account.account(), // READ
+            remaining_accounts: transfer_hook_accounts,
             amount,
             decimals,
-            token_program: &pinocchio_token_2022::ID,
         }
         .invoke_signed(&[market_signer!(base_mint, quote_mint, market_bump)])
     }
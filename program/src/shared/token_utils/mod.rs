@@ -1,3 +1,4 @@
 //! Utilities for SPL token operations.
 
 pub mod market_transfers;
+pub mod transfer_checked;
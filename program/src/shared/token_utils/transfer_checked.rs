@@ -0,0 +1,138 @@
+//! See [`TransferCheckedWithHook`].
+
+use core::mem::MaybeUninit;
+
+use dropset_interface::error::DropsetError;
+use pinocchio::{
+    account::AccountView,
+    cpi::invoke_signed_with_bounds,
+    ProgramResult,
+};
+use solana_instruction_view::{
+    cpi::Signer,
+    InstructionAccount,
+    InstructionView,
+};
+
+/// The max number of trailing accounts that can be forwarded to a `TransferChecked` CPI.
+///
+/// This covers the transfer hook program, its extra-account-metas PDA, and any extra accounts the
+/// hook itself requires.
+pub const MAX_TRANSFER_HOOK_ACCOUNTS: usize = 8;
+
+/// The number of accounts in a plain `TransferChecked` instruction.
+const TRANSFER_CHECKED_NUM_ACCOUNTS: usize = 4;
+
+const MAX_CPI_ACCOUNTS: usize = TRANSFER_CHECKED_NUM_ACCOUNTS + MAX_TRANSFER_HOOK_ACCOUNTS;
+
+/// The `spl_token_2022` instruction discriminant for `TransferChecked`.
+const TRANSFER_CHECKED_TAG: u8 = 12;
+
+/// The instruction data length for `TransferChecked`: the tag, a `u64` amount, and a `u8` for the
+/// mint decimals.
+const TRANSFER_CHECKED_DATA_LEN: usize = 1 + size_of::<u64>() + 1;
+
+/// A `spl_token_2022` `TransferChecked` instruction that forwards trailing accounts to the token
+/// program, which is necessary for mints with a transfer hook extension.
+///
+/// The token program resolves the hook program and its extra-account-metas PDA from the accounts
+/// passed, so their relative order in `remaining_accounts` doesn't matter.
+///
+/// ### Accounts
+///   0. `[WRITE]` Source token account
+///   1. `[READ]` Mint account
+///   2. `[WRITE]` Destination token account
+///   3. `[READ, SIGNER]` Authority
+///   4. .. `remaining_accounts`, forwarded as is
+pub struct TransferCheckedWithHook<'a, 'r> {
+    pub from: &'a AccountView,
+    pub mint: &'a AccountView,
+    pub to: &'a AccountView,
+    pub authority: &'a AccountView,
+    pub remaining_accounts: &'r [AccountView],
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+impl TransferCheckedWithHook<'_, '_> {
+    #[inline(always)]
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        // Without any trailing accounts, defer to the regular `TransferChecked` CPI.
+        if self.remaining_accounts.is_empty() {
+            return pinocchio_token_2022::instructions::TransferChecked {
+                from: self.from,
+                to: self.to,
+                mint: self.mint,
+                authority: self.authority,
+                amount: self.amount,
+                decimals: self.decimals,
+                token_program: &pinocchio_token_2022::ID,
+            }
+            .invoke_signed(signers);
+        }
+
+        if self.remaining_accounts.len() > MAX_TRANSFER_HOOK_ACCOUNTS {
+            return Err(DropsetError::TooManyTransferHookAccounts.into());
+        }
+
+        let num_accounts = TRANSFER_CHECKED_NUM_ACCOUNTS + self.remaining_accounts.len();
+
+        let mut instruction_accounts =
+            [const { MaybeUninit::<InstructionAccount>::uninit() }; MAX_CPI_ACCOUNTS];
+        let mut account_views = [const { MaybeUninit::<&AccountView>::uninit() }; MAX_CPI_ACCOUNTS];
+
+        instruction_accounts[0].write(InstructionAccount::writable(self.from.address()));
+        instruction_accounts[1].write(InstructionAccount::readonly(self.mint.address()));
+        instruction_accounts[2].write(InstructionAccount::writable(self.to.address()));
+        instruction_accounts[3].write(InstructionAccount::readonly_signer(
+            self.authority.address(),
+        ));
+        account_views[0].write(self.from);
+        account_views[1].write(self.mint);
+        account_views[2].write(self.to);
+        account_views[3].write(self.authority);
+
+        for (i, account) in self.remaining_accounts.iter().enumerate() {
+            let idx = TRANSFER_CHECKED_NUM_ACCOUNTS + i;
+            instruction_accounts[idx].write(InstructionAccount::new(
+                account.address(),
+                account.is_writable(),
+                account.is_signer(),
+            ));
+            account_views[idx].write(account);
+        }
+
+        let mut data = [0u8; TRANSFER_CHECKED_DATA_LEN];
+        data[0] = TRANSFER_CHECKED_TAG;
+        data[1..9].copy_from_slice(&self.amount.to_le_bytes());
+        data[9] = self.decimals;
+
+        // Safety: The first `num_accounts` elements of both arrays were initialized above.
+        let (instruction_accounts, account_views) = unsafe {
+            (
+                core::slice::from_raw_parts(
+                    instruction_accounts.as_ptr() as *const InstructionAccount,
+                    num_accounts,
+                ),
+                core::slice::from_raw_parts(
+                    account_views.as_ptr() as *const &AccountView,
+                    num_accounts,
+                ),
+            )
+        };
+
+        invoke_signed_with_bounds::<MAX_CPI_ACCOUNTS>(
+            &InstructionView {
+                program_id: &pinocchio_token_2022::ID,
+                accounts: instruction_accounts,
+                data: &data,
+            },
+            account_views,
+            signers,
+        )
+    }
+}
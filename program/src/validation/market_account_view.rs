@@ -9,7 +9,10 @@ use dropset_interface::{
             MarketRef,
             MarketRefMut,
         },
-        market_header::MarketHeader,
+        market_header::{
+            MarketHeader,
+            CURRENT_MARKET_LAYOUT_VERSION,
+        },
         sector::{
             Sector,
             SECTOR_SIZE,
@@ -41,7 +44,8 @@ impl<'a> MarketAccountView<'a> {
         self.account
     }
 
-    /// Checks that the account is owned by this program and is a properly initialized `Market`.
+    /// Checks that the account is owned by this program, is a properly initialized `Market`, and
+    /// uses the current market account layout.
     ///
     /// ## NOTE
     ///
@@ -59,6 +63,33 @@ impl<'a> MarketAccountView<'a> {
     ///   0. `[READ]` Market account
     #[inline(always)]
     pub unsafe fn new(account: &'a AccountView) -> Result<MarketAccountView<'a>, DropsetError> {
+        // Safety: The safety contract is the same as `new_any_version`.
+        let (market_account, layout_version) = unsafe { Self::new_any_version(account) }?;
+        if unlikely(layout_version != CURRENT_MARKET_LAYOUT_VERSION) {
+            return Err(DropsetError::OutdatedMarketLayout);
+        }
+
+        Ok(market_account)
+    }
+
+    /// Checks that the account is owned by this program and is a properly initialized `Market`
+    /// with any supported layout version, returning the view and the account's layout version.
+    ///
+    /// The sectors of an outdated market must not be interpreted with the current layout. This
+    /// should only be used to migrate a market to the current layout.
+    ///
+    /// # Safety
+    ///
+    /// Caller guarantees:
+    /// - WRITE accounts are not currently borrowed in *any* capacity.
+    /// - READ accounts are not currently mutably borrowed.
+    ///
+    /// ### Accounts
+    ///   0. `[READ]` Market account
+    #[inline(always)]
+    pub unsafe fn new_any_version(
+        account: &'a AccountView,
+    ) -> Result<(MarketAccountView<'a>, u8), DropsetError> {
         if unlikely(!owned_by(account, &program::ID)) {
             return Err(DropsetError::InvalidMarketAccountOwner);
         }
@@ -78,7 +109,12 @@ impl<'a> MarketAccountView<'a> {
             return Err(DropsetError::AccountNotInitialized);
         }
 
-        Ok(Self { account })
+        let layout_version = market.header.layout_version();
+        if unlikely(layout_version > CURRENT_MARKET_LAYOUT_VERSION) {
+            return Err(DropsetError::UnsupportedMarketLayout);
+        }
+
+        Ok((Self { account }, layout_version))
     }
 
     /// Safety:
@@ -3,12 +3,14 @@
 
 mod parse;
 mod parsed_account;
+mod parsed_error;
 mod parsed_instruction;
 mod parsed_logs;
 mod parsed_transaction;
 
 pub use parse::*;
 pub use parsed_account::*;
+pub use parsed_error::*;
 pub use parsed_instruction::*;
 pub use parsed_logs::*;
 pub use parsed_transaction::*;
@@ -10,7 +10,9 @@ use solana_address::Address;
 use solana_transaction_status_client_types::ParsedAccount as SdkParsedAccount;
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ParsedAccount {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::display"))]
     pub address: Address,
     pub writable: bool,
     pub signer: bool,
@@ -33,6 +35,7 @@ impl From<SdkParsedAccount> for ParsedAccount {
 }
 
 #[derive(Clone, Debug, Default, Deref, Index, IntoIterator, AsRef)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ParsedAccounts(Vec<ParsedAccount>);
 
 impl ParsedAccounts {
@@ -0,0 +1,217 @@
+//! Decodes custom program errors from failed `dropset` instructions back into [`DropsetError`]s.
+
+use std::fmt::Display;
+
+use dropset_interface::{
+    error::DropsetError,
+    instructions::{
+        BatchReplaceInstructionData,
+        CancelOrderInstructionData,
+        CloseSeatInstructionData,
+        DepositInstructionData,
+        DropsetInstruction,
+        FlushEventsInstructionData,
+        MarketOrderInstructionData,
+        MigrateMarketInstructionData,
+        PostOrderInstructionData,
+        RegisterMarketInstructionData,
+        WithdrawInstructionData,
+    },
+};
+use solana_address::Address;
+use solana_sdk::{
+    instruction::InstructionError,
+    transaction::TransactionError,
+};
+
+use crate::client_rpc::ParsedTransaction;
+
+/// The decoded arguments of a `dropset` instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DropsetInstructionArgs {
+    CloseSeat(CloseSeatInstructionData),
+    Deposit(DepositInstructionData),
+    RegisterMarket(RegisterMarketInstructionData),
+    Withdraw(WithdrawInstructionData),
+    PostOrder(PostOrderInstructionData),
+    CancelOrder(CancelOrderInstructionData),
+    BatchReplace(BatchReplaceInstructionData),
+    MarketOrder(MarketOrderInstructionData),
+    FlushEvents(FlushEventsInstructionData),
+    MigrateMarket(MigrateMarketInstructionData),
+}
+
+impl DropsetInstructionArgs {
+    /// Unpacks tagged `dropset` instruction data. Returns `None` if the tag is invalid or the data
+    /// is too short for the tagged instruction's arguments.
+    pub fn unpack(instruction_data: &[u8]) -> Option<Self> {
+        let [tag, data @ ..] = instruction_data else {
+            return None;
+        };
+
+        let args = match DropsetInstruction::try_from(*tag).ok()? {
+            DropsetInstruction::CloseSeat => {
+                Self::CloseSeat(CloseSeatInstructionData::unpack_untagged(data).ok()?)
+            }
+            DropsetInstruction::Deposit => {
+                Self::Deposit(DepositInstructionData::unpack_untagged(data).ok()?)
+            }
+            DropsetInstruction::RegisterMarket => {
+                Self::RegisterMarket(RegisterMarketInstructionData::unpack_untagged(data).ok()?)
+            }
+            DropsetInstruction::Withdraw => {
+                Self::Withdraw(WithdrawInstructionData::unpack_untagged(data).ok()?)
+            }
+            DropsetInstruction::PostOrder => {
+                Self::PostOrder(PostOrderInstructionData::unpack_untagged(data).ok()?)
+            }
+            DropsetInstruction::CancelOrder => {
+                Self::CancelOrder(CancelOrderInstructionData::unpack_untagged(data).ok()?)
+            }
+            DropsetInstruction::BatchReplace => {
+                Self::BatchReplace(BatchReplaceInstructionData::unpack_untagged(data).ok()?)
+            }
+            DropsetInstruction::MarketOrder => {
+                Self::MarketOrder(MarketOrderInstructionData::unpack_untagged(data).ok()?)
+            }
+            DropsetInstruction::FlushEvents => {
+                Self::FlushEvents(FlushEventsInstructionData::unpack_untagged(data).ok()?)
+            }
+            DropsetInstruction::MigrateMarket => {
+                Self::MigrateMarket(MigrateMarketInstructionData::unpack_untagged(data).ok()?)
+            }
+        };
+
+        Some(args)
+    }
+}
+
+/// A [`DropsetError`] returned by a `dropset` instruction, along with the instruction that failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParsedDropsetError {
+    /// The index of the failing outer instruction in the transaction.
+    pub instruction_index: u8,
+    pub instruction: DropsetInstruction,
+    /// The failing instruction's arguments, or `None` if its data couldn't be unpacked.
+    pub args: Option<DropsetInstructionArgs>,
+    pub error: DropsetError,
+    pub message: &'static str,
+}
+
+impl ParsedDropsetError {
+    /// Decodes an error returned by the instruction at `instruction_index`. Returns `None` unless
+    /// it's a custom error returned by a `dropset` instruction with a valid tag and error code.
+    pub fn from_instruction_error(
+        instruction_index: u8,
+        program_id: &Address,
+        instruction_data: &[u8],
+        error: &InstructionError,
+    ) -> Option<Self> {
+        let InstructionError::Custom(code) = error else {
+            return None;
+        };
+        if *program_id != dropset::ID {
+            return None;
+        }
+
+        let error = u8::try_from(*code).ok().and_then(DropsetError::from_repr)?;
+        let instruction = DropsetInstruction::try_from(*instruction_data.first()?).ok()?;
+
+        Some(Self {
+            instruction_index,
+            instruction,
+            args: DropsetInstructionArgs::unpack(instruction_data),
+            message: error.clone().into(),
+            error,
+        })
+    }
+}
+
+impl Display for ParsedDropsetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (instruction {}) failed: {}",
+            self.instruction, self.instruction_index, self.message
+        )
+    }
+}
+
+impl ParsedTransaction {
+    /// Decodes the transaction's error into a [`ParsedDropsetError`] if it failed on a custom error
+    /// returned by one of its `dropset` instructions.
+    pub fn dropset_error(&self) -> Option<ParsedDropsetError> {
+        let error: TransactionError = self.err.clone()?.into();
+        let TransactionError::InstructionError(index, error) = error else {
+            return None;
+        };
+        let instruction = &self.instructions.get(index as usize)?.outer_instruction;
+
+        ParsedDropsetError::from_instruction_error(
+            index,
+            &instruction.program_id,
+            &instruction.data,
+            &error,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use instruction_macros_traits::Tagged;
+
+    use super::*;
+
+    #[test]
+    fn decodes_dropset_custom_errors() {
+        let data = DepositInstructionData::new(500, 3).pack_tagged();
+        let custom = InstructionError::Custom(DropsetError::InsufficientUserBalance as u32);
+
+        let decoded =
+            ParsedDropsetError::from_instruction_error(2, &dropset::ID, &data, &custom).unwrap();
+        assert_eq!(decoded.instruction_index, 2);
+        assert_eq!(decoded.instruction, DropsetInstruction::Deposit);
+        assert_eq!(
+            decoded.args,
+            Some(DropsetInstructionArgs::Deposit(
+                DepositInstructionData::new(500, 3)
+            ))
+        );
+        assert_eq!(decoded.error, DropsetError::InsufficientUserBalance);
+        assert_eq!(
+            decoded.message,
+            <&'static str>::from(DropsetError::InsufficientUserBalance)
+        );
+    }
+
+    #[test]
+    fn ignores_other_errors() {
+        let data = DepositInstructionData::new(500, 3).pack_tagged();
+        let custom = InstructionError::Custom(DropsetError::InsufficientUserBalance as u32);
+
+        // Custom errors from other programs.
+        assert!(ParsedDropsetError::from_instruction_error(
+            0,
+            &Address::new_from_array([9; 32]),
+            &data,
+            &custom
+        )
+        .is_none());
+        // Builtin instruction errors.
+        assert!(ParsedDropsetError::from_instruction_error(
+            0,
+            &dropset::ID,
+            &data,
+            &InstructionError::InvalidAccountData
+        )
+        .is_none());
+        // Unknown error codes.
+        assert!(ParsedDropsetError::from_instruction_error(
+            0,
+            &dropset::ID,
+            &data,
+            &InstructionError::Custom(u32::MAX)
+        )
+        .is_none());
+    }
+}
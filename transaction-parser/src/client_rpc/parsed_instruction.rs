@@ -20,20 +20,25 @@ use crate::{
 };
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ParsedInstruction {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::display"))]
     pub program_id: Address,
     pub accounts: ParsedAccounts,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::base58"))]
     pub data: Vec<u8>,
     pub compute_info: Option<ParsedLogs>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ParsedOuterInstruction {
     pub outer_instruction: ParsedInstruction,
     pub inner_instructions: Vec<ParsedInstruction>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ParsedInnerInstruction {
     pub parent_index: u8,
     pub inner_instruction: ParsedInstruction,
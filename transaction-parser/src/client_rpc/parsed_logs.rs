@@ -16,10 +16,12 @@ use crate::{
 /// A struct that represents information extracted from a transaction's parsed program logs.
 #[derive(Debug, Clone)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ParsedLogs {
     /// The instruction invocation index- i.e., the order in which the instruction was executed.
     pub invocation_index: usize,
     /// The program's ID.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::display"))]
     pub program_id: Address,
     /// The height of the invocation/call stack.
     pub stack_height: usize,
@@ -35,9 +35,13 @@ use crate::client_rpc::{
 };
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ParsedTransaction {
     pub version: Option<i8>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::display"))]
     pub signature: Signature,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::display"))]
+    pub fee_payer: Address,
     pub slot: u64,
     pub block_time: Option<UnixTimestamp>,
     pub err: Option<UiTransactionError>,
@@ -93,6 +97,11 @@ impl ParsedTransaction {
             }
         };
 
+        // Static keys come before any loaded addresses, and the first one is always the fee payer.
+        let fee_payer = parsed_accounts
+            .first()
+            .ok_or(anyhow::Error::msg("Expected at least one account key"))?
+            .address;
         let inner_instructions: Vec<ParsedInnerInstruction> =
             parse_inner_instructions(meta.inner_instructions, &parsed_accounts);
 
@@ -102,6 +111,7 @@ impl ParsedTransaction {
                 _ => -1,
             }),
             signature,
+            fee_payer,
             slot,
             block_time,
             err: meta.err,
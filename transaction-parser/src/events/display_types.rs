@@ -1,16 +1,25 @@
-//! Defines the Display-able types for some event instruction data.
+//! Defines the Display-able types for each event's instruction data.
 
 use dropset_interface::events::{
+    CancelOrderEventInstructionData,
+    CloseSeatEventInstructionData,
+    DepositEventInstructionData,
     HeaderEventInstructionData,
+    MakerFillEventInstructionData,
+    MarketOrderEventInstructionData,
+    PostOrderEventInstructionData,
     RegisterMarketEventInstructionData,
+    WithdrawEventInstructionData,
 };
 use solana_address::Address;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DisplayHeaderData {
     pub instruction_tag: u8,
     pub emitted_count: u16,
     pub num_events: u64,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::display"))]
     pub market: Address,
 }
 
@@ -25,8 +34,10 @@ impl From<HeaderEventInstructionData> for DisplayHeaderData {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DisplayRegisterMarketData {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::display"))]
     pub market: Address,
 }
 
@@ -37,3 +48,153 @@ impl From<RegisterMarketEventInstructionData> for DisplayRegisterMarketData {
         }
     }
 }
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DisplayPostOrderData {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::display"))]
+    pub user: Address,
+    pub is_bid: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::json::encoded_price")
+    )]
+    pub encoded_price: u32,
+    pub user_seat_sector_index: u32,
+    pub order_sector_index: u32,
+    pub base_atoms: u64,
+    pub quote_atoms: u64,
+}
+
+impl From<PostOrderEventInstructionData> for DisplayPostOrderData {
+    fn from(value: PostOrderEventInstructionData) -> Self {
+        Self {
+            user: value.user,
+            is_bid: value.is_bid,
+            encoded_price: value.encoded_price,
+            user_seat_sector_index: value.user_seat_sector_index,
+            order_sector_index: value.order_sector_index,
+            base_atoms: value.base_atoms,
+            quote_atoms: value.quote_atoms,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DisplayCancelOrderData {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::display"))]
+    pub user: Address,
+    pub is_bid: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::json::encoded_price")
+    )]
+    pub encoded_price: u32,
+    pub user_seat_sector_index: u32,
+    pub order_sector_index: u32,
+    pub base_remaining: u64,
+    pub quote_remaining: u64,
+}
+
+impl From<CancelOrderEventInstructionData> for DisplayCancelOrderData {
+    fn from(value: CancelOrderEventInstructionData) -> Self {
+        Self {
+            user: value.user,
+            is_bid: value.is_bid,
+            encoded_price: value.encoded_price,
+            user_seat_sector_index: value.user_seat_sector_index,
+            order_sector_index: value.order_sector_index,
+            base_remaining: value.base_remaining,
+            quote_remaining: value.quote_remaining,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DisplayDepositData {
+    pub amount: u64,
+    pub is_base: bool,
+    pub seat_sector_index: u32,
+}
+
+impl From<DepositEventInstructionData> for DisplayDepositData {
+    fn from(value: DepositEventInstructionData) -> Self {
+        Self {
+            amount: value.amount,
+            is_base: value.is_base,
+            seat_sector_index: value.seat_sector_index,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DisplayWithdrawData {
+    pub amount: u64,
+    pub is_base: bool,
+}
+
+impl From<WithdrawEventInstructionData> for DisplayWithdrawData {
+    fn from(value: WithdrawEventInstructionData) -> Self {
+        Self {
+            amount: value.amount,
+            is_base: value.is_base,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DisplayCloseSeatData {
+    pub user_seat_sector_index: u32,
+}
+
+impl From<CloseSeatEventInstructionData> for DisplayCloseSeatData {
+    fn from(value: CloseSeatEventInstructionData) -> Self {
+        Self {
+            user_seat_sector_index: value.user_seat_sector_index,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DisplayMarketOrderData {
+    pub order_size: u64,
+    pub is_buy: bool,
+    pub is_base: bool,
+    pub base_filled: u64,
+    pub quote_filled: u64,
+}
+
+impl From<MarketOrderEventInstructionData> for DisplayMarketOrderData {
+    fn from(value: MarketOrderEventInstructionData) -> Self {
+        Self {
+            order_size: value.order_size,
+            is_buy: value.is_buy,
+            is_base: value.is_base,
+            base_filled: value.base_filled,
+            quote_filled: value.quote_filled,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DisplayMakerFillData {
+    pub order_sector_index: u32,
+    pub base_filled: u64,
+    pub quote_filled: u64,
+}
+
+impl From<MakerFillEventInstructionData> for DisplayMakerFillData {
+    fn from(value: MakerFillEventInstructionData) -> Self {
+        Self {
+            order_sector_index: value.order_sector_index,
+            base_filled: value.base_filled,
+            quote_filled: value.quote_filled,
+        }
+    }
+}
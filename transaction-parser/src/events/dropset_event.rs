@@ -7,25 +7,33 @@ use dropset_interface::events::{
     DepositEventInstructionData,
     DropsetEventTag,
     HeaderEventInstructionData,
+    MakerFillEventInstructionData,
     MarketOrderEventInstructionData,
     PostOrderEventInstructionData,
     RegisterMarketEventInstructionData,
     WithdrawEventInstructionData,
 };
 use instruction_macros_traits::Tagged;
+use solana_address::Address;
 
 use crate::events::display_types;
 
-#[derive(Debug, strum_macros::VariantNames)]
+#[derive(Clone, Debug, strum_macros::VariantNames)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
 pub enum DropsetEvent {
     Header(display_types::DisplayHeaderData),
-    Deposit(DepositEventInstructionData),
-    Withdraw(WithdrawEventInstructionData),
+    Deposit(display_types::DisplayDepositData),
+    Withdraw(display_types::DisplayWithdrawData),
     RegisterMarket(display_types::DisplayRegisterMarketData),
-    CloseSeat(CloseSeatEventInstructionData),
-    PostOrder(PostOrderEventInstructionData),
-    CancelOrder(CancelOrderEventInstructionData),
-    MarketOrder(MarketOrderEventInstructionData),
+    CloseSeat(display_types::DisplayCloseSeatData),
+    PostOrder(display_types::DisplayPostOrderData),
+    CancelOrder(display_types::DisplayCancelOrderData),
+    MarketOrder(display_types::DisplayMarketOrderData),
+    MakerFill(display_types::DisplayMakerFillData),
 }
 
 impl DropsetEvent {
@@ -39,6 +47,7 @@ impl DropsetEvent {
             Self::PostOrder(_) => PostOrderEventInstructionData::LEN_WITH_TAG,
             Self::CancelOrder(_) => CancelOrderEventInstructionData::LEN_WITH_TAG,
             Self::MarketOrder(_) => MarketOrderEventInstructionData::LEN_WITH_TAG,
+            Self::MakerFill(_) => MakerFillEventInstructionData::LEN_WITH_TAG,
         }
     }
 }
@@ -50,6 +59,24 @@ pub enum EventError {
     UnpackError(DropsetEventTag),
     InvalidTag,
     EventBufferHasRemainingBytes,
+    EmittedCountExceedsNumEvents,
+}
+
+/// A [`DropsetEvent`] paired with the market it was emitted for and its market-local sequence
+/// number.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SequencedEvent {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::display"))]
+    pub market: Address,
+    /// The market's total number of events right after this event was emitted. The first event a
+    /// market emits has sequence number `1`, and each subsequent event increments it by one.
+    ///
+    /// This lines up with the market header's `num_events`, so an account snapshot with
+    /// `num_events == n` reflects every event up to and including sequence number `n`.
+    pub seq: u64,
+    /// Nested rather than flattened, since some events have their own `market` field.
+    pub event: DropsetEvent,
 }
 
 /// Unpack instruction events from instruction data that starts *after* the instruction tag is
@@ -57,6 +84,36 @@ pub enum EventError {
 ///
 /// That is, `instruction_data` here starts after the instruction tag.
 pub fn unpack_instruction_events(instruction_data: &[u8]) -> Result<Vec<DropsetEvent>, EventError> {
+    unpack_header_and_events(instruction_data).map(|(_header, events)| events)
+}
+
+/// Unpack instruction events the same way as [`unpack_instruction_events`], but tag each event
+/// with its market-local sequence number derived from the event header.
+///
+/// The header reports the market's total number of events *after* the buffer was flushed, so the
+/// events in the buffer are numbered `num_events - emitted_count + 1 ..= num_events`.
+pub fn unpack_sequenced_events(instruction_data: &[u8]) -> Result<Vec<SequencedEvent>, EventError> {
+    let (header, events) = unpack_header_and_events(instruction_data)?;
+    let first_seq = header
+        .num_events
+        .checked_sub(header.emitted_count as u64)
+        .ok_or(EventError::EmittedCountExceedsNumEvents)?
+        + 1;
+
+    Ok(events
+        .into_iter()
+        .zip(first_seq..)
+        .map(|(event, seq)| SequencedEvent {
+            market: header.market,
+            seq,
+            event,
+        })
+        .collect())
+}
+
+fn unpack_header_and_events(
+    instruction_data: &[u8],
+) -> Result<(display_types::DisplayHeaderData, Vec<DropsetEvent>), EventError> {
     let original_len = instruction_data.len();
 
     // The first event should be the event header.
@@ -66,8 +123,7 @@ pub fn unpack_instruction_events(instruction_data: &[u8]) -> Result<Vec<DropsetE
     };
 
     let num_events = header.emitted_count as usize;
-    let header_event = DropsetEvent::Header(header);
-    let mut cursor = header_event.len_with_tag();
+    let mut cursor = HeaderEventInstructionData::LEN_WITH_TAG;
     let mut res = vec![];
 
     for _ in 0..num_events {
@@ -82,7 +138,7 @@ pub fn unpack_instruction_events(instruction_data: &[u8]) -> Result<Vec<DropsetE
         return Err(EventError::EventBufferHasRemainingBytes);
     }
 
-    Ok(res)
+    Ok((header, res))
 }
 
 impl DropsetEvent {
@@ -100,10 +156,14 @@ impl DropsetEvent {
                     .into(),
             )),
             DropsetEventTag::DepositEvent => Ok(DropsetEvent::Deposit(
-                DepositEventInstructionData::unpack_untagged(data).map_err(|_| err())?,
+                DepositEventInstructionData::unpack_untagged(data)
+                    .map_err(|_| err())?
+                    .into(),
             )),
             DropsetEventTag::WithdrawEvent => Ok(DropsetEvent::Withdraw(
-                WithdrawEventInstructionData::unpack_untagged(data).map_err(|_| err())?,
+                WithdrawEventInstructionData::unpack_untagged(data)
+                    .map_err(|_| err())?
+                    .into(),
             )),
             DropsetEventTag::RegisterMarketEvent => Ok(DropsetEvent::RegisterMarket(
                 RegisterMarketEventInstructionData::unpack_untagged(data)
@@ -111,17 +171,118 @@ impl DropsetEvent {
                     .into(),
             )),
             DropsetEventTag::CloseSeatEvent => Ok(DropsetEvent::CloseSeat(
-                CloseSeatEventInstructionData::unpack_untagged(data).map_err(|_| err())?,
+                CloseSeatEventInstructionData::unpack_untagged(data)
+                    .map_err(|_| err())?
+                    .into(),
             )),
             DropsetEventTag::PostOrderEvent => Ok(DropsetEvent::PostOrder(
-                PostOrderEventInstructionData::unpack_untagged(data).map_err(|_| err())?,
+                PostOrderEventInstructionData::unpack_untagged(data)
+                    .map_err(|_| err())?
+                    .into(),
             )),
             DropsetEventTag::CancelOrderEvent => Ok(DropsetEvent::CancelOrder(
-                CancelOrderEventInstructionData::unpack_untagged(data).map_err(|_| err())?,
+                CancelOrderEventInstructionData::unpack_untagged(data)
+                    .map_err(|_| err())?
+                    .into(),
             )),
             DropsetEventTag::MarketOrderEvent => Ok(DropsetEvent::MarketOrder(
-                MarketOrderEventInstructionData::unpack_untagged(data).map_err(|_| err())?,
+                MarketOrderEventInstructionData::unpack_untagged(data)
+                    .map_err(|_| err())?
+                    .into(),
+            )),
+            DropsetEventTag::MakerFillEvent => Ok(DropsetEvent::MakerFill(
+                MakerFillEventInstructionData::unpack_untagged(data)
+                    .map_err(|_| err())?
+                    .into(),
             )),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use dropset_interface::instructions::DropsetInstruction;
+
+    use super::*;
+
+    #[test]
+    fn unpack_order_lifecycle_events() {
+        let user = Address::new_from_array([7; 32]);
+        let header = HeaderEventInstructionData::new(
+            DropsetInstruction::CancelOrder as u8,
+            2,
+            5,
+            Address::new_from_array([1; 32]),
+        );
+        let post = PostOrderEventInstructionData::new(user, true, 1234, 3, 9, 100, 200);
+        let cancel = CancelOrderEventInstructionData::new(user, true, 1234, 3, 9, 100, 150);
+        let data = [
+            header.pack_tagged().as_slice(),
+            post.pack_tagged().as_slice(),
+            cancel.pack_tagged().as_slice(),
+        ]
+        .concat();
+
+        let events = unpack_instruction_events(&data).unwrap();
+        let [DropsetEvent::PostOrder(post), DropsetEvent::CancelOrder(cancel)] = &events[..] else {
+            panic!("Unexpected events: {events:?}");
+        };
+        assert_eq!(post.user, user);
+        assert!(post.is_bid);
+        assert_eq!(post.encoded_price, 1234);
+        assert_eq!(post.user_seat_sector_index, 3);
+        assert_eq!(post.order_sector_index, 9);
+        assert_eq!((post.base_atoms, post.quote_atoms), (100, 200));
+        assert_eq!(cancel.user, user);
+        assert_eq!(cancel.encoded_price, 1234);
+        assert_eq!(cancel.order_sector_index, 9);
+        assert_eq!((cancel.base_remaining, cancel.quote_remaining), (100, 150));
+
+        // The header reports 5 total events after flushing these 2, so they're events 4 and 5.
+        let sequenced = unpack_sequenced_events(&data).unwrap();
+        assert_eq!(sequenced.iter().map(|e| e.seq).collect::<Vec<_>>(), [4, 5]);
+        assert!(sequenced
+            .iter()
+            .all(|e| e.market == Address::new_from_array([1; 32])));
+    }
+
+    #[test]
+    fn unpack_market_order_maker_fills() {
+        let header = HeaderEventInstructionData::new(
+            DropsetInstruction::MarketOrder as u8,
+            3,
+            3,
+            Address::new_from_array([1; 32]),
+        );
+        let full = MakerFillEventInstructionData::new(4, 100, 200);
+        let partial = MakerFillEventInstructionData::new(6, 50, 110);
+        let market_order = MarketOrderEventInstructionData::new(150, true, true, 150, 310);
+        let data = [
+            header.pack_tagged().as_slice(),
+            full.pack_tagged().as_slice(),
+            partial.pack_tagged().as_slice(),
+            market_order.pack_tagged().as_slice(),
+        ]
+        .concat();
+
+        let events = unpack_instruction_events(&data).unwrap();
+        // Each maker order filled emits its own event, followed by the taker's aggregate.
+        let [fills @ .., DropsetEvent::MarketOrder(market_order)] = &events[..] else {
+            panic!("Unexpected events: {events:?}");
+        };
+        let [DropsetEvent::MakerFill(full), DropsetEvent::MakerFill(partial)] = fills else {
+            panic!("Unexpected events: {events:?}");
+        };
+        assert_eq!(full.order_sector_index, 4);
+        assert_eq!((full.base_filled, full.quote_filled), (100, 200));
+        assert_eq!(partial.order_sector_index, 6);
+        assert_eq!((partial.base_filled, partial.quote_filled), (50, 110));
+        assert_eq!(
+            (market_order.base_filled, market_order.quote_filled),
+            (
+                full.base_filled + partial.base_filled,
+                full.quote_filled + partial.quote_filled
+            )
+        );
+    }
+}
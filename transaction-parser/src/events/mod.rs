@@ -2,3 +2,4 @@
 
 pub mod display_types;
 pub mod dropset_event;
+pub mod sequencer;
@@ -0,0 +1,239 @@
+//! See [`EventSequencer`].
+
+use std::collections::{
+    HashMap,
+    VecDeque,
+};
+
+use solana_address::Address;
+use solana_sdk::signature::Signature;
+
+use crate::events::dropset_event::SequencedEvent;
+
+/// The default number of recent events retained per market for telling duplicates from reorgs.
+pub const DEFAULT_HISTORY_LEN: usize = 1024;
+
+/// The result of observing a single [`SequencedEvent`] with an [`EventSequencer`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SequenceStatus {
+    /// The event immediately follows the last event observed for its market.
+    Next,
+    /// The event was already observed in the same transaction and should be ignored.
+    Duplicate,
+    /// The event is older than the retained history or the last resync point, so it can't be
+    /// verified. Its effects are already reflected in any state synced past it.
+    Stale,
+    /// Events `expected..found` were never observed. Consumers should resync the market from
+    /// account state.
+    Gap { expected: u64, found: u64 },
+    /// An event with the same sequence number was previously observed in a different transaction,
+    /// meaning the history from `seq` onwards was rolled back and replaced. Consumers should
+    /// resync the market from account state.
+    Reorg { seq: u64, previous: Signature },
+}
+
+impl SequenceStatus {
+    /// Whether or not the consumer's view of the market is no longer trustworthy and should be
+    /// rebuilt from the market account's state.
+    pub fn needs_resync(&self) -> bool {
+        matches!(self, Self::Gap { .. } | Self::Reorg { .. })
+    }
+}
+
+#[derive(Debug, Default)]
+struct MarketSequence {
+    /// The sequence number of the last event observed or resynced to.
+    last_seq: u64,
+    /// The most recently observed events' sequence numbers and transaction signatures, in
+    /// ascending sequence order.
+    history: VecDeque<(u64, Signature)>,
+}
+
+/// Tracks the market-local sequence numbers of each market's event stream across transactions
+/// and detects gaps, duplicates, and reorgs.
+///
+/// Markets are tracked once [`EventSequencer::resync`] is called for them or their first event is
+/// observed, whichever comes first.
+#[derive(Debug)]
+pub struct EventSequencer {
+    markets: HashMap<Address, MarketSequence>,
+    history_len: usize,
+}
+
+impl Default for EventSequencer {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_LEN)
+    }
+}
+
+impl EventSequencer {
+    /// Creates a sequencer that retains the last `history_len` events for each market.
+    pub fn new(history_len: usize) -> Self {
+        Self {
+            markets: HashMap::new(),
+            history_len: history_len.max(1),
+        }
+    }
+
+    /// The sequence number of the last event observed or resynced to for `market`, if tracked.
+    pub fn last_seq(&self, market: &Address) -> Option<u64> {
+        self.markets.get(market).map(|m| m.last_seq)
+    }
+
+    /// Resets the sequence for `market` to a freshly fetched account snapshot, where `num_events`
+    /// is the market header's total number of events at the time of the snapshot.
+    pub fn resync(&mut self, market: Address, num_events: u64) {
+        self.markets.insert(
+            market,
+            MarketSequence {
+                last_seq: num_events,
+                history: VecDeque::new(),
+            },
+        );
+    }
+
+    /// Observes a single event emitted in the transaction with `signature`.
+    ///
+    /// Events from the same transaction must be observed in the order they were emitted.
+    pub fn observe(&mut self, signature: Signature, event: &SequencedEvent) -> SequenceStatus {
+        let seq = event.seq;
+        let Some(market) = self.markets.get_mut(&event.market) else {
+            let mut market = MarketSequence::default();
+            market.push(seq, signature, self.history_len);
+            self.markets.insert(event.market, market);
+            return SequenceStatus::Next;
+        };
+
+        if seq > market.last_seq {
+            let expected = market.last_seq + 1;
+            market.push(seq, signature, self.history_len);
+            return match seq == expected {
+                true => SequenceStatus::Next,
+                false => SequenceStatus::Gap {
+                    expected,
+                    found: seq,
+                },
+            };
+        }
+
+        let Some(previous) = market.find(seq) else {
+            return SequenceStatus::Stale;
+        };
+        if previous == signature {
+            return SequenceStatus::Duplicate;
+        }
+
+        // Drop the rolled back history and continue from the replacing transaction.
+        market.history.retain(|(s, _)| *s < seq);
+        market.push(seq, signature, self.history_len);
+        SequenceStatus::Reorg { seq, previous }
+    }
+}
+
+impl MarketSequence {
+    fn push(&mut self, seq: u64, signature: Signature, history_len: usize) {
+        self.last_seq = seq;
+        self.history.push_back((seq, signature));
+        while self.history.len() > history_len {
+            self.history.pop_front();
+        }
+    }
+
+    fn find(&self, seq: u64) -> Option<Signature> {
+        self.history
+            .iter()
+            .rev()
+            .find(|(s, _)| *s == seq)
+            .map(|(_, signature)| *signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dropset_interface::events::WithdrawEventInstructionData;
+
+    use super::*;
+    use crate::events::dropset_event::DropsetEvent;
+
+    const MARKET: Address = Address::new_from_array([1; 32]);
+
+    fn event(seq: u64) -> SequencedEvent {
+        SequencedEvent {
+            market: MARKET,
+            seq,
+            event: DropsetEvent::Withdraw(WithdrawEventInstructionData::new(1, true).into()),
+        }
+    }
+
+    fn sig(n: u8) -> Signature {
+        Signature::from([n; 64])
+    }
+
+    #[test]
+    fn in_order_and_duplicates() {
+        let mut sequencer = EventSequencer::default();
+        assert_eq!(sequencer.observe(sig(1), &event(1)), SequenceStatus::Next);
+        assert_eq!(sequencer.observe(sig(1), &event(2)), SequenceStatus::Next);
+        assert_eq!(sequencer.observe(sig(2), &event(3)), SequenceStatus::Next);
+        // The same transaction delivered twice.
+        assert_eq!(
+            sequencer.observe(sig(2), &event(3)),
+            SequenceStatus::Duplicate
+        );
+        assert_eq!(sequencer.last_seq(&MARKET), Some(3));
+    }
+
+    #[test]
+    fn gap() {
+        let mut sequencer = EventSequencer::default();
+        sequencer.resync(MARKET, 10);
+        assert_eq!(sequencer.observe(sig(1), &event(8)), SequenceStatus::Stale);
+        let status = sequencer.observe(sig(1), &event(13));
+        assert_eq!(
+            status,
+            SequenceStatus::Gap {
+                expected: 11,
+                found: 13
+            }
+        );
+        assert!(status.needs_resync());
+        assert_eq!(sequencer.observe(sig(2), &event(14)), SequenceStatus::Next);
+    }
+
+    #[test]
+    fn reorg() {
+        let mut sequencer = EventSequencer::default();
+        for seq in 1..=4 {
+            sequencer.observe(sig(seq as u8), &event(seq));
+        }
+        // Events 3 and 4 were rolled back and event 3 landed in a different transaction instead.
+        let status = sequencer.observe(sig(9), &event(3));
+        assert_eq!(
+            status,
+            SequenceStatus::Reorg {
+                seq: 3,
+                previous: sig(3)
+            }
+        );
+        assert!(status.needs_resync());
+        assert_eq!(sequencer.last_seq(&MARKET), Some(3));
+        assert_eq!(
+            sequencer.observe(sig(9), &event(3)),
+            SequenceStatus::Duplicate
+        );
+        assert_eq!(sequencer.observe(sig(9), &event(4)), SequenceStatus::Next);
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut sequencer = EventSequencer::new(2);
+        for seq in 1..=5 {
+            sequencer.observe(sig(seq as u8), &event(seq));
+        }
+        assert_eq!(sequencer.observe(sig(0), &event(3)), SequenceStatus::Stale);
+        assert_eq!(
+            sequencer.observe(sig(4), &event(4)),
+            SequenceStatus::Duplicate
+        );
+    }
+}
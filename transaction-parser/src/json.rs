@@ -0,0 +1,224 @@
+//! Stable JSON and NDJSON export for parsed events, market views, and transactions.
+//!
+//! The JSON representation follows a few conventions across all types:
+//! - Addresses and signatures are base58 strings.
+//! - Raw instruction data is a base58 string.
+//! - Encoded prices are objects with the on-chain `encoded` u32 and the decoded `price` as a
+//!   decimal string, or `null` if the encoded price doesn't represent a finite price.
+//! - Events are internally tagged with an explicit snake case `type`, e.g. `"type": "post_order"`.
+//! - Sequenced events nest the event under an `event` key next to its `market` and `seq`.
+
+use std::{
+    collections::{
+        BTreeMap,
+        HashMap,
+    },
+    fmt::Display,
+    io::Write,
+    str::FromStr,
+};
+
+use dropset_interface::state::{
+    sector::SectorIndex,
+    user_order_sectors::{
+        OrderSectors,
+        UserOrderSectors,
+    },
+};
+use price::client_helpers::try_encoded_u32_to_decoded_decimal;
+use serde::{
+    ser::SerializeSeq,
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+};
+use solana_sdk::bs58;
+
+/// Writes each value as a single line of JSON, i.e. newline-delimited JSON.
+pub fn write_ndjson<T: Serialize, W: Write>(
+    mut writer: W,
+    values: impl IntoIterator<Item = T>,
+) -> serde_json::Result<()> {
+    for value in values {
+        serde_json::to_writer(&mut writer, &value)?;
+        writer.write_all(b"\n").map_err(serde_json::Error::io)?;
+    }
+
+    Ok(())
+}
+
+/// Serializes a value with its `Display` implementation, e.g. addresses and signatures as base58.
+pub(crate) fn display<T: Display, S: Serializer>(value: &T, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(value)
+}
+
+/// Deserializes a value from its string form, i.e. the inverse of [`display`].
+pub(crate) fn from_str<'de, T, D>(d: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    String::deserialize(d)?
+        .parse()
+        .map_err(serde::de::Error::custom)
+}
+
+/// Serializes raw bytes as a base58 string.
+pub(crate) fn base58<S: Serializer>(bytes: &[u8], s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&bs58::encode(bytes).into_string())
+}
+
+/// An encoded price along with its decoded, human-readable decimal price.
+#[derive(Clone, Debug, Serialize)]
+pub struct EncodedPriceJson {
+    pub encoded: u32,
+    pub price: Option<String>,
+}
+
+impl From<u32> for EncodedPriceJson {
+    fn from(encoded: u32) -> Self {
+        Self {
+            encoded,
+            price: try_encoded_u32_to_decoded_decimal(encoded)
+                .ok()
+                .map(|decimal| decimal.to_string()),
+        }
+    }
+}
+
+/// Serializes an encoded price along with its decoded, human-readable decimal price.
+pub(crate) fn encoded_price<S: Serializer>(encoded: &u32, s: S) -> Result<S::Ok, S::Error> {
+    EncodedPriceJson::from(*encoded).serialize(s)
+}
+
+/// Serializes a map keyed by address as a JSON object with base58 keys, sorted for stable output.
+pub(crate) fn address_map<K: Display, V: Serialize, S: Serializer>(
+    map: &HashMap<K, V>,
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    map.iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect::<BTreeMap<_, _>>()
+        .serialize(s)
+}
+
+#[derive(Serialize)]
+struct OrderSectorJson {
+    encoded_price: EncodedPriceJson,
+    sector_index: SectorIndex,
+}
+
+struct OrderSectorsJson<'a>(&'a OrderSectors);
+
+impl Serialize for OrderSectorsJson<'_> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let entries = self.0.iter().filter(|entry| !entry.is_free());
+        let mut seq = s.serialize_seq(None)?;
+        for entry in entries {
+            seq.serialize_element(&OrderSectorJson {
+                encoded_price: u32::from_le_bytes(entry.encoded_price.as_array()).into(),
+                sector_index: SectorIndex::from_le_bytes(entry.sector_index),
+            })?;
+        }
+        seq.end()
+    }
+}
+
+/// Serializes a seat's user order sectors as the occupied bid and ask entries only.
+pub(crate) fn user_order_sectors<S: Serializer>(
+    sectors: &UserOrderSectors,
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct UserOrderSectorsJson<'a> {
+        bids: OrderSectorsJson<'a>,
+        asks: OrderSectorsJson<'a>,
+    }
+
+    UserOrderSectorsJson {
+        bids: OrderSectorsJson(&sectors.bids),
+        asks: OrderSectorsJson(&sectors.asks),
+    }
+    .serialize(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use dropset_interface::events::{
+        DepositEventInstructionData,
+        PostOrderEventInstructionData,
+        RegisterMarketEventInstructionData,
+    };
+    use price::OrderInfoArgs;
+    use solana_address::Address;
+
+    use super::*;
+    use crate::events::dropset_event::{
+        DropsetEvent,
+        SequencedEvent,
+    };
+
+    #[test]
+    fn event_json() {
+        let user = Address::new_from_array([7; 32]);
+        let encoded_price = price::to_order_info(OrderInfoArgs::new_unscaled(12_345_678, 1))
+            .unwrap()
+            .encoded_price
+            .as_u32();
+        let event = DropsetEvent::PostOrder(
+            PostOrderEventInstructionData::new(user, true, encoded_price, 3, 9, 1, 12_345_678)
+                .into(),
+        );
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "post_order");
+        assert_eq!(json["user"], user.to_string());
+        assert_eq!(json["encoded_price"]["encoded"], encoded_price);
+        assert_eq!(json["encoded_price"]["price"], "12345678");
+        assert_eq!(json["base_atoms"], 1);
+    }
+
+    #[test]
+    fn sequenced_event_ndjson() {
+        let events = (1..=2).map(|seq| SequencedEvent {
+            market: Address::new_from_array([1; 32]),
+            seq,
+            event: DropsetEvent::Deposit(DepositEventInstructionData::new(10, true, 0).into()),
+        });
+
+        let mut out = vec![];
+        write_ndjson(&mut out, events).unwrap();
+        let lines = String::from_utf8(out).unwrap();
+        let lines = lines.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["seq"], 1);
+        assert_eq!(first["event"]["type"], "deposit");
+        assert_eq!(first["event"]["amount"], 10);
+        assert_eq!(
+            first["market"],
+            Address::new_from_array([1; 32]).to_string()
+        );
+    }
+
+    #[test]
+    fn sequenced_register_market_json() {
+        let market = Address::new_from_array([1; 32]);
+        let event = SequencedEvent {
+            market,
+            seq: 1,
+            event: DropsetEvent::RegisterMarket(
+                RegisterMarketEventInstructionData::new(market).into(),
+            ),
+        };
+
+        // The event's own `market` field doesn't collide with the sequenced event's.
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["market"], market.to_string());
+        assert_eq!(json["event"]["type"], "register_market");
+        assert_eq!(json["event"]["market"], market.to_string());
+    }
+}
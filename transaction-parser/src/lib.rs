@@ -3,6 +3,9 @@
 
 pub mod client_rpc;
 pub mod events;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod market_data;
 mod parse_dropset_events;
 pub mod program_ids;
 pub mod views;
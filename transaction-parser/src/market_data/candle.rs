@@ -0,0 +1,399 @@
+//! See [`CandleAggregator`].
+
+use std::collections::HashMap;
+
+use chrono::{
+    DateTime,
+    Datelike,
+};
+use rust_decimal::Decimal;
+use solana_address::Address;
+use solana_sdk::clock::UnixTimestamp;
+use strum::IntoEnumIterator;
+use strum_macros::{
+    AsRefStr,
+    Display,
+    EnumIter,
+    EnumString,
+};
+
+use crate::market_data::trade::Trade;
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+
+/// The number of days between the unix epoch, a Thursday, and the Monday before it.
+const EPOCH_DAYS_SINCE_MONDAY: i64 = 3;
+
+/// OANDA candlestick time-bucket sizes and their alignment rules (minute/hour/day/week/month).
+/// Candles for `dropset` markets use the same granularities so they line up with external price
+/// feeds. See: <https://developer.oanda.com/rest-live-v20/instrument-df/#CandlestickGranularity>
+///
+/// Every candle length up to a day evenly divides a day, so aligning candles to multiples of their
+/// length since the unix epoch also aligns them to the minute, hour, or day. Weekly candles start
+/// on Monday and monthly candles start on the first day of the month, both at 00:00 UTC.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    EnumString,
+    AsRefStr,
+    Display,
+    EnumIter,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CandlestickGranularity {
+    /// 5 second candlesticks, minute alignment
+    S5,
+    /// 10 second candlesticks, minute alignment
+    S10,
+    /// 15 second candlesticks, minute alignment
+    S15,
+    /// 30 second candlesticks, minute alignment
+    S30,
+
+    /// 1 minute candlesticks, minute alignment
+    M1,
+    /// 2 minute candlesticks, hour alignment
+    M2,
+    /// 4 minute candlesticks, hour alignment
+    M4,
+    /// 5 minute candlesticks, hour alignment
+    M5,
+    /// 10 minute candlesticks, hour alignment
+    M10,
+    /// 15 minute candlesticks, hour alignment
+    M15,
+    /// 30 minute candlesticks, hour alignment
+    M30,
+
+    /// 1 hour candlesticks, hour alignment
+    H1,
+    /// 2 hour candlesticks, day alignment
+    H2,
+    /// 3 hour candlesticks, day alignment
+    H3,
+    /// 4 hour candlesticks, day alignment
+    H4,
+    /// 6 hour candlesticks, day alignment
+    H6,
+    /// 8 hour candlesticks, day alignment
+    H8,
+    /// 12 hour candlesticks, day alignment
+    H12,
+
+    /// 1 day candlesticks, day alignment
+    D,
+    /// 1 week candlesticks, aligned to start of week
+    W,
+    /// 1 month candlesticks, aligned to first day of the month
+    M,
+}
+
+impl CandlestickGranularity {
+    /// The length of each candle in seconds, or `None` for monthly candles since months vary in
+    /// length.
+    pub fn seconds(self) -> Option<i64> {
+        let seconds = match self {
+            Self::S5 => 5,
+            Self::S10 => 10,
+            Self::S15 => 15,
+            Self::S30 => 30,
+            Self::M1 => MINUTE,
+            Self::M2 => 2 * MINUTE,
+            Self::M4 => 4 * MINUTE,
+            Self::M5 => 5 * MINUTE,
+            Self::M10 => 10 * MINUTE,
+            Self::M15 => 15 * MINUTE,
+            Self::M30 => 30 * MINUTE,
+            Self::H1 => HOUR,
+            Self::H2 => 2 * HOUR,
+            Self::H3 => 3 * HOUR,
+            Self::H4 => 4 * HOUR,
+            Self::H6 => 6 * HOUR,
+            Self::H8 => 8 * HOUR,
+            Self::H12 => 12 * HOUR,
+            Self::D => DAY,
+            Self::W => 7 * DAY,
+            Self::M => return None,
+        };
+
+        Some(seconds)
+    }
+
+    /// The start time of the candle that contains `timestamp`.
+    pub fn candle_start(self, timestamp: UnixTimestamp) -> UnixTimestamp {
+        match self {
+            Self::W => {
+                let offset = EPOCH_DAYS_SINCE_MONDAY * DAY;
+                timestamp - (timestamp + offset).rem_euclid(7 * DAY)
+            }
+            Self::M => {
+                let date = DateTime::from_timestamp(timestamp, 0)
+                    .expect("Timestamp should be in range")
+                    .date_naive();
+                date.with_day(1)
+                    .and_then(|first| first.and_hms_opt(0, 0, 0))
+                    .expect("The first of the month at midnight should be valid")
+                    .and_utc()
+                    .timestamp()
+            }
+            _ => {
+                let seconds = self.seconds().expect("Only monthly candles vary in length");
+                timestamp - timestamp.rem_euclid(seconds)
+            }
+        }
+    }
+}
+
+/// An OHLCV candle for a single market. Prices are in whole quote tokens per whole base token, and
+/// volumes are in atoms.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Candle {
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "crate::json::display",
+            deserialize_with = "crate::json::from_str"
+        )
+    )]
+    pub market: Address,
+    pub granularity: CandlestickGranularity,
+    /// The start time of the candle.
+    pub start: UnixTimestamp,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub base_volume: u128,
+    pub quote_volume: u128,
+    /// The number of trades in the candle.
+    pub trades: u64,
+}
+
+impl Candle {
+    fn new(
+        trade: &Trade,
+        price: Decimal,
+        granularity: CandlestickGranularity,
+        start: UnixTimestamp,
+    ) -> Self {
+        Self {
+            market: trade.market,
+            granularity,
+            start,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            base_volume: trade.base_atoms as u128,
+            quote_volume: trade.quote_atoms as u128,
+            trades: 1,
+        }
+    }
+
+    fn update(&mut self, trade: &Trade, price: Decimal) {
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.base_volume += trade.base_atoms as u128;
+        self.quote_volume += trade.quote_atoms as u128;
+        self.trades += 1;
+    }
+}
+
+/// Aggregates trades into OHLCV candles for each market at several granularities at once.
+///
+/// Trades must be pushed in the order they happened and have a [token price](Trade::token_price).
+/// Periods without any trades don't produce candles, and a trade with a timestamp before its
+/// market's open candle is folded into that candle, since block times aren't strictly monotonic.
+#[derive(Debug)]
+pub struct CandleAggregator {
+    granularities: Vec<CandlestickGranularity>,
+    open: HashMap<(Address, CandlestickGranularity), Candle>,
+}
+
+impl Default for CandleAggregator {
+    fn default() -> Self {
+        Self::new(CandlestickGranularity::iter())
+    }
+}
+
+impl CandleAggregator {
+    pub fn new(granularities: impl IntoIterator<Item = CandlestickGranularity>) -> Self {
+        let mut granularities = granularities.into_iter().collect::<Vec<_>>();
+        granularities.sort();
+        granularities.dedup();
+
+        Self {
+            granularities,
+            open: HashMap::new(),
+        }
+    }
+
+    /// Folds a trade into its market's open candles and returns the candles it closed, i.e. the
+    /// previous candle for each granularity where the trade starts a new one.
+    ///
+    /// Fails without changing any candles if the trade has no token price.
+    pub fn push(&mut self, trade: &Trade) -> anyhow::Result<Vec<Candle>> {
+        let price = trade.token_price.ok_or_else(|| {
+            anyhow::anyhow!(
+                "Trade {} in market {} has no token price",
+                trade.seq,
+                trade.market
+            )
+        })?;
+
+        let mut closed = vec![];
+        for &granularity in self.granularities.iter() {
+            let start = granularity.candle_start(trade.timestamp);
+            match self.open.get_mut(&(trade.market, granularity)) {
+                Some(candle) if start <= candle.start => candle.update(trade, price),
+                Some(candle) => closed.push(std::mem::replace(
+                    candle,
+                    Candle::new(trade, price, granularity, start),
+                )),
+                None => {
+                    self.open.insert(
+                        (trade.market, granularity),
+                        Candle::new(trade, price, granularity, start),
+                    );
+                }
+            }
+        }
+
+        Ok(closed)
+    }
+
+    /// The candles currently being built, in no particular order.
+    pub fn open_candles(&self) -> impl Iterator<Item = &Candle> {
+        self.open.values()
+    }
+
+    /// The candle currently being built for `market` at `granularity`, if any.
+    pub fn open_candle(
+        &self,
+        market: &Address,
+        granularity: CandlestickGranularity,
+    ) -> Option<&Candle> {
+        self.open.get(&(*market, granularity))
+    }
+
+    /// Removes and returns all open candles, e.g. before shutting down.
+    pub fn flush(&mut self) -> Vec<Candle> {
+        self.open.drain().map(|(_, candle)| candle).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MARKET: Address = Address::new_from_array([1; 32]);
+
+    /// 2024-01-03T10:20:30Z, a Wednesday.
+    const WEDNESDAY: UnixTimestamp = 1_704_277_230;
+
+    /// A trade at `price` quote atoms per base atom between mints with the same decimals.
+    fn trade(timestamp: UnixTimestamp, price: i64, base_atoms: u64) -> Trade {
+        Trade {
+            market: MARKET,
+            seq: 0,
+            slot: 0,
+            timestamp,
+            is_buy: true,
+            base_atoms,
+            quote_atoms: base_atoms * price as u64,
+            price: Decimal::from(price),
+            token_price: None,
+        }
+        .with_decimals(6, 6)
+    }
+
+    #[test]
+    fn candle_starts() {
+        use CandlestickGranularity::*;
+
+        assert_eq!(S5.candle_start(WEDNESDAY), WEDNESDAY);
+        assert_eq!(M1.candle_start(WEDNESDAY), WEDNESDAY - 30);
+        assert_eq!(M15.candle_start(WEDNESDAY), WEDNESDAY - 5 * MINUTE - 30);
+        assert_eq!(
+            H4.candle_start(WEDNESDAY),
+            WEDNESDAY - 2 * HOUR - 20 * MINUTE - 30
+        );
+        // 2024-01-03T00:00:00Z
+        assert_eq!(D.candle_start(WEDNESDAY), 1_704_240_000);
+        // Monday, 2024-01-01T00:00:00Z
+        assert_eq!(W.candle_start(WEDNESDAY), 1_704_067_200);
+        assert_eq!(M.candle_start(WEDNESDAY), 1_704_067_200);
+        // 2024-02-29T23:59:59Z is still in February.
+        assert_eq!(M.candle_start(1_709_251_199), 1_706_745_600);
+        assert_eq!("H12".parse::<CandlestickGranularity>().unwrap(), H12);
+    }
+
+    #[test]
+    fn aggregate_candles() {
+        let mut aggregator =
+            CandleAggregator::new([CandlestickGranularity::M1, CandlestickGranularity::H1]);
+        let start = CandlestickGranularity::H1.candle_start(WEDNESDAY);
+
+        assert!(aggregator
+            .push(&trade(start + 1, 10, 1))
+            .unwrap()
+            .is_empty());
+        assert!(aggregator
+            .push(&trade(start + 2, 12, 2))
+            .unwrap()
+            .is_empty());
+        assert!(aggregator.push(&trade(start + 3, 9, 3)).unwrap().is_empty());
+
+        // Starts the next minute candle, closing the first one.
+        let closed = aggregator.push(&trade(start + MINUTE, 11, 4)).unwrap();
+        let [minute] = &closed[..] else {
+            panic!("Expected one closed candle: {closed:?}");
+        };
+        assert_eq!(minute.granularity, CandlestickGranularity::M1);
+        assert_eq!(minute.start, start);
+        assert_eq!(
+            (minute.open, minute.high, minute.low, minute.close),
+            (10.into(), 12.into(), 9.into(), 9.into())
+        );
+        assert_eq!((minute.base_volume, minute.quote_volume), (6, 61));
+        assert_eq!(minute.trades, 3);
+
+        let hour = aggregator
+            .open_candle(&MARKET, CandlestickGranularity::H1)
+            .unwrap();
+        assert_eq!((hour.open, hour.close), (10.into(), 11.into()));
+        assert_eq!(hour.trades, 4);
+
+        // Late trades are folded into the open candle.
+        assert!(aggregator.push(&trade(start, 8, 1)).unwrap().is_empty());
+        let minute = aggregator
+            .open_candle(&MARKET, CandlestickGranularity::M1)
+            .unwrap();
+        assert_eq!((minute.low, minute.trades), (8.into(), 2));
+
+        // Trades without a token price are rejected without touching the candles.
+        let mut atoms_only = trade(start + 4, 100, 1);
+        atoms_only.token_price = None;
+        assert!(aggregator.push(&atoms_only).is_err());
+        assert_eq!(
+            aggregator
+                .open_candle(&MARKET, CandlestickGranularity::M1)
+                .unwrap()
+                .trades,
+            2
+        );
+
+        assert_eq!(aggregator.flush().len(), 2);
+        assert_eq!(aggregator.open_candles().count(), 0);
+    }
+}
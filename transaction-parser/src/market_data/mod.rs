@@ -0,0 +1,4 @@
+//! Market data derived from parsed `dropset` events, i.e. the trade tape and OHLCV candles.
+
+pub mod candle;
+pub mod trade;
@@ -0,0 +1,184 @@
+//! See [`Trade`] and [`TradeTape`].
+
+use std::collections::VecDeque;
+
+use price::client_helpers::atoms_to_token_price;
+use rust_decimal::Decimal;
+use solana_address::Address;
+use solana_sdk::clock::UnixTimestamp;
+
+use crate::events::dropset_event::{
+    DropsetEvent,
+    SequencedEvent,
+};
+
+/// A single filled market order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trade {
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "crate::json::display",
+            deserialize_with = "crate::json::from_str"
+        )
+    )]
+    pub market: Address,
+    /// The sequence number of the market order event the trade was derived from.
+    pub seq: u64,
+    pub slot: u64,
+    /// The block time of the transaction the market order landed in.
+    pub timestamp: UnixTimestamp,
+    /// Whether the taker bought base, i.e. lifted asks, or sold base, i.e. hit bids.
+    pub is_buy: bool,
+    pub base_atoms: u64,
+    pub quote_atoms: u64,
+    /// The volume-weighted average fill price in quote atoms per base atom, the same units as a
+    /// decoded order price.
+    pub price: Decimal,
+    /// The same price in whole quote tokens per whole base token, or `None` if the mints' decimals
+    /// weren't provided. See [`Trade::with_decimals`].
+    pub token_price: Option<Decimal>,
+}
+
+impl Trade {
+    /// Derives a trade from a market order event. Returns `None` for any other event and for market
+    /// orders that didn't fill at all.
+    pub fn from_event(event: &SequencedEvent, slot: u64, timestamp: UnixTimestamp) -> Option<Self> {
+        let DropsetEvent::MarketOrder(market_order) = &event.event else {
+            return None;
+        };
+        if market_order.base_filled == 0 {
+            return None;
+        }
+
+        Some(Self {
+            market: event.market,
+            seq: event.seq,
+            slot,
+            timestamp,
+            is_buy: market_order.is_buy,
+            base_atoms: market_order.base_filled,
+            quote_atoms: market_order.quote_filled,
+            price: Self::atoms_price(market_order.base_filled, market_order.quote_filled),
+            token_price: None,
+        })
+    }
+
+    /// Sets the trade's token price from the market's base and quote mint decimals.
+    pub fn with_decimals(mut self, base_decimals: u8, quote_decimals: u8) -> Self {
+        self.token_price = Some(atoms_to_token_price(
+            self.price,
+            base_decimals,
+            quote_decimals,
+        ));
+        self
+    }
+
+    /// The average price of a fill in quote atoms per base atom. `base_atoms` must be nonzero.
+    pub fn atoms_price(base_atoms: u64, quote_atoms: u64) -> Decimal {
+        (Decimal::from(quote_atoms) / Decimal::from(base_atoms)).normalize()
+    }
+}
+
+/// A bounded, in-order record of the most recent trades.
+#[derive(Clone, Debug)]
+pub struct TradeTape {
+    trades: VecDeque<Trade>,
+    capacity: usize,
+}
+
+impl TradeTape {
+    /// Creates a tape that retains the last `capacity` trades.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            trades: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Appends a trade, evicting the oldest trade if the tape is full.
+    pub fn push(&mut self, trade: Trade) {
+        if self.trades.len() == self.capacity {
+            self.trades.pop_front();
+        }
+        self.trades.push_back(trade);
+    }
+
+    /// The retained trades, oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Trade> {
+        self.trades.iter()
+    }
+
+    /// The most recent trade, if any.
+    pub fn latest(&self) -> Option<&Trade> {
+        self.trades.back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.trades.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trades.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dropset_interface::events::{
+        DepositEventInstructionData,
+        MarketOrderEventInstructionData,
+    };
+
+    use super::*;
+
+    fn market_order(seq: u64, base_filled: u64, quote_filled: u64) -> SequencedEvent {
+        SequencedEvent {
+            market: Address::new_from_array([1; 32]),
+            seq,
+            event: DropsetEvent::MarketOrder(
+                MarketOrderEventInstructionData::new(
+                    base_filled,
+                    true,
+                    true,
+                    base_filled,
+                    quote_filled,
+                )
+                .into(),
+            ),
+        }
+    }
+
+    #[test]
+    fn trade_from_event() {
+        let trade = Trade::from_event(&market_order(7, 400, 1000), 3, 100).unwrap();
+        assert_eq!(trade.seq, 7);
+        assert_eq!((trade.slot, trade.timestamp), (3, 100));
+        assert!(trade.is_buy);
+        assert_eq!(trade.price, Decimal::new(25, 1));
+        assert_eq!(trade.token_price, None);
+        // 2.5 quote atoms per base atom is 2,500 quote tokens per base token if base has three
+        // more decimals.
+        let trade = trade.with_decimals(9, 6);
+        assert_eq!(trade.token_price, Some(Decimal::from(2_500)));
+
+        assert!(Trade::from_event(&market_order(8, 0, 0), 3, 100).is_none());
+        let deposit = SequencedEvent {
+            market: Address::new_from_array([1; 32]),
+            seq: 9,
+            event: DropsetEvent::Deposit(DepositEventInstructionData::new(1, true, 0).into()),
+        };
+        assert!(Trade::from_event(&deposit, 3, 100).is_none());
+    }
+
+    #[test]
+    fn tape_is_bounded() {
+        let mut tape = TradeTape::new(2);
+        for seq in 1..=3 {
+            tape.push(Trade::from_event(&market_order(seq, 1, 1), seq, 0).unwrap());
+        }
+        assert_eq!(tape.iter().map(|t| t.seq).collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(tape.latest().map(|t| t.seq), Some(3));
+    }
+}
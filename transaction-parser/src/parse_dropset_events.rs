@@ -4,8 +4,10 @@ use dropset_interface::instructions::DropsetInstruction;
 
 use crate::events::dropset_event::{
     unpack_instruction_events,
+    unpack_sequenced_events,
     DropsetEvent,
     EventError,
+    SequencedEvent,
 };
 
 const DROPSET_ID_BYTES: [u8; 32] = dropset::ID.to_bytes();
@@ -18,10 +20,25 @@ pub trait ParseDropsetEvents {
     fn instruction_data(&self) -> &[u8];
 
     fn parse_events(&self) -> Result<Vec<DropsetEvent>, EventError> {
-        let (tag_byte, instruction_event_data) = match self.instruction_data().split_at_checked(1) {
-            Some(v) => v,
-            None => return Ok(vec![]),
-        };
+        match self.flushed_event_data() {
+            Some(data) => unpack_instruction_events(data),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Parses the events the same way as [`ParseDropsetEvents::parse_events`], but tags each event
+    /// with its market and market-local sequence number.
+    fn parse_sequenced_events(&self) -> Result<Vec<SequencedEvent>, EventError> {
+        match self.flushed_event_data() {
+            Some(data) => unpack_sequenced_events(data),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Returns the event data after the instruction tag if this is a `dropset` `FlushEvents`
+    /// instruction.
+    fn flushed_event_data(&self) -> Option<&[u8]> {
+        let (tag_byte, instruction_event_data) = self.instruction_data().split_at_checked(1)?;
 
         let tag = tag_byte
             .first()
@@ -29,9 +46,9 @@ pub trait ParseDropsetEvents {
 
         match (self.program_id(), tag) {
             (&DROPSET_ID_BYTES, Some(DropsetInstruction::FlushEvents)) => {
-                unpack_instruction_events(instruction_event_data)
+                Some(instruction_event_data)
             }
-            _ => Ok(vec![]),
+            _ => None,
         }
     }
 }
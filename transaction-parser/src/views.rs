@@ -17,6 +17,7 @@ use itertools::Itertools;
 use solana_address::Address;
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MarketHeaderView {
     pub discriminant: u64,
     pub num_seats: u32,
@@ -30,15 +31,20 @@ pub struct MarketHeaderView {
     pub bids_dll_tail: SectorIndex,
     pub asks_dll_head: SectorIndex,
     pub asks_dll_tail: SectorIndex,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::display"))]
     pub base_mint: Address,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::display"))]
     pub quote_mint: Address,
     pub market_bump: u8,
     pub nonce: u64,
-    pub _padding: [u8; 3],
+    pub layout_version: u8,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _padding: [u8; 2],
 }
 
 /// A view on a market account's data with the collection of type T sectors.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MarketView<T> {
     pub header: MarketHeaderView,
     pub sectors: Vec<T>,
@@ -46,6 +52,7 @@ pub struct MarketView<T> {
 
 /// The various data associated with a single user for each market.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MarketUserData {
     pub seat: MarketSeatView,
     pub bids: Vec<OrderView>,
@@ -54,11 +61,13 @@ pub struct MarketUserData {
 
 /// A view on a market account's data showing all collections of all sector types.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MarketViewAll {
     pub header: MarketHeaderView,
     pub seats: Vec<MarketSeatView>,
     pub bids: Vec<OrderView>,
     pub asks: Vec<OrderView>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::address_map"))]
     pub users: HashMap<Address, MarketUserData>,
 }
 
@@ -93,21 +102,26 @@ pub fn try_market_view_all_from_owner_and_data(
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MarketSeatView {
     pub prev_index: SectorIndex,
     pub index: SectorIndex,
     pub next_index: SectorIndex,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::display"))]
     pub user: Address,
     pub base_available: u64,
     pub quote_available: u64,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::user_order_sectors"))]
     pub user_order_sectors: UserOrderSectors,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct OrderView {
     pub prev_index: SectorIndex,
     pub index: SectorIndex,
     pub next_index: SectorIndex,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::encoded_price"))]
     pub encoded_price: u32,
     pub user_seat: SectorIndex,
     pub base_remaining: u64,
@@ -165,7 +179,8 @@ impl From<&MarketHeader> for MarketHeaderView {
             quote_mint: header.quote_mint,
             market_bump: header.market_bump,
             nonce: header.num_events(),
-            _padding: [0; 3],
+            layout_version: header.layout_version(),
+            _padding: [0; 2],
         }
     }
 }